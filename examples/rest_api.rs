@@ -115,7 +115,7 @@ fn get_child_container(container: &mut Container) -> Result<Container, NfsError>
     let sub_containers = container.get_containers();
     let ref container_name = get_user_string("Container name");
     let info = sub_containers.iter().find(|info| *info.get_name() == *container_name);
-    let container_info = try!(info.ok_or(NfsError::DirectoryNotFound));
+    let container_info = try!(info.ok_or(NfsError::DirectoryNotFound(container_name.clone())));
     container.get_container(container_info, None)
 }
 
@@ -0,0 +1,315 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+
+use std::collections::{HashMap, HashSet};
+
+use directory_listing::DirectoryListing;
+use directory_listing::index::{Entry, EntryList};
+use file::File;
+use metadata::directory_metadata::DirectoryMetadata;
+use metadata::file_metadata::FileMetadata;
+use symlink::Symlink;
+
+/// Changes made to one kind of entry of a directory between two of its versions. Entries are
+/// matched by their ids, so a renamed entry is not reported as removed and added. An entry which
+/// was both renamed and modified is reported under each change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntriesDiff<T> {
+    added: Vec<T>,
+    removed: Vec<T>,
+    renamed: Vec<(T, T)>,
+    content_changed: Vec<(T, T)>,
+    metadata_changed: Vec<(T, T)>,
+}
+
+impl<T> EntriesDiff<T> {
+    /// Entries only found in the newer version
+    pub fn get_added(&self) -> &Vec<T> {
+        &self.added
+    }
+
+    /// Entries only found in the older version
+    pub fn get_removed(&self) -> &Vec<T> {
+        &self.removed
+    }
+
+    /// Entries whose name changed, as (older, newer)
+    pub fn get_renamed(&self) -> &Vec<(T, T)> {
+        &self.renamed
+    }
+
+    /// Entries whose content changed, as (older, newer). For files this means the data-map differs
+    /// and for symlinks the target. Sub directories are never reported here, changes to their
+    /// entries are found through `DirectoryDiff::get_sub_directory_diffs` instead.
+    pub fn get_content_changed(&self) -> &Vec<(T, T)> {
+        &self.content_changed
+    }
+
+    /// Entries whose metadata changed, apart from the name and what is derived from the content,
+    /// as (older, newer)
+    pub fn get_metadata_changed(&self) -> &Vec<(T, T)> {
+        &self.metadata_changed
+    }
+
+    /// Returns true if no entry changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.renamed.is_empty() &&
+        self.content_changed.is_empty() && self.metadata_changed.is_empty()
+    }
+}
+
+/// Differences between two versions of a DirectoryListing
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectoryDiff {
+    files: EntriesDiff<File>,
+    symlinks: EntriesDiff<Symlink>,
+    sub_directories: EntriesDiff<DirectoryMetadata>,
+    sub_directory_diffs: Vec<(String, DirectoryDiff)>,
+}
+
+impl DirectoryDiff {
+    /// Changes to the files of the directory
+    pub fn get_files(&self) -> &EntriesDiff<File> {
+        &self.files
+    }
+
+    /// Changes to the symlinks of the directory
+    pub fn get_symlinks(&self) -> &EntriesDiff<Symlink> {
+        &self.symlinks
+    }
+
+    /// Changes to the sub directories of the directory
+    pub fn get_sub_directories(&self) -> &EntriesDiff<DirectoryMetadata> {
+        &self.sub_directories
+    }
+
+    /// Differences within the sub directories found in both versions, along with the name of
+    /// each sub directory in the newer version. Only filled in by recursive diffs and only for
+    /// sub directories which changed.
+    pub fn get_sub_directory_diffs(&self) -> &Vec<(String, DirectoryDiff)> {
+        &self.sub_directory_diffs
+    }
+
+    /// Returns true if nothing changed between the two versions
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.symlinks.is_empty() && self.sub_directories.is_empty() &&
+        self.sub_directory_diffs.is_empty()
+    }
+}
+
+/// Compares two versions of a DirectoryListing. Sub directories are not descended into.
+pub fn diff(from: &DirectoryListing, to: &DirectoryListing) -> DirectoryDiff {
+    DirectoryDiff {
+        files: diff_entries(from.get_files(), to.get_files()),
+        symlinks: diff_entries(from.get_symlinks(), to.get_symlinks()),
+        sub_directories: diff_entries(from.get_sub_directories(), to.get_sub_directories()),
+        sub_directory_diffs: Vec::new(),
+    }
+}
+
+/// Compares two versions of a DirectoryListing. `diff_sub_directory` is called with the older
+/// and the newer metadata of every sub directory found in both versions and may return the
+/// differences within it.
+pub fn diff_recursively<F, E>(from: &DirectoryListing,
+                              to: &DirectoryListing,
+                              mut diff_sub_directory: F)
+                              -> Result<DirectoryDiff, E>
+    where F: FnMut(&DirectoryMetadata, &DirectoryMetadata) -> Result<Option<DirectoryDiff>, E>
+{
+    let mut directory_diff = diff(from, to);
+    for newer in to.get_sub_directories() {
+        if let Some(older) = from.find_sub_directory_by_id(newer.get_id()) {
+            if let Some(sub_directory_diff) = try!(diff_sub_directory(older, newer)) {
+                if !sub_directory_diff.is_empty() {
+                    directory_diff.sub_directory_diffs
+                                  .push((newer.get_name().clone(), sub_directory_diff));
+                }
+            }
+        }
+    }
+    Ok(directory_diff)
+}
+
+/// Entries of a DirectoryListing which can be compared across versions
+trait DiffEntry: Entry + Clone {
+    fn content_differs(&self, other: &Self) -> bool;
+    fn metadata_differs(&self, other: &Self) -> bool;
+}
+
+impl DiffEntry for File {
+    fn content_differs(&self, other: &File) -> bool {
+        self.get_datamap() != other.get_datamap()
+    }
+
+    fn metadata_differs(&self, other: &File) -> bool {
+        content_independent_metadata(self.get_metadata()) !=
+        content_independent_metadata(other.get_metadata())
+    }
+}
+
+impl DiffEntry for Symlink {
+    fn content_differs(&self, other: &Symlink) -> bool {
+        self.get_target() != other.get_target()
+    }
+
+    fn metadata_differs(&self, other: &Symlink) -> bool {
+        let mut metadata = content_independent_metadata(self.get_metadata());
+        let mut other_metadata = content_independent_metadata(other.get_metadata());
+        metadata.set_symlink_target(None);
+        other_metadata.set_symlink_target(None);
+        metadata != other_metadata
+    }
+}
+
+impl DiffEntry for DirectoryMetadata {
+    fn content_differs(&self, _: &DirectoryMetadata) -> bool {
+        false
+    }
+
+    fn metadata_differs(&self, other: &DirectoryMetadata) -> bool {
+        let mut metadata = self.clone();
+        metadata.set_name(other.get_name().clone());
+        metadata != *other
+    }
+}
+
+/// Metadata of a file without the name and the fields which change along with the content
+fn content_independent_metadata(metadata: &FileMetadata) -> FileMetadata {
+    let mut metadata = metadata.clone();
+    metadata.set_name(String::new());
+    metadata.set_size(0);
+    metadata.set_modified_time(::time::empty_tm());
+    metadata.set_content_hash(None);
+    metadata.set_mime_type(None);
+    metadata
+}
+
+fn diff_entries<T: DiffEntry>(from: &EntryList<T>, to: &EntryList<T>) -> EntriesDiff<T> {
+    let mut entries_diff = EntriesDiff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        renamed: Vec::new(),
+        content_changed: Vec::new(),
+        metadata_changed: Vec::new(),
+    };
+    let older_entries = from.iter()
+                            .map(|entry| (entry.get_entry_id(), entry))
+                            .collect::<HashMap<_, _>>();
+    let newer_ids = to.iter().map(|entry| entry.get_entry_id()).collect::<HashSet<_>>();
+    for newer in to {
+        match older_entries.get(newer.get_entry_id()) {
+            Some(older) => {
+                if older.get_entry_name() != newer.get_entry_name() {
+                    entries_diff.renamed.push(((*older).clone(), newer.clone()));
+                }
+                if older.content_differs(newer) {
+                    entries_diff.content_changed.push(((*older).clone(), newer.clone()));
+                }
+                if older.metadata_differs(newer) {
+                    entries_diff.metadata_changed.push(((*older).clone(), newer.clone()));
+                }
+            }
+            None => entries_diff.added.push(newer.clone()),
+        }
+    }
+    for older in from {
+        if !newer_ids.contains(older.get_entry_id()) {
+            entries_diff.removed.push(older.clone());
+        }
+    }
+    entries_diff
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use directory_listing::DirectoryListing;
+    use file::File;
+    use metadata::file_metadata::FileMetadata;
+    use self_encryption::DataMap;
+    use symlink::Symlink;
+
+    fn new_file(name: &str) -> File {
+        unwrap_result!(File::new(FileMetadata::new(name.to_string(), Vec::new()),
+                                 DataMap::None))
+    }
+
+    fn new_sub_directory(name: &str) -> DirectoryListing {
+        unwrap_result!(DirectoryListing::new(name.to_string(),
+                                             10,
+                                             Vec::new(),
+                                             true,
+                                             ::AccessLevel::Private,
+                                             None))
+    }
+
+    #[test]
+    fn diff_versions() {
+        let mut from = new_sub_directory("Home");
+        let kept = new_file("kept.txt");
+        let renamed = new_file("rename_me.txt");
+        let removed = new_file("remove_me.txt");
+        let rewritten = new_file("rewrite_me.txt");
+        let symlink = unwrap_result!(Symlink::new("link".to_string(), "kept.txt".to_string()));
+        let child = new_sub_directory("Child");
+        from.upsert_file(kept.clone());
+        from.upsert_file(renamed.clone());
+        from.upsert_file(removed.clone());
+        from.upsert_file(rewritten.clone());
+        from.upsert_symlink(symlink.clone());
+        from.upsert_sub_directory(child.get_metadata().clone());
+
+        let mut to = from.clone();
+        let added = new_file("added.txt");
+        to.upsert_file(added.clone());
+        unwrap_result!(to.remove_file(removed.get_name()));
+        let mut renamed_to = renamed.clone();
+        renamed_to.get_mut_metadata().set_name("renamed.txt".to_string());
+        renamed_to.get_mut_metadata().set_user_metadata(vec![1u8; 5]);
+        to.upsert_file(renamed_to.clone());
+        let mut rewritten_to = rewritten.clone();
+        rewritten_to.set_datamap(DataMap::Content(vec![1u8; 10]));
+        rewritten_to.get_mut_metadata().set_size(10);
+        to.upsert_file(rewritten_to.clone());
+        let mut symlink_to = symlink.clone();
+        unwrap_result!(symlink_to.set_target("added.txt".to_string()));
+        to.upsert_symlink(symlink_to.clone());
+        let mut child_to = child.get_metadata().clone();
+        child_to.set_name("Renamed".to_string());
+        to.upsert_sub_directory(child_to.clone());
+
+        let directory_diff = diff(&from, &to);
+        let files = directory_diff.get_files();
+        assert_eq!(*files.get_added(), vec![added]);
+        assert_eq!(*files.get_removed(), vec![removed]);
+        assert_eq!(*files.get_renamed(), vec![(renamed.clone(), renamed_to.clone())]);
+        assert_eq!(*files.get_content_changed(), vec![(rewritten, rewritten_to)]);
+        assert_eq!(*files.get_metadata_changed(), vec![(renamed, renamed_to)]);
+        let symlinks = directory_diff.get_symlinks();
+        assert_eq!(*symlinks.get_content_changed(), vec![(symlink, symlink_to)]);
+        assert!(symlinks.get_renamed().is_empty());
+        assert!(symlinks.get_metadata_changed().is_empty());
+        let sub_directories = directory_diff.get_sub_directories();
+        assert_eq!(*sub_directories.get_renamed(),
+                   vec![(child.get_metadata().clone(), child_to)]);
+        assert!(sub_directories.get_metadata_changed().is_empty());
+        assert!(directory_diff.get_sub_directory_diffs().is_empty());
+
+        assert!(diff(&from, &from).is_empty());
+    }
+}
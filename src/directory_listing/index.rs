@@ -0,0 +1,329 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::slice;
+
+use rustc_serialize::{Encodable, Encoder};
+
+use file::File;
+use metadata::directory_metadata::DirectoryMetadata;
+use symlink::Symlink;
+use xor_name::XorName;
+
+/// Entries of a DirectoryListing, identified by their name and id
+pub trait Entry {
+    /// Returns the name of the entry
+    fn get_entry_name(&self) -> &String;
+    /// Returns the id of the entry
+    fn get_entry_id(&self) -> &XorName;
+}
+
+impl Entry for File {
+    fn get_entry_name(&self) -> &String {
+        self.get_name()
+    }
+
+    fn get_entry_id(&self) -> &XorName {
+        self.get_id()
+    }
+}
+
+impl Entry for DirectoryMetadata {
+    fn get_entry_name(&self) -> &String {
+        self.get_name()
+    }
+
+    fn get_entry_id(&self) -> &XorName {
+        self.get_id()
+    }
+}
+
+impl Entry for Symlink {
+    fn get_entry_name(&self) -> &String {
+        self.get_name()
+    }
+
+    fn get_entry_id(&self) -> &XorName {
+        self.get_id()
+    }
+}
+
+/// Entries kept in insertion order and indexed in memory by name and id.
+/// Every entry has a slot which does not move when other entries are removed: a removal only
+/// empties the slot, and the slots are compacted once at least half of them are empty. Lookups,
+/// upserts and removals therefore never shift the remaining entries or their positions in the
+/// index. Iterating mutably may rename entries, so lookups fall back to a linear search from
+/// then on until the next upsert or removal rebuilds the index.
+#[derive(Clone)]
+pub struct EntryList<T> {
+    slots: Vec<Option<T>>,
+    len: usize,
+    names: HashMap<String, usize>,
+    ids: HashMap<XorName, usize>,
+    stale: bool,
+}
+
+impl<T: Entry> EntryList<T> {
+    /// Creates an empty list
+    pub fn new() -> EntryList<T> {
+        EntryList {
+            slots: Vec::new(),
+            len: 0,
+            names: HashMap::new(),
+            ids: HashMap::new(),
+            stale: false,
+        }
+    }
+
+    /// Number of entries in the list
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the list holds no entries
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterates over the entries in insertion order
+    pub fn iter(&self) -> Iter<T> {
+        Iter {
+            slots: self.slots.iter(),
+            remaining: self.len,
+        }
+    }
+
+    /// Iterates mutably over the entries in insertion order
+    pub fn iter_mut(&mut self) -> IterMut<T> {
+        self.stale = true;
+        IterMut {
+            slots: self.slots.iter_mut(),
+            remaining: self.len,
+        }
+    }
+
+    /// Appends an entry. For duplicated names or ids the first entry wins, matching a linear
+    /// search.
+    pub fn push(&mut self, entry: T) {
+        let slot = self.slots.len();
+        if !self.stale {
+            let _ = self.names.entry(entry.get_entry_name().clone()).or_insert(slot);
+            let _ = self.ids.entry(entry.get_entry_id().clone()).or_insert(slot);
+        }
+        self.slots.push(Some(entry));
+        self.len += 1;
+    }
+
+    /// Returns the entry with the given name
+    pub fn find_by_name(&self, name: &String) -> Option<&T> {
+        if self.stale {
+            self.iter().find(|entry| *entry.get_entry_name() == *name)
+        } else {
+            self.names.get(name).and_then(|&slot| self.slots[slot].as_ref())
+        }
+    }
+
+    /// Returns the entry with the given id
+    pub fn find_by_id(&self, id: &XorName) -> Option<&T> {
+        if self.stale {
+            self.iter().find(|entry| *entry.get_entry_id() == *id)
+        } else {
+            self.ids.get(id).and_then(|&slot| self.slots[slot].as_ref())
+        }
+    }
+
+    /// Replaces the entry with the same id in place, or appends the entry if there is none
+    pub fn upsert(&mut self, entry: T) {
+        self.refresh();
+        match self.ids.get(entry.get_entry_id()).cloned() {
+            Some(slot) => {
+                if let Some(ref replaced) = self.slots[slot] {
+                    if self.names.get(replaced.get_entry_name()) == Some(&slot) {
+                        let _ = self.names.remove(replaced.get_entry_name());
+                    }
+                }
+                let _ = self.names.insert(entry.get_entry_name().clone(), slot);
+                self.slots[slot] = Some(entry);
+            }
+            None => {
+                let slot = self.slots.len();
+                let _ = self.names.insert(entry.get_entry_name().clone(), slot);
+                let _ = self.ids.insert(entry.get_entry_id().clone(), slot);
+                self.slots.push(Some(entry));
+                self.len += 1;
+            }
+        }
+    }
+
+    /// Removes the entry with the given name, keeping the order of the others
+    pub fn remove_by_name(&mut self, name: &String) -> Option<T> {
+        self.refresh();
+        let slot = match self.names.remove(name) {
+            Some(slot) => slot,
+            None => return None,
+        };
+        let removed = self.slots[slot].take();
+        if let Some(ref removed) = removed {
+            if self.ids.get(removed.get_entry_id()) == Some(&slot) {
+                let _ = self.ids.remove(removed.get_entry_id());
+            }
+            self.len -= 1;
+        }
+        if self.slots.len() - self.len > self.len {
+            self.compact();
+        }
+        removed
+    }
+
+    /// Rebuilds the index if it was invalidated by iterating mutably
+    fn refresh(&mut self) {
+        if self.stale {
+            self.compact();
+        }
+    }
+
+    /// Drops the empty slots and rebuilds the index
+    fn compact(&mut self) {
+        let entries = self.slots.drain(..).filter_map(|slot| slot).collect::<Vec<_>>();
+        *self = EntryList::from(entries);
+    }
+}
+
+impl<T: Entry> From<Vec<T>> for EntryList<T> {
+    fn from(entries: Vec<T>) -> EntryList<T> {
+        let mut list = EntryList::new();
+        list.slots.reserve(entries.len());
+        for entry in entries {
+            list.push(entry);
+        }
+        list
+    }
+}
+
+impl<'a, T: Entry> IntoIterator for &'a EntryList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: PartialEq + Entry> PartialEq for EntryList<T> {
+    fn eq(&self, other: &EntryList<T>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq + Entry> Eq for EntryList<T> {}
+
+impl<T: PartialOrd + Entry> PartialOrd for EntryList<T> {
+    fn partial_cmp(&self, other: &EntryList<T>) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Ord + Entry> Ord for EntryList<T> {
+    fn cmp(&self, other: &EntryList<T>) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<T: fmt::Debug + Entry> fmt::Debug for EntryList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Encoded as the sequence of its entries, like a Vec
+impl<T: Encodable + Entry> Encodable for EntryList<T> {
+    fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
+        e.emit_seq(self.len(), |e| {
+            for (index, entry) in self.iter().enumerate() {
+                try!(e.emit_seq_elt(index, |e| entry.encode(e)));
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Iterator over the entries of an EntryList
+pub struct Iter<'a, T: 'a> {
+    slots: slice::Iter<'a, Option<T>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        while let Some(slot) = self.slots.next() {
+            if let Some(ref entry) = *slot {
+                self.remaining -= 1;
+                return Some(entry);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        while let Some(slot) = self.slots.next_back() {
+            if let Some(ref entry) = *slot {
+                self.remaining -= 1;
+                return Some(entry);
+            }
+        }
+        None
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+/// Iterator over the entries of an EntryList, handing them out mutably
+pub struct IterMut<'a, T: 'a> {
+    slots: slice::IterMut<'a, Option<T>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        while let Some(slot) = self.slots.next() {
+            if let Some(entry) = slot.as_mut() {
+                self.remaining -= 1;
+                return Some(entry);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
@@ -0,0 +1,355 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+
+use directory_listing::DirectoryListing;
+use directory_listing::index::{Entry, EntryList};
+use file::File;
+use metadata::directory_metadata::DirectoryMetadata;
+use symlink::Symlink;
+use xor_name::XorName;
+
+/// A change made on both sides of a merge which could not be reconciled automatically
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeConflict {
+    /// The file with this id was changed differently on both sides. `None` means it was removed.
+    File {
+        /// Id of the file
+        id: XorName,
+        /// The file as found in our listing
+        ours: Option<File>,
+        /// The file as found in their listing
+        theirs: Option<File>,
+    },
+    /// The symlink with this id was changed differently on both sides. `None` means it was
+    /// removed.
+    Symlink {
+        /// Id of the symlink
+        id: XorName,
+        /// The symlink as found in our listing
+        ours: Option<Symlink>,
+        /// The symlink as found in their listing
+        theirs: Option<Symlink>,
+    },
+    /// The sub directory with this id was changed differently on both sides. `None` means it was
+    /// removed.
+    SubDirectory {
+        /// Id of the sub directory
+        id: XorName,
+        /// The sub directory as found in our listing
+        ours: Option<DirectoryMetadata>,
+        /// The sub directory as found in their listing
+        theirs: Option<DirectoryMetadata>,
+    },
+    /// The name, user metadata, extended attributes or retention policy of the directory itself
+    /// were changed differently on both sides
+    DirectoryMetadata {
+        /// Metadata as found in our listing
+        ours: DirectoryMetadata,
+        /// Metadata as found in their listing
+        theirs: DirectoryMetadata,
+    },
+    /// Different files, symlinks or sub directories ended up with the same name after the merge
+    NameCollision(String),
+}
+
+/// Three-way merge of two DirectoryListings which were both derived from `base`.
+/// Files, symlinks and sub directories are matched by their ids, so additions, removals, renames
+/// and modifications made on only one side are all carried over. An entry changed on both sides
+/// is a conflict unless both sides made the same change.
+/// Returns the merged DirectoryListing or the list of conflicts.
+pub fn merge(base: &DirectoryListing,
+             ours: &DirectoryListing,
+             theirs: &DirectoryListing)
+             -> Result<DirectoryListing, Vec<MergeConflict>> {
+    let mut conflicts = Vec::new();
+
+    let (files, file_conflicts) = merge_entries(base.get_files(),
+                                                ours.get_files(),
+                                                theirs.get_files(),
+                                                File::get_id,
+                                                |_, _| None);
+    for (id, ours, theirs) in file_conflicts {
+        conflicts.push(MergeConflict::File {
+            id: id,
+            ours: ours,
+            theirs: theirs,
+        });
+    }
+
+    let (symlinks, symlink_conflicts) = merge_entries(base.get_symlinks(),
+                                                      ours.get_symlinks(),
+                                                      theirs.get_symlinks(),
+                                                      Symlink::get_id,
+                                                      |_, _| None);
+    for (id, ours, theirs) in symlink_conflicts {
+        conflicts.push(MergeConflict::Symlink {
+            id: id,
+            ours: ours,
+            theirs: theirs,
+        });
+    }
+
+    let (sub_directories, sub_directory_conflicts) =
+        merge_entries(base.get_sub_directories(),
+                      ours.get_sub_directories(),
+                      theirs.get_sub_directories(),
+                      DirectoryMetadata::get_id,
+                      reconcile_sub_directories);
+    for (id, ours, theirs) in sub_directory_conflicts {
+        conflicts.push(MergeConflict::SubDirectory {
+            id: id,
+            ours: ours,
+            theirs: theirs,
+        });
+    }
+
+    let mut merged = ours.clone();
+    {
+        let base_metadata = base.get_metadata();
+        let their_metadata = theirs.get_metadata();
+        let merged_metadata = merged.get_mut_metadata();
+        let ours_changed = merged_metadata.get_name() != base_metadata.get_name() ||
+                           merged_metadata.get_user_metadata() !=
+                           base_metadata.get_user_metadata() ||
+                           merged_metadata.get_xattrs() != base_metadata.get_xattrs() ||
+                           merged_metadata.get_retention_policy() !=
+                           base_metadata.get_retention_policy();
+        let theirs_changed = their_metadata.get_name() != base_metadata.get_name() ||
+                             their_metadata.get_user_metadata() !=
+                             base_metadata.get_user_metadata() ||
+                             their_metadata.get_xattrs() != base_metadata.get_xattrs() ||
+                             their_metadata.get_retention_policy() !=
+                             base_metadata.get_retention_policy();
+        if theirs_changed && !ours_changed {
+            merged_metadata.set_name(their_metadata.get_name().clone());
+            merged_metadata.set_user_metadata(their_metadata.get_user_metadata().clone());
+            *merged_metadata.get_mut_xattrs() = their_metadata.get_xattrs().clone();
+            merged_metadata.set_retention_policy(their_metadata.get_retention_policy().cloned());
+        } else if theirs_changed && ours_changed &&
+           (merged_metadata.get_name() != their_metadata.get_name() ||
+            merged_metadata.get_user_metadata() != their_metadata.get_user_metadata() ||
+            merged_metadata.get_xattrs() != their_metadata.get_xattrs() ||
+            merged_metadata.get_retention_policy() != their_metadata.get_retention_policy()) {
+            conflicts.push(MergeConflict::DirectoryMetadata {
+                ours: merged_metadata.clone(),
+                theirs: their_metadata.clone(),
+            });
+        }
+        let modified_time = cmp::max(merged_metadata.get_modified_time().clone(),
+                                     their_metadata.get_modified_time().clone());
+        merged_metadata.set_modified_time(modified_time);
+    }
+
+    let mut names = HashSet::new();
+    for name in files.iter()
+                     .map(|file| file.get_name())
+                     .chain(symlinks.iter().map(|symlink| symlink.get_name())) {
+        if !names.insert(name.clone()) {
+            conflicts.push(MergeConflict::NameCollision(name.clone()));
+        }
+    }
+    names.clear();
+    for name in sub_directories.iter().map(|metadata| metadata.get_name()) {
+        if !names.insert(name.clone()) {
+            conflicts.push(MergeConflict::NameCollision(name.clone()));
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(conflicts);
+    }
+    *merged.get_mut_files() = EntryList::from(files);
+    merged.symlinks = EntryList::from(symlinks);
+    *merged.get_mut_sub_directories() = EntryList::from(sub_directories);
+    Ok(merged)
+}
+
+/// Merges entries matched by id. `reconcile` is consulted when both sides changed an entry and
+/// may return the entry to keep.
+/// Returns (merged entries, Vec<(id, ours, theirs)> of the conflicting entries)
+fn merge_entries<'a, T, G, R>(base: &'a EntryList<T>,
+                              ours: &'a EntryList<T>,
+                              theirs: &'a EntryList<T>,
+                              get_id: G,
+                              reconcile: R)
+                              -> (Vec<T>, Vec<(XorName, Option<T>, Option<T>)>)
+    where T: Entry + Clone + PartialEq,
+          G: Fn(&T) -> &XorName,
+          R: Fn(&T, &T) -> Option<T>
+{
+    let index = |entries: &'a EntryList<T>| -> HashMap<&'a XorName, &'a T> {
+        entries.iter().map(|entry| (get_id(entry), entry)).collect()
+    };
+    let base_entries = index(base);
+    let our_entries = index(ours);
+    let their_entries = index(theirs);
+
+    let mut ids = Vec::new();
+    let mut seen = HashSet::new();
+    for entry in ours.iter().chain(theirs.iter()).chain(base.iter()) {
+        if seen.insert(get_id(entry)) {
+            ids.push(get_id(entry));
+        }
+    }
+
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+    for id in ids {
+        let base_entry = base_entries.get(id).cloned();
+        let our_entry = our_entries.get(id).cloned();
+        let their_entry = their_entries.get(id).cloned();
+        let resolved = if our_entry == their_entry || their_entry == base_entry {
+            our_entry.cloned()
+        } else if our_entry == base_entry {
+            their_entry.cloned()
+        } else {
+            let reconciled = match (our_entry, their_entry) {
+                (Some(our_entry), Some(their_entry)) => reconcile(our_entry, their_entry),
+                _ => None,
+            };
+            match reconciled {
+                Some(entry) => Some(entry),
+                None => {
+                    conflicts.push((id.clone(), our_entry.cloned(), their_entry.cloned()));
+                    continue;
+                }
+            }
+        };
+        if let Some(entry) = resolved {
+            merged.push(entry);
+        }
+    }
+    (merged, conflicts)
+}
+
+/// Parent listings only cache the metadata of their sub directories, so entries which differ in
+/// nothing but the modified time are the same directory updated on both sides.
+fn reconcile_sub_directories(ours: &DirectoryMetadata,
+                             theirs: &DirectoryMetadata)
+                             -> Option<DirectoryMetadata> {
+    let mut ours_with_their_time = ours.clone();
+    ours_with_their_time.set_modified_time(theirs.get_modified_time().clone());
+    if ours_with_their_time != *theirs {
+        None
+    } else if ours.get_modified_time() >= theirs.get_modified_time() {
+        Some(ours.clone())
+    } else {
+        Some(theirs.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use directory_listing::DirectoryListing;
+    use file::File;
+    use metadata::file_metadata::FileMetadata;
+    use self_encryption::DataMap;
+
+    fn new_file(name: &str) -> File {
+        unwrap_result!(File::new(FileMetadata::new(name.to_string(), Vec::new()),
+                                 DataMap::None))
+    }
+
+    fn new_sub_directory(name: &str) -> DirectoryListing {
+        unwrap_result!(DirectoryListing::new(name.to_string(),
+                                             10,
+                                             Vec::new(),
+                                             true,
+                                             ::AccessLevel::Private,
+                                             None))
+    }
+
+    #[test]
+    fn merge_independent_changes() {
+        let mut base = new_sub_directory("Home");
+        let kept = new_file("kept.txt");
+        let renamed = new_file("rename_me.txt");
+        let removed = new_file("remove_me.txt");
+        let child = new_sub_directory("Child");
+        base.upsert_file(kept.clone());
+        base.upsert_file(renamed.clone());
+        base.upsert_file(removed.clone());
+        base.upsert_sub_directory(child.get_metadata().clone());
+
+        let mut ours = base.clone();
+        let added_by_us = new_file("ours.txt");
+        ours.upsert_file(added_by_us.clone());
+        let mut renamed_by_us = renamed.clone();
+        renamed_by_us.get_mut_metadata().set_name("renamed.txt".to_string());
+        ours.upsert_file(renamed_by_us.clone());
+        let mut child_updated_by_us = child.get_metadata().clone();
+        child_updated_by_us.set_modified_time(::time::now_utc());
+        ours.upsert_sub_directory(child_updated_by_us);
+
+        let mut theirs = base.clone();
+        let added_by_them = new_file("theirs.txt");
+        theirs.upsert_file(added_by_them.clone());
+        unwrap_result!(theirs.remove_file(removed.get_name()));
+        let mut child_updated_by_them = child.get_metadata().clone();
+        child_updated_by_them.set_modified_time(::time::now_utc());
+        theirs.upsert_sub_directory(child_updated_by_them);
+        theirs.get_mut_metadata().set_user_metadata(vec![1u8; 5]);
+
+        let merged = unwrap_result!(merge(&base, &ours, &theirs));
+        assert_eq!(merged.get_files().len(), 4);
+        assert!(merged.find_file(kept.get_name()).is_some());
+        assert!(merged.find_file(added_by_us.get_name()).is_some());
+        assert!(merged.find_file(added_by_them.get_name()).is_some());
+        assert!(merged.find_file(&"renamed.txt".to_string()).is_some());
+        assert!(merged.find_file(removed.get_name()).is_none());
+        assert_eq!(merged.get_sub_directories().len(), 1);
+        assert_eq!(*merged.get_metadata().get_user_metadata(), vec![1u8; 5]);
+    }
+
+    #[test]
+    fn merge_conflicting_changes() {
+        let mut base = new_sub_directory("Home");
+        let file = new_file("file.txt");
+        base.upsert_file(file.clone());
+
+        let mut ours = base.clone();
+        let mut our_file = file.clone();
+        our_file.get_mut_metadata().set_user_metadata(vec![1u8]);
+        ours.upsert_file(our_file.clone());
+        ours.upsert_file(new_file("new.txt"));
+
+        let mut theirs = base.clone();
+        let mut their_file = file.clone();
+        their_file.get_mut_metadata().set_user_metadata(vec![2u8]);
+        theirs.upsert_file(their_file.clone());
+        theirs.upsert_file(new_file("new.txt"));
+
+        let conflicts = match merge(&base, &ours, &theirs) {
+            Ok(_) => panic!("Expected conflicts"),
+            Err(conflicts) => conflicts,
+        };
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts.contains(&MergeConflict::File {
+            id: file.get_id().clone(),
+            ours: Some(our_file),
+            theirs: Some(their_file),
+        }));
+        assert!(conflicts.contains(&MergeConflict::NameCollision("new.txt".to_string())));
+
+        // Identical changes on both sides are not conflicts
+        assert!(merge(&base, &ours, &ours).is_ok());
+    }
+}
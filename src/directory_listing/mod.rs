@@ -15,9 +15,11 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
-use std::cmp;
+use std::cmp::{self, Ordering};
+use std::fmt;
 use std::sync::{Arc, Mutex};
 
+use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
 use sodiumoxide::crypto::box_;
 
 use errors::NfsError;
@@ -26,16 +28,33 @@ use xor_name::XorName;
 use maidsafe_utilities::serialisation::{serialise, deserialise};
 use metadata::directory_key::DirectoryKey;
 use metadata::directory_metadata::DirectoryMetadata;
+use metadata::xattr::ExtendedAttributes;
 use safe_core::client::Client;
 use safe_core::SelfEncryptionStorage;
 use self_encryption::{DataMap, SelfEncryptor};
+use symlink::Symlink;
+
+/// Differences between two versions of a DirectoryListing
+pub mod diff;
+/// Three-way merge of concurrently edited DirectoryListings
+pub mod merge;
+/// Layout of directories whose files are split across multiple StructuredData
+pub mod shard;
+/// Ordered collections of the entries of a DirectoryListing, indexed by name and id
+pub mod index;
+
+use self::index::EntryList;
 
 /// DirectoryListing is the representation of a deserialised Directory in the network
-#[derive(Debug, RustcEncodable, RustcDecodable, PartialEq, Eq, PartialOrd, Ord, Clone)]
+/// Files, symlinks and sub directories are indexed in memory by name and id, so that lookups,
+/// upserts and removals do not scan the whole listing. Entries keep their insertion order.
+/// Symlinks are serialised among the files and separated from them again on decoding.
+#[derive(Clone)]
 pub struct DirectoryListing {
     metadata: DirectoryMetadata,
-    sub_directories: Vec<DirectoryMetadata>,
-    files: Vec<File>,
+    sub_directories: EntryList<DirectoryMetadata>,
+    files: EntryList<File>,
+    symlinks: EntryList<Symlink>,
 }
 
 impl DirectoryListing {
@@ -53,11 +72,34 @@ impl DirectoryListing {
                                                     access_level,
                                                     user_metadata,
                                                     parent_dir_key));
-        Ok(DirectoryListing {
-            metadata: meta_data,
-            sub_directories: Vec::new(),
-            files: Vec::new(),
-        })
+        Ok(DirectoryListing::from_parts(meta_data, Vec::new(), Vec::new()))
+    }
+
+    /// `stored_files` may hold the Files symlinks are stored as
+    fn from_parts(metadata: DirectoryMetadata,
+                  sub_directories: Vec<DirectoryMetadata>,
+                  stored_files: Vec<File>)
+                  -> DirectoryListing {
+        let mut files = Vec::with_capacity(stored_files.len());
+        let mut symlinks = Vec::new();
+        for file in stored_files {
+            if file.get_metadata().get_symlink_target().is_some() {
+                symlinks.extend(Symlink::from_file(file));
+            } else {
+                files.push(file);
+            }
+        }
+        DirectoryListing {
+            metadata: metadata,
+            sub_directories: EntryList::from(sub_directories),
+            files: EntryList::from(files),
+            symlinks: EntryList::from(symlinks),
+        }
+    }
+
+    /// Returns the files followed by the Files the symlinks are stored as
+    fn get_stored_files<'a>(&'a self) -> Box<Iterator<Item = &'a File> + 'a> {
+        Box::new(self.files.iter().chain(self.symlinks.iter().map(Symlink::as_file)))
     }
 
     /// Returns the DirectoryKey representing the DirectoryListing
@@ -75,23 +117,38 @@ impl DirectoryListing {
         &mut self.metadata
     }
 
+    /// Get the extended attributes of the directory
+    pub fn get_xattrs(&self) -> &ExtendedAttributes {
+        self.metadata.get_xattrs()
+    }
+
+    /// Get the extended attributes of the directory in mutable format so that they can be updated
+    pub fn get_mut_xattrs(&mut self) -> &mut ExtendedAttributes {
+        self.metadata.get_mut_xattrs()
+    }
+
     /// Get all files in this DirectoryListing
-    pub fn get_files(&self) -> &Vec<File> {
+    pub fn get_files(&self) -> &EntryList<File> {
         &self.files
     }
 
     /// Get all files in this DirectoryListing with mutability to update the listing of files
-    pub fn get_mut_files(&mut self) -> &mut Vec<File> {
+    /// Once the files are iterated mutably, lookups fall back to a linear search until the next
+    /// upsert or removal of a file
+    pub fn get_mut_files(&mut self) -> &mut EntryList<File> {
         &mut self.files
     }
 
     /// Get all subdirectories in this DirectoryListing
-    pub fn get_sub_directories(&self) -> &Vec<DirectoryMetadata> {
+    pub fn get_sub_directories(&self) -> &EntryList<DirectoryMetadata> {
         &self.sub_directories
     }
 
-    /// Get all subdirectories in this DirectoryListing with mutability to update the listing of subdirectories
-    pub fn get_mut_sub_directories(&mut self) -> &mut Vec<DirectoryMetadata> {
+    /// Get all subdirectories in this DirectoryListing with mutability to update the listing of
+    /// subdirectories
+    /// Once the subdirectories are iterated mutably, lookups fall back to a linear search until
+    /// the next upsert or removal of a subdirectory
+    pub fn get_mut_sub_directories(&mut self) -> &mut EntryList<DirectoryMetadata> {
         &mut self.sub_directories
     }
 
@@ -127,44 +184,42 @@ impl DirectoryListing {
     /// Get DirectoryInfo of sub_directory within a DirectoryListing.
     /// Returns the Option<DirectoryInfo> for the directory_name from the DirectoryListing
     pub fn find_file(&self, file_name: &String) -> Option<&File> {
-        self.get_files().iter().find(|file| *file.get_name() == *file_name)
+        self.files.find_by_name(file_name)
     }
 
     /// Get DirectoryInfo of sub_directory within a DirectoryListing.
     /// Returns the Option<DirectoryInfo> for the directory_name from the DirectoryListing
     pub fn find_file_by_id(&self, id: &XorName) -> Option<&File> {
-        self.get_files().iter().find(|file| *file.get_id() == *id)
+        self.files.find_by_id(id)
+    }
+
+    /// Get all symlinks in this DirectoryListing
+    pub fn get_symlinks(&self) -> &EntryList<Symlink> {
+        &self.symlinks
+    }
+
+    /// Get the symlink with the given name
+    pub fn find_symlink(&self, symlink_name: &String) -> Option<&Symlink> {
+        self.symlinks.find_by_name(symlink_name)
     }
 
     /// Get DirectoryInfo of sub_directory within a DirectoryListing.
     /// Returns the Option<DirectoryInfo> for the directory_name from the DirectoryListing
     pub fn find_sub_directory(&self, directory_name: &String) -> Option<&DirectoryMetadata> {
-        self.get_sub_directories().iter().find(|info| *info.get_name() == *directory_name)
+        self.sub_directories.find_by_name(directory_name)
     }
 
     /// Get DirectoryInfo of sub_directory within a DirectoryListing.
     /// Returns the Option<DirectoryInfo> for the directory_name from the DirectoryListing
     pub fn find_sub_directory_by_id(&self, id: &XorName) -> Option<&DirectoryMetadata> {
-        self.get_sub_directories().iter().find(|info| *info.get_id() == *id)
+        self.sub_directories.find_by_id(id)
     }
 
     /// If file is present in the DirectoryListing then replace it else insert it
     pub fn upsert_file(&mut self, file: File) {
         let modified_time = file.get_metadata().get_modified_time().clone();
-        // TODO try using the below approach for efficiency - also try the same
-        // in upsert_sub_directory
-        //     if let Some(mut existing_file) = self.files.iter_mut().find(
-        //             |entry| *entry.get_name() == *file.get_name()) {
-        //         *existing_file = file;
-        if let Some(index) = self.files.iter().position(|entry| *entry.get_id() == *file.get_id()) {
-            debug!("Replacing file in directory listing ...");
-            let mut existing = unwrap_option!(self.files.get_mut(index),
-                                              "Programming Error - Report this as a Bug.");
-            *existing = file;
-        } else {
-            debug!("Adding file to directory listing ...");
-            self.files.push(file);
-        }
+        debug!("Upserting file in directory listing ...");
+        self.files.upsert(file);
         self.get_mut_metadata().set_modified_time(modified_time)
     }
 
@@ -172,39 +227,43 @@ impl DirectoryListing {
     /// then replace it else insert it
     pub fn upsert_sub_directory(&mut self, directory_metadata: DirectoryMetadata) {
         let modified_time = directory_metadata.get_modified_time().clone();
-        if let Some(index) = self.sub_directories.iter().position(|entry| {
-            *entry.get_key().get_id() == *directory_metadata.get_key().get_id()
-        }) {
-            debug!("Replacing directory listing metadata ...");
-            let mut existing = unwrap_option!(self.sub_directories.get_mut(index),
-                                              "Programming Error - Report this as a Bug.");
-            *existing = directory_metadata;
-        } else {
-            debug!("Adding metadata to directory listing ...");
-            self.sub_directories.push(directory_metadata);
-        }
+        debug!("Upserting directory listing metadata ...");
+        self.sub_directories.upsert(directory_metadata);
         self.get_mut_metadata().set_modified_time(modified_time);
     }
 
     /// Remove a sub_directory
     pub fn remove_sub_directory(&mut self, directory_name: &String) -> Result<(), NfsError> {
-        let index = try!(self.get_sub_directories()
-                             .iter()
-                             .position(|dir_info| *dir_info.get_name() == *directory_name)
-                             .ok_or(NfsError::DirectoryNotFound));
-        debug!("Removing sub directory at index {:?} ...", index);
-        let _ = self.get_mut_sub_directories().remove(index);
+        debug!("Removing sub directory {:?} ...", directory_name);
+        let _ = try!(self.sub_directories
+                         .remove_by_name(directory_name)
+                         .ok_or(NfsError::DirectoryNotFound(directory_name.clone())));
         Ok(())
     }
 
     /// Remove a file
     pub fn remove_file(&mut self, file_name: &String) -> Result<(), NfsError> {
-        let index = try!(self.get_files()
-                             .iter()
-                             .position(|file| *file.get_name() == *file_name)
-                             .ok_or(NfsError::FileNotFound));
-        debug!("Removing file at index {:?} ...", index);
-        let _ = self.get_mut_files().remove(index);
+        debug!("Removing file {:?} ...", file_name);
+        let _ = try!(self.files
+                         .remove_by_name(file_name)
+                         .ok_or(NfsError::FileNotFound(file_name.clone())));
+        Ok(())
+    }
+
+    /// If the symlink is present in the DirectoryListing then replace it else insert it
+    pub fn upsert_symlink(&mut self, symlink: Symlink) {
+        let modified_time = symlink.get_metadata().get_modified_time().clone();
+        debug!("Upserting symlink in directory listing ...");
+        self.symlinks.upsert(symlink);
+        self.get_mut_metadata().set_modified_time(modified_time)
+    }
+
+    /// Remove a symlink
+    pub fn remove_symlink(&mut self, symlink_name: &String) -> Result<(), NfsError> {
+        debug!("Removing symlink {:?} ...", symlink_name);
+        let _ = try!(self.symlinks
+                         .remove_by_name(symlink_name)
+                         .ok_or(NfsError::FileNotFound(symlink_name.clone())));
         Ok(())
     }
 
@@ -219,6 +278,72 @@ impl DirectoryListing {
     }
 }
 
+impl Encodable for DirectoryListing {
+    fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
+        e.emit_struct("DirectoryListing", 3, |e| {
+            try!(e.emit_struct_field("metadata", 0, |e| self.metadata.encode(e)));
+            try!(e.emit_struct_field("sub_directories", 1, |e| self.sub_directories.encode(e)));
+            try!(e.emit_struct_field("files", 2, |e| {
+                e.emit_seq(self.files.len() + self.symlinks.len(), |e| {
+                    for (index, file) in self.get_stored_files().enumerate() {
+                        try!(e.emit_seq_elt(index, |e| file.encode(e)));
+                    }
+                    Ok(())
+                })
+            }));
+
+            Ok(())
+        })
+    }
+}
+
+impl Decodable for DirectoryListing {
+    fn decode<D: Decoder>(d: &mut D) -> Result<DirectoryListing, D::Error> {
+        d.read_struct("DirectoryListing", 3, |d| {
+            let metadata = try!(d.read_struct_field("metadata", 0, |d| Decodable::decode(d)));
+            let sub_directories = try!(d.read_struct_field("sub_directories",
+                                                           1,
+                                                           |d| Decodable::decode(d)));
+            let files = try!(d.read_struct_field("files", 2, |d| Decodable::decode(d)));
+            Ok(DirectoryListing::from_parts(metadata, sub_directories, files))
+        })
+    }
+}
+
+impl PartialEq for DirectoryListing {
+    fn eq(&self, other: &DirectoryListing) -> bool {
+        self.metadata == other.metadata && self.sub_directories == other.sub_directories &&
+        self.files == other.files && self.symlinks == other.symlinks
+    }
+}
+
+impl Eq for DirectoryListing {}
+
+impl PartialOrd for DirectoryListing {
+    fn partial_cmp(&self, other: &DirectoryListing) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DirectoryListing {
+    fn cmp(&self, other: &DirectoryListing) -> Ordering {
+        (&self.metadata, &self.sub_directories, &self.files, &self.symlinks)
+            .cmp(&(&other.metadata, &other.sub_directories, &other.files, &other.symlinks))
+    }
+}
+
+impl fmt::Debug for DirectoryListing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "DirectoryListing > metadata: {:?}, sub_directories: {:?}, files: {:?}, symlinks: \
+                {:?}",
+               self.metadata,
+               self.sub_directories,
+               self.files,
+               self.symlinks)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::sync::{Arc, Mutex};
@@ -228,6 +353,7 @@ mod test {
     use metadata::file_metadata::FileMetadata;
     use safe_core::utility::test_utils;
     use self_encryption::DataMap;
+    use symlink::Symlink;
 
     #[test]
     fn serialise_and_deserialise_directory_listing() {
@@ -245,6 +371,37 @@ mod test {
         assert_eq!(obj_before, obj_after);
     }
 
+    #[test]
+    fn serialise_and_deserialise_symlinks() {
+        let mut obj_before = unwrap_result!(DirectoryListing::new("Home".to_string(),
+                                                                  10,
+                                                                  Vec::new(),
+                                                                  true,
+                                                                  ::AccessLevel::Private,
+                                                                  None));
+        obj_before.upsert_file(unwrap_result!(File::new(FileMetadata::new("a.jpg".to_string(),
+                                                                          Vec::new()),
+                                                        DataMap::None)));
+        obj_before.upsert_symlink(unwrap_result!(Symlink::new("latest".to_string(),
+                                                              "a.jpg".to_string())));
+
+        let serialised_data = unwrap_result!(serialise(&obj_before));
+        let obj_after: DirectoryListing = unwrap_result!(deserialise(&serialised_data));
+        assert_eq!(obj_before, obj_after);
+        assert_eq!(obj_after.get_files().len(), 1);
+        assert_eq!(obj_after.get_symlinks().len(), 1);
+        assert!(obj_after.find_file(&"latest".to_string()).is_none());
+        assert_eq!(unwrap_option!(obj_after.find_symlink(&"latest".to_string()),
+                                  "Symlink not found")
+                       .get_target(),
+                   "a.jpg");
+
+        let mut obj_after = obj_after;
+        unwrap_result!(obj_after.remove_symlink(&"latest".to_string()));
+        assert!(obj_after.get_symlinks().is_empty());
+        assert!(obj_after.remove_symlink(&"latest".to_string()).is_err());
+    }
+
     #[test]
     fn encrypt_and_decrypt_directory_listing() {
         let test_client = unwrap_result!(test_utils::get_client());
@@ -355,4 +512,147 @@ mod test {
         assert_eq!(directory_listing.get_sub_directories().len(), 0);
     }
 
+    #[test]
+    fn indexed_lookups_in_large_directory() {
+        const ENTRIES: usize = 100_000;
+
+        let mut directory_listing = unwrap_result!(DirectoryListing::new("Home".to_string(),
+                                                                         10,
+                                                                         Vec::new(),
+                                                                         true,
+                                                                         ::AccessLevel::Private,
+                                                                         None));
+
+        for i in 0..ENTRIES {
+            let file = unwrap_result!(File::new(FileMetadata::new(format!("{}.txt", i),
+                                                                  Vec::new()),
+                                                DataMap::None));
+            directory_listing.upsert_file(file);
+        }
+        assert_eq!(directory_listing.get_files().len(), ENTRIES);
+
+        for i in 0..ENTRIES {
+            let name = format!("{}.txt", i);
+            let file = unwrap_option!(directory_listing.find_file(&name), "File not found");
+            assert_eq!(*file.get_name(), name);
+            assert!(directory_listing.find_file_by_id(file.get_id()).is_some());
+        }
+
+        // Renaming through upsert keeps the entry and updates the name index
+        let mut renamed = unwrap_option!(directory_listing.find_file(&"0.txt".to_string()),
+                                         "File not found")
+                              .clone();
+        renamed.get_mut_metadata().set_name("renamed.txt".to_string());
+        directory_listing.upsert_file(renamed.clone());
+        assert_eq!(directory_listing.get_files().len(), ENTRIES);
+        assert!(directory_listing.find_file(&"0.txt".to_string()).is_none());
+        assert_eq!(unwrap_option!(directory_listing.find_file(&"renamed.txt".to_string()),
+                                  "File not found")
+                       .get_id(),
+                   renamed.get_id());
+
+        // Removals keep the remaining files in insertion order
+        for i in (1..ENTRIES).filter(|i| i % 2 == 0) {
+            unwrap_result!(directory_listing.remove_file(&format!("{}.txt", i)));
+        }
+        assert_eq!(directory_listing.get_files().len(), ENTRIES / 2 + 1);
+        let expected_names = Some("renamed.txt".to_string())
+                                 .into_iter()
+                                 .chain((1..ENTRIES)
+                                            .filter(|i| i % 2 == 1)
+                                            .map(|i| format!("{}.txt", i)))
+                                 .collect::<Vec<_>>();
+        assert_eq!(directory_listing.get_files()
+                                    .iter()
+                                    .map(|file| file.get_name().clone())
+                                    .collect::<Vec<_>>(),
+                   expected_names);
+        for i in 1..ENTRIES {
+            assert_eq!(directory_listing.find_file(&format!("{}.txt", i)).is_some(),
+                       i % 2 == 1);
+        }
+
+        // The index is rebuilt on decode
+        let serialised_data = unwrap_result!(serialise(&directory_listing));
+        let deserialised: DirectoryListing = unwrap_result!(deserialise(&serialised_data));
+        assert_eq!(deserialised, directory_listing);
+        assert!(deserialised.find_file(&"1.txt".to_string()).is_some());
+        assert!(deserialised.find_file(&"renamed.txt".to_string()).is_some());
+
+        // Pushed entries are indexed, entries renamed in place are still found, and the next
+        // upsert rebuilds the index
+        let file = unwrap_result!(File::new(FileMetadata::new("pushed.txt".to_string(),
+                                                              Vec::new()),
+                                            DataMap::None));
+        directory_listing.get_mut_files().push(file);
+        assert!(directory_listing.find_file(&"pushed.txt".to_string()).is_some());
+        for file in directory_listing.get_mut_files().iter_mut() {
+            if *file.get_name() == "1.txt" {
+                file.get_mut_metadata().set_name("edited.txt".to_string());
+            }
+        }
+        assert!(directory_listing.find_file(&"1.txt".to_string()).is_none());
+        assert!(directory_listing.find_file(&"edited.txt".to_string()).is_some());
+        let file = unwrap_result!(File::new(FileMetadata::new("upserted.txt".to_string(),
+                                                              Vec::new()),
+                                            DataMap::None));
+        directory_listing.upsert_file(file);
+        assert!(directory_listing.find_file(&"edited.txt".to_string()).is_some());
+        assert!(directory_listing.find_file(&"upserted.txt".to_string()).is_some());
+        unwrap_result!(directory_listing.remove_file(&"pushed.txt".to_string()));
+        assert!(directory_listing.find_file(&"pushed.txt".to_string()).is_none());
+        assert!(directory_listing.find_file(&"upserted.txt".to_string()).is_some());
+        assert_eq!(unwrap_option!(directory_listing.get_files().iter().last(),
+                                  "No files")
+                       .get_name(),
+                   "upserted.txt");
+    }
+
+    // Times filling a directory with `entries` files, looking each of them up and removing them
+    // all from the front, so that every removal precedes all the remaining files
+    fn time_lookups_and_removals(entries: usize) -> u64 {
+        let mut directory_listing = unwrap_result!(DirectoryListing::new("Home".to_string(),
+                                                                         10,
+                                                                         Vec::new(),
+                                                                         true,
+                                                                         ::AccessLevel::Private,
+                                                                         None));
+        let files = (0..entries)
+                        .map(|i| {
+                            unwrap_result!(File::new(FileMetadata::new(format!("{}.txt", i),
+                                                                       Vec::new()),
+                                                     DataMap::None))
+                        })
+                        .collect::<Vec<_>>();
+        let start = ::time::precise_time_ns();
+        for file in files {
+            directory_listing.upsert_file(file);
+        }
+        for i in 0..entries {
+            assert!(directory_listing.find_file(&format!("{}.txt", i)).is_some());
+        }
+        for i in 0..entries {
+            unwrap_result!(directory_listing.remove_file(&format!("{}.txt", i)));
+        }
+        assert!(directory_listing.get_files().is_empty());
+        ::time::precise_time_ns() - start
+    }
+
+    // Run with `cargo test -- --ignored`. If every operation scanned or shifted the listing,
+    // quadrupling the entries would make the whole workload take about sixteen times longer.
+    #[test]
+    #[ignore]
+    fn lookups_and_removals_stay_sub_linear() {
+        const ENTRIES: usize = 25_000;
+
+        let _ = time_lookups_and_removals(ENTRIES);
+        let small = time_lookups_and_removals(ENTRIES);
+        let large = time_lookups_and_removals(4 * ENTRIES);
+        assert!(large < 8 * small,
+                "{} entries took {} ns, {} entries took {} ns",
+                ENTRIES,
+                small,
+                4 * ENTRIES,
+                large);
+    }
 }
@@ -0,0 +1,362 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::cmp;
+
+use maidsafe_utilities::serialisation::serialise;
+use sodiumoxide::crypto::hash::{sha256, sha512};
+
+use directory_listing::DirectoryListing;
+use errors::NfsError;
+use file::File;
+use metadata::directory_metadata::DirectoryMetadata;
+use xor_name::XorName;
+
+/// Largest number of shards the entries of a directory are split across
+const MAX_SHARD_COUNT: u32 = 1 << 16;
+
+/// Where a shard is stored and what it holds. Every rewrite of a shard is stored under a new
+/// generation, so that a head never refers to a shard written for another head.
+#[derive(RustcEncodable, RustcDecodable, Debug, Clone, PartialEq)]
+struct ShardReference {
+    generation: u64,
+    digest: Vec<u8>,
+}
+
+/// The part of a sharded DirectoryListing stored in the StructuredData of the directory itself.
+/// The files, symlinks and sub directories are split by the hash of their names across shards,
+/// each stored in its own StructuredData. The head lists the generation and the digest of every
+/// shard: readers check each shard against its digest, and an update only rewrites the shards
+/// whose entries changed. The number of shards is doubled whenever a shard would hold more than
+/// `::DIRECTORY_LISTING_SHARD_CAPACITY` entries.
+#[derive(RustcEncodable, RustcDecodable, Debug, Clone, PartialEq)]
+pub struct ShardedListingHead {
+    metadata: DirectoryMetadata,
+    shards: Vec<ShardReference>,
+}
+
+impl ShardedListingHead {
+    /// Returns the number of shards the entries are split across
+    pub fn get_shard_count(&self) -> u32 {
+        self.shards.len() as u32
+    }
+
+    /// Returns the digest of the serialised entries of the shard
+    pub fn get_shard_digest(&self, index: u32) -> Option<&Vec<u8>> {
+        self.shards.get(index as usize).map(|shard| &shard.digest)
+    }
+
+    /// Returns the generation the shard was last written in
+    pub fn get_shard_generation(&self, index: u32) -> Option<u64> {
+        self.shards.get(index as usize).map(|shard| shard.generation)
+    }
+
+    /// Returns the id of the StructuredData holding the shard
+    pub fn get_shard_id(&self, directory_id: &XorName, index: u32) -> Option<XorName> {
+        self.get_shard_generation(index)
+            .map(|generation| get_shard_id(directory_id, index, generation))
+    }
+
+    /// Returns true if the serialised entries match the digest of the shard
+    pub fn verify_shard(&self, index: u32, serialised_shard: &[u8]) -> bool {
+        self.get_shard_digest(index) == Some(&sha256::hash(serialised_shard).0.to_vec())
+    }
+}
+
+/// The entries of a sharded DirectoryListing held in one shard. Symlinks are held as the Files
+/// they are stored as.
+#[derive(RustcEncodable, RustcDecodable, Debug, Clone, PartialEq, Default)]
+pub struct Shard {
+    files: Vec<File>,
+    sub_directories: Vec<DirectoryMetadata>,
+}
+
+impl Shard {
+    /// Returns the number of files and sub directories held in the shard
+    pub fn len(&self) -> usize {
+        self.files.len() + self.sub_directories.len()
+    }
+
+    /// Returns true if the shard holds no entry
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.sub_directories.is_empty()
+    }
+
+    /// Returns the files of the shard, including the Files symlinks are stored as
+    pub fn get_files(&self) -> &Vec<File> {
+        &self.files
+    }
+
+    /// Returns the sub directories of the shard
+    pub fn get_sub_directories(&self) -> &Vec<DirectoryMetadata> {
+        &self.sub_directories
+    }
+}
+
+/// A DirectoryListing split into its head and the serialised entries of every shard
+pub struct ShardedListing {
+    head: ShardedListingHead,
+    shards: Vec<Vec<u8>>,
+}
+
+impl ShardedListing {
+    /// Splits the entries of the DirectoryListing across at least `shard_count` shards, doubling
+    /// the count until no shard holds more than `::DIRECTORY_LISTING_SHARD_CAPACITY` entries.
+    /// Shards which hold the same entries as in the `previous` head keep their generation, the
+    /// others are given `generation`, which has to differ from every generation used before.
+    pub fn split(directory: &DirectoryListing,
+                 shard_count: u32,
+                 previous: Option<&ShardedListingHead>,
+                 generation: u64)
+                 -> Result<ShardedListing, NfsError> {
+        if shard_count == 0 {
+            return Err(NfsError::ParameterIsNotValid("Shard count can not be zero".to_string()));
+        }
+        let mut shard_count = shard_count;
+        let mut shards = distribute(directory, shard_count);
+        while shard_count < MAX_SHARD_COUNT &&
+              shards.iter().any(|shard| shard.len() > ::DIRECTORY_LISTING_SHARD_CAPACITY) {
+            shard_count = cmp::min(2 * shard_count, MAX_SHARD_COUNT);
+            shards = distribute(directory, shard_count);
+        }
+        let previous = previous.and_then(|previous| {
+            if previous.get_shard_count() == shard_count {
+                Some(previous)
+            } else {
+                None
+            }
+        });
+        let mut serialised_shards = Vec::with_capacity(shards.len());
+        let mut shard_references = Vec::with_capacity(shards.len());
+        for (index, shard) in shards.iter().enumerate() {
+            let serialised_shard = try!(serialise(shard));
+            let digest = sha256::hash(&serialised_shard).0.to_vec();
+            let generation = match previous.and_then(|previous| previous.shards.get(index)) {
+                Some(reference) if reference.digest == digest => reference.generation,
+                _ => generation,
+            };
+            shard_references.push(ShardReference {
+                generation: generation,
+                digest: digest,
+            });
+            serialised_shards.push(serialised_shard);
+        }
+        Ok(ShardedListing {
+            head: ShardedListingHead {
+                metadata: directory.get_metadata().clone(),
+                shards: shard_references,
+            },
+            shards: serialised_shards,
+        })
+    }
+
+    /// Returns the head to be stored in the StructuredData of the directory
+    pub fn get_head(&self) -> &ShardedListingHead {
+        &self.head
+    }
+
+    /// Returns the serialised entries of every shard, in shard order
+    pub fn get_shards(&self) -> &Vec<Vec<u8>> {
+        &self.shards
+    }
+}
+
+/// Reassembles the DirectoryListing from its head and every shard
+pub fn join(head: ShardedListingHead, shards: Vec<Shard>) -> DirectoryListing {
+    let mut files = Vec::new();
+    let mut sub_directories = Vec::new();
+    for shard in shards {
+        files.extend(shard.files);
+        sub_directories.extend(shard.sub_directories);
+    }
+    DirectoryListing::from_parts(head.metadata, sub_directories, files)
+}
+
+fn distribute(directory: &DirectoryListing, shard_count: u32) -> Vec<Shard> {
+    let mut shards = vec![Shard::default(); shard_count as usize];
+    for file in directory.get_stored_files() {
+        shards[get_shard_index(file.get_name(), shard_count) as usize]
+            .files
+            .push(file.clone());
+    }
+    for sub_directory in directory.get_sub_directories() {
+        shards[get_shard_index(sub_directory.get_name(), shard_count) as usize]
+            .sub_directories
+            .push(sub_directory.clone());
+    }
+    shards
+}
+
+/// Returns the shard holding the file or sub directory with the given name
+pub fn get_shard_index(name: &String, shard_count: u32) -> u32 {
+    let digest = sha256::hash(name.as_bytes());
+    let value = digest.0[0..4].iter().fold(0u32, |value, byte| (value << 8) | *byte as u32);
+    value % shard_count
+}
+
+/// Returns the id of the StructuredData holding a generation of a shard of the directory
+pub fn get_shard_id(directory_id: &XorName, index: u32, generation: u64) -> XorName {
+    let mut data = directory_id.0.to_vec();
+    data.extend_from_slice(&[(index >> 24) as u8,
+                             (index >> 16) as u8,
+                             (index >> 8) as u8,
+                             index as u8]);
+    for shift in (0..8).rev() {
+        data.push((generation >> (8 * shift)) as u8);
+    }
+    XorName(sha512::hash(&data).0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use directory_listing::DirectoryListing;
+    use file::File;
+    use maidsafe_utilities::serialisation::deserialise;
+    use metadata::file_metadata::FileMetadata;
+    use self_encryption::DataMap;
+
+    #[test]
+    fn split_and_join() {
+        let mut directory = unwrap_result!(DirectoryListing::new("Home".to_string(),
+                                                                 10,
+                                                                 Vec::new(),
+                                                                 false,
+                                                                 ::AccessLevel::Private,
+                                                                 None));
+        for i in 0..50 {
+            directory.upsert_file(unwrap_result!(File::new(FileMetadata::new(format!("{}.txt", i),
+                                                                             Vec::new()),
+                                                           DataMap::None)));
+        }
+        for i in 0..10 {
+            let sub_directory = unwrap_result!(DirectoryListing::new(format!("Sub{}", i),
+                                                                     10,
+                                                                     Vec::new(),
+                                                                     false,
+                                                                     ::AccessLevel::Private,
+                                                                     None));
+            directory.upsert_sub_directory(sub_directory.get_metadata().clone());
+        }
+        let sharded_listing = unwrap_result!(ShardedListing::split(&directory, 8, None, 0));
+        assert_eq!(sharded_listing.get_head().get_shard_count(), 8);
+        assert_eq!(sharded_listing.get_shards().len(), 8);
+        assert!(ShardedListing::split(&directory, 0, None, 0).is_err());
+
+        let shards = sharded_listing.get_shards()
+                                    .iter()
+                                    .map(|serialised_shard| {
+                                        unwrap_result!(deserialise::<Shard>(serialised_shard))
+                                    })
+                                    .collect::<Vec<_>>();
+        for (index, serialised_shard) in sharded_listing.get_shards().iter().enumerate() {
+            assert!(sharded_listing.get_head().verify_shard(index as u32, serialised_shard));
+            assert!(!sharded_listing.get_head().verify_shard(index as u32, &[]));
+        }
+        for (index, shard) in shards.iter().enumerate() {
+            for file in shard.get_files() {
+                assert_eq!(get_shard_index(file.get_name(), 8), index as u32);
+            }
+            for sub_directory in shard.get_sub_directories() {
+                assert_eq!(get_shard_index(sub_directory.get_name(), 8), index as u32);
+            }
+        }
+        let joined = join(sharded_listing.get_head().clone(), shards);
+        assert_eq!(joined.get_metadata(), directory.get_metadata());
+        assert_eq!(joined.get_files().len(), 50);
+        for i in 0..50 {
+            assert!(joined.find_file(&format!("{}.txt", i)).is_some());
+        }
+        assert_eq!(joined.get_sub_directories().len(), 10);
+        for i in 0..10 {
+            assert!(joined.find_sub_directory(&format!("Sub{}", i)).is_some());
+        }
+
+        // Adding a file only changes the digest and the generation of the shard holding it
+        let name = "new.txt".to_string();
+        directory.upsert_file(unwrap_result!(File::new(FileMetadata::new(name.clone(), Vec::new()),
+                                                       DataMap::None)));
+        let updated = unwrap_result!(ShardedListing::split(&directory,
+                                                           8,
+                                                           Some(sharded_listing.get_head()),
+                                                           1));
+        let changed_shard = get_shard_index(&name, 8);
+        for index in 0..8 {
+            assert_eq!(updated.get_head().get_shard_digest(index) ==
+                       sharded_listing.get_head().get_shard_digest(index),
+                       index != changed_shard);
+            let expected_generation = if index == changed_shard {
+                1
+            } else {
+                0
+            };
+            assert_eq!(updated.get_head().get_shard_generation(index),
+                       Some(expected_generation));
+        }
+        let directory_id = directory.get_key().get_id();
+        assert!(get_shard_id(directory_id, 0, 0) != get_shard_id(directory_id, 1, 0));
+        assert!(get_shard_id(directory_id, 0, 0) != get_shard_id(directory_id, 0, 1));
+        assert_eq!(updated.get_head().get_shard_id(directory_id, changed_shard),
+                   Some(get_shard_id(directory_id, changed_shard, 1)));
+        assert!(updated.get_head().get_shard_id(directory_id, 8).is_none());
+    }
+
+    #[test]
+    fn split_full_shards() {
+        const ENTRIES: usize = 3 * ::DIRECTORY_LISTING_SHARD_CAPACITY;
+
+        let mut directory = unwrap_result!(DirectoryListing::new("Home".to_string(),
+                                                                 10,
+                                                                 Vec::new(),
+                                                                 false,
+                                                                 ::AccessLevel::Private,
+                                                                 None));
+        for i in 0..ENTRIES {
+            directory.upsert_file(unwrap_result!(File::new(FileMetadata::new(format!("{}.txt", i),
+                                                                             Vec::new()),
+                                                           DataMap::None)));
+        }
+        let sharded_listing = unwrap_result!(ShardedListing::split(&directory, 1, None, 0));
+        let shard_count = sharded_listing.get_head().get_shard_count();
+        assert!(shard_count >= 4);
+        assert!(shard_count.is_power_of_two());
+        let shards = sharded_listing.get_shards()
+                                    .iter()
+                                    .map(|serialised_shard| {
+                                        unwrap_result!(deserialise::<Shard>(serialised_shard))
+                                    })
+                                    .collect::<Vec<_>>();
+        assert!(shards.iter().all(|shard| shard.len() <= ::DIRECTORY_LISTING_SHARD_CAPACITY));
+        assert_eq!(shards.iter().fold(0, |entries, shard| entries + shard.len()), ENTRIES);
+
+        // Unchanged shards keep their generation, while a change of the shard count rewrites
+        // every shard in the new generation
+        let unchanged = unwrap_result!(ShardedListing::split(&directory,
+                                                             shard_count,
+                                                             Some(sharded_listing.get_head()),
+                                                             1));
+        assert_eq!(unchanged.get_head(), sharded_listing.get_head());
+        let resplit = unwrap_result!(ShardedListing::split(&directory,
+                                                           2 * shard_count,
+                                                           Some(sharded_listing.get_head()),
+                                                           1));
+        assert_eq!(resplit.get_head().get_shard_count(), 2 * shard_count);
+        for index in 0..resplit.get_head().get_shard_count() {
+            assert_eq!(resplit.get_head().get_shard_generation(index), Some(1));
+        }
+    }
+}
@@ -17,6 +17,7 @@
 
 use std::fmt;
 
+use directory_listing::merge::MergeConflict;
 use maidsafe_utilities::serialisation::SerialisationError;
 use safe_core::errors::{CoreError, CLIENT_ERROR_START_RANGE};
 
@@ -33,22 +34,30 @@ pub enum NfsError {
     DirectoryAlreadyExistsWithSameName,
     /// Destination is Same as the Source
     DestinationAndSourceAreSame,
-    /// Directory not found
-    DirectoryNotFound,
+    /// Directory not found - holds the name or path of the directory that could not be found
+    DirectoryNotFound(String),
     /// File Already exists with the same name in a directory
     FileAlreadyExistsWithSameName,
     /// File does not match with the existing file in the directory listing
     FileDoesNotMatch,
-    /// File not found
-    FileNotFound,
+    /// File not found - holds the name or path of the file that could not be found
+    FileNotFound(String),
     /// Invalid byte range specified
     InvalidRangeSpecified,
-    /// Validation error - if the field passed as parameter is not valid
-    ParameterIsNotValid,
+    /// Validation error - if the field passed as parameter is not valid. Holds the reason.
+    ParameterIsNotValid(String),
     /// Unexpected error
     Unexpected(String),
     /// Unsuccessful Serialisation or Deserialisation
     UnsuccessfulEncodeDecode(SerialisationError),
+    /// A directory was found to be its own ancestor while following parent directory keys
+    CircularDirectoryReference,
+    /// The directory was updated by someone else since it was read
+    ConcurrentModification,
+    /// Concurrent changes to a directory could not be merged automatically
+    MergeConflicts(Vec<MergeConflict>),
+    /// Too many symlinks were followed while resolving a path. Holds the path reached.
+    SymlinkLoop(String),
 }
 
 impl From<CoreError> for NfsError {
@@ -75,14 +84,18 @@ impl Into<i32> for NfsError {
             NfsError::CoreError(error) => error.into(),
             NfsError::DirectoryAlreadyExistsWithSameName => NFS_ERROR_START_RANGE - 1,
             NfsError::DestinationAndSourceAreSame => NFS_ERROR_START_RANGE - 2,
-            NfsError::DirectoryNotFound => NFS_ERROR_START_RANGE - 3,
+            NfsError::DirectoryNotFound(_) => NFS_ERROR_START_RANGE - 3,
             NfsError::FileAlreadyExistsWithSameName => NFS_ERROR_START_RANGE - 4,
             NfsError::FileDoesNotMatch => NFS_ERROR_START_RANGE - 5,
-            NfsError::FileNotFound => NFS_ERROR_START_RANGE - 6,
+            NfsError::FileNotFound(_) => NFS_ERROR_START_RANGE - 6,
             NfsError::InvalidRangeSpecified => NFS_ERROR_START_RANGE - 7,
-            NfsError::ParameterIsNotValid => NFS_ERROR_START_RANGE - 8,
+            NfsError::ParameterIsNotValid(_) => NFS_ERROR_START_RANGE - 8,
             NfsError::Unexpected(_) => NFS_ERROR_START_RANGE - 9,
             NfsError::UnsuccessfulEncodeDecode(_) => NFS_ERROR_START_RANGE - 10,
+            NfsError::CircularDirectoryReference => NFS_ERROR_START_RANGE - 11,
+            NfsError::ConcurrentModification => NFS_ERROR_START_RANGE - 12,
+            NfsError::MergeConflicts(_) => NFS_ERROR_START_RANGE - 13,
+            NfsError::SymlinkLoop(_) => NFS_ERROR_START_RANGE - 14,
         }
     }
 }
@@ -97,18 +110,30 @@ impl fmt::Debug for NfsError {
             NfsError::DestinationAndSourceAreSame => {
                 write!(f, "NfsError::DestinationAndSourceAreSame")
             }
-            NfsError::DirectoryNotFound => write!(f, "NfsError::DirectoryNotFound"),
+            NfsError::DirectoryNotFound(ref name) => {
+                write!(f, "NfsError::DirectoryNotFound -> {:?}", name)
+            }
             NfsError::FileAlreadyExistsWithSameName => {
                 write!(f, "NfsError::FileAlreadyExistsWithSameName")
             }
             NfsError::FileDoesNotMatch => write!(f, "NfsError::FileDoesNotMatch"),
-            NfsError::FileNotFound => write!(f, "NfsError::FileNotFound"),
+            NfsError::FileNotFound(ref name) => write!(f, "NfsError::FileNotFound -> {:?}", name),
             NfsError::InvalidRangeSpecified => write!(f, "NfsError::InvalidRangeSpecified"),
-            NfsError::ParameterIsNotValid => write!(f, "NfsError::ParameterIsNotValid"),
+            NfsError::ParameterIsNotValid(ref reason) => {
+                write!(f, "NfsError::ParameterIsNotValid -> {:?}", reason)
+            }
             NfsError::Unexpected(ref error) => write!(f, "NfsError::Unexpected -> {:?}", error),
             NfsError::UnsuccessfulEncodeDecode(ref error) => {
                 write!(f, "NfsError::UnsuccessfulEncodeDecode -> {:?}", error)
             }
+            NfsError::CircularDirectoryReference => {
+                write!(f, "NfsError::CircularDirectoryReference")
+            }
+            NfsError::ConcurrentModification => write!(f, "NfsError::ConcurrentModification"),
+            NfsError::MergeConflicts(ref conflicts) => {
+                write!(f, "NfsError::MergeConflicts -> {:?}", conflicts)
+            }
+            NfsError::SymlinkLoop(ref path) => write!(f, "NfsError::SymlinkLoop -> {:?}", path),
         }
     }
 }
@@ -19,6 +19,7 @@ use std::fmt;
 
 use errors::NfsError;
 use metadata::file_metadata::FileMetadata;
+use metadata::xattr::ExtendedAttributes;
 use safe_core::utility;
 use self_encryption::DataMap;
 use xor_name::XorName;
@@ -62,12 +63,47 @@ impl File {
         &mut self.metadata
     }
 
+    /// Get the extended attributes of the file
+    pub fn get_xattrs(&self) -> &ExtendedAttributes {
+        self.metadata.get_xattrs()
+    }
+
+    /// Get the extended attributes of the file, with mutability to allow updation
+    pub fn get_mut_xattrs(&mut self) -> &mut ExtendedAttributes {
+        self.metadata.get_mut_xattrs()
+    }
+
     /// Get the data-map of the File. This is generated by passing the contents of the File to
     /// self-encryption
     pub fn get_datamap(&self) -> &DataMap {
         &self.datamap
     }
 
+    /// Returns the names of the self-encrypted chunks referenced by the data-map of the File.
+    /// Small files whose content is held inline in the data-map have no chunks.
+    pub fn get_chunk_names(&self) -> Vec<XorName> {
+        self.get_chunk_sizes().into_iter().map(|(name, _)| name).collect()
+    }
+
+    /// Returns the names of the self-encrypted chunks referenced by the data-map of the File
+    /// along with the size of the content each chunk was encrypted from
+    pub fn get_chunk_sizes(&self) -> Vec<(XorName, u64)> {
+        match self.datamap {
+            DataMap::Chunks(ref chunks) => {
+                chunks.iter()
+                      .map(|chunk| {
+                          let mut name = [0u8; 64];
+                          for (index, byte) in chunk.hash.iter().take(name.len()).enumerate() {
+                              name[index] = *byte;
+                          }
+                          (XorName::new(name), chunk.source_size)
+                      })
+                      .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
     /// Set a data-map to be associated with the File
     pub fn set_datamap(&mut self, datamap: DataMap) {
         self.datamap = datamap;
@@ -15,27 +15,247 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
+use std::cmp;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::sync::{Arc, Mutex};
 
 use errors::NfsError;
 use directory_listing::DirectoryListing;
+use directory_listing::diff::{self, DirectoryDiff};
+use directory_listing::merge;
+use directory_listing::shard::{self, Shard, ShardedListing, ShardedListingHead};
+use file::File;
+use helper::history;
+use helper::link;
+use helper::name_validator::NameValidator;
+use helper::storage;
+use helper::version_log;
+use helper::walker::{WalkEntry, WalkError, WalkOrder, Walker};
 use xor_name::XorName;
 use maidsafe_utilities::serialisation::{serialise, deserialise};
 use metadata::directory_key::DirectoryKey;
-use routing::{ImmutableData, ImmutableDataType, StructuredData, Data, DataRequest};
+use metadata::directory_metadata::DirectoryMetadata;
+use metadata::version_record::VersionRecord;
+use routing::{ImmutableData, ImmutableDataType, StructuredData, Data};
 use safe_core::client::Client;
-use safe_core::errors::CoreError;
 use safe_core::structured_data_operations::{unversioned, versioned};
+use symlink::Symlink;
+use time::Tm;
+
+/// Maximum number of symlinks followed while resolving a single path
+pub const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Summary of the network data released by a recursive delete of a directory tree
+#[derive(Debug, Clone, Default)]
+pub struct DeletedDirectoryTree {
+    directories: Vec<DirectoryKey>,
+    versions: Vec<XorName>,
+    files: Vec<File>,
+    chunks: Vec<XorName>,
+    cleanup_failures: Vec<(File, String)>,
+}
+
+impl DeletedDirectoryTree {
+    /// Keys of the directories whose StructuredData is (or would be) deleted
+    pub fn get_directories(&self) -> &Vec<DirectoryKey> {
+        &self.directories
+    }
+
+    /// Names of the ImmutableData holding the versions of the deleted versioned directories
+    pub fn get_versions(&self) -> &Vec<XorName> {
+        &self.versions
+    }
+
+    /// Files that were held in the deleted directories
+    pub fn get_files(&self) -> &Vec<File> {
+        &self.files
+    }
+
+    /// Names of the self-encrypted chunks no longer referenced by the deleted files
+    pub fn get_chunks(&self) -> &Vec<XorName> {
+        &self.chunks
+    }
+
+    /// Deleted files whose link or version history could not be removed, along with the error.
+    /// The directories are deleted regardless, so these are only left behind in the network.
+    pub fn get_cleanup_failures(&self) -> &Vec<(File, String)> {
+        &self.cleanup_failures
+    }
+}
+
+/// Summary of the versions dropped by `DirectoryHelper::prune_versions`
+#[derive(Debug, Clone, Default)]
+pub struct PrunedVersions {
+    versions: Vec<XorName>,
+    released: Vec<XorName>,
+    undated: Vec<XorName>,
+}
+
+impl PrunedVersions {
+    /// Names of the ImmutableData holding the dropped versions, oldest first
+    pub fn get_versions(&self) -> &Vec<XorName> {
+        &self.versions
+    }
+
+    /// Names of the ImmutableData no longer referenced by the kept versions of the directory.
+    /// They are not deleted, as identical listings saved elsewhere share them.
+    pub fn get_released(&self) -> &Vec<XorName> {
+        &self.released
+    }
+
+    /// Versions without a record in the version log, e.g. because recording them failed. They
+    /// were dated by the modified time of their listing instead of the time they were saved.
+    pub fn get_undated(&self) -> &Vec<XorName> {
+        &self.undated
+    }
+
+    /// Returns true if no version was dropped
+    pub fn is_empty(&self) -> bool {
+        self.versions.is_empty()
+    }
+}
+
+/// Storage consumed by a directory tree, as computed by `DirectoryHelper::get_usage`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirectoryUsage {
+    directory_count: u64,
+    file_count: u64,
+    size: u64,
+    version_count: u64,
+    versions_size: u64,
+    chunk_count: u64,
+    chunks_size: u64,
+}
+
+impl DirectoryUsage {
+    /// Number of directories in the tree, including the directory itself
+    pub fn get_directory_count(&self) -> u64 {
+        self.directory_count
+    }
+
+    /// Number of files in the latest version of every directory in the tree
+    pub fn get_file_count(&self) -> u64 {
+        self.file_count
+    }
+
+    /// Sum of the sizes of the files counted by `get_file_count`
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    /// Number of historical versions of the versioned directories in the tree, excluding the
+    /// latest ones. Only counted if requested.
+    pub fn get_version_count(&self) -> u64 {
+        self.version_count
+    }
+
+    /// Sum of the sizes of the distinct files which are only found in historical versions
+    pub fn get_versions_size(&self) -> u64 {
+        self.versions_size
+    }
+
+    /// Number of distinct self-encrypted chunks referenced by the files counted
+    pub fn get_chunk_count(&self) -> u64 {
+        self.chunk_count
+    }
+
+    /// Estimate of the network storage used by the chunks counted by `get_chunk_count`. Content
+    /// of small files held inline in their data-map is not included.
+    pub fn get_chunks_size(&self) -> u64 {
+        self.chunks_size
+    }
+}
+
+/// Position in the files of a directory from which `DirectoryHelper::list_files` continues.
+/// A cursor is only meaningful as long as the directory is not modified.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListingCursor {
+    shard: u32,
+    offset: usize,
+}
+
+/// The directories passed through while resolving a path, from the starting directory down to
+/// the current one, along with their names
+struct PathResolution {
+    directories: Vec<DirectoryListing>,
+    path: Vec<String>,
+    symlink_hops: usize,
+}
+
+/// The versions of the sub directories compared by a recursive diff, each with the timestamp of
+/// its record in the version log if it has one, and the listings fetched for those versions
+#[derive(Default)]
+struct VersionCache {
+    versions: HashMap<XorName, Vec<(XorName, Option<Tm>)>>,
+    listings: HashMap<XorName, DirectoryListing>,
+}
+
+impl PathResolution {
+    fn new(start_directory: DirectoryListing) -> PathResolution {
+        PathResolution {
+            directories: vec![start_directory],
+            path: Vec::new(),
+            symlink_hops: 0,
+        }
+    }
+
+    fn current(&self) -> &DirectoryListing {
+        &self.directories[self.directories.len() - 1]
+    }
+
+    fn into_current(mut self) -> DirectoryListing {
+        let last = self.directories.len() - 1;
+        self.directories.swap_remove(last)
+    }
+
+    fn enter(&mut self, name: String, directory: DirectoryListing) {
+        self.path.push(name);
+        self.directories.push(directory);
+    }
+
+    /// Moves to the parent directory. The starting directory is its own parent.
+    fn leave(&mut self) {
+        if self.directories.len() > 1 {
+            let _ = self.path.pop();
+            let _ = self.directories.pop();
+        }
+    }
+
+    /// Counts the symlink against `MAX_SYMLINK_HOPS` and moves back to the starting directory if
+    /// the target is absolute
+    fn follow_symlink(&mut self, name: &str, target: &str) -> Result<(), NfsError> {
+        self.symlink_hops += 1;
+        if self.symlink_hops > MAX_SYMLINK_HOPS {
+            self.path.push(name.to_string());
+            return Err(NfsError::SymlinkLoop(DirectoryHelper::join_path(&self.path)));
+        }
+        if target.starts_with('/') {
+            self.directories.truncate(1);
+            self.path.clear();
+        }
+        Ok(())
+    }
+}
 
 /// DirectoryHelper provides helper functions to perform Operations on Directory
 pub struct DirectoryHelper {
     client: Arc<Mutex<Client>>,
+    name_validator: NameValidator,
 }
 
 impl DirectoryHelper {
     /// Create a new DirectoryHelper instance
     pub fn new(client: Arc<Mutex<Client>>) -> DirectoryHelper {
-        DirectoryHelper { client: client }
+        DirectoryHelper {
+            client: client,
+            name_validator: NameValidator::new(),
+        }
+    }
+
+    /// Replaces the rules the names of created, copied, moved and renamed directories are
+    /// validated against
+    pub fn set_name_validator(&mut self, name_validator: NameValidator) {
+        self.name_validator = name_validator;
     }
 
     /// Creates a Directory in the network.
@@ -43,6 +263,10 @@ impl DirectoryHelper {
     /// Then the parent directory is updated.
     /// If the parent_directory passed has a parent, then the parent_directory's parent
     /// is also updated and the same is returned
+    /// Passing `::SHARDED_DIRECTORY_LISTING_TAG` as the tag_type splits the files and sub
+    /// directories of the directory across `::DIRECTORY_LISTING_SHARD_COUNT` StructuredData, so
+    /// that an update only rewrites the shards it touched. The shard count doubles as the
+    /// directory grows. Sharded directories can not be versioned.
     /// Returns (created_directory, Option<parent_directory's parent>)
     pub fn create(&self,
                   directory_name: String,
@@ -52,14 +276,19 @@ impl DirectoryHelper {
                   access_level: ::AccessLevel,
                   parent_directory: Option<&mut DirectoryListing>)
                   -> Result<(DirectoryListing, Option<DirectoryListing>), NfsError> {
-
-        if parent_directory.iter()
-                           .next()
-                           .and_then(|dir| dir.find_sub_directory(&directory_name))
-                           .is_some() {
-            return Err(NfsError::DirectoryAlreadyExistsWithSameName);
+        if tag_type == ::SHARDED_DIRECTORY_LISTING_TAG && versioned {
+            return Err(NfsError::ParameterIsNotValid("Sharded directories can not be versioned"
+                                                         .to_string()));
         }
 
+        let directory_name = match parent_directory {
+            Some(ref parent_directory) => {
+                try!(self.name_validator
+                         .validate_directory_name(directory_name, parent_directory, None))
+            }
+            None => try!(self.name_validator.validate(directory_name)),
+        };
+
         let directory = try!(DirectoryListing::new(directory_name,
                                                    tag_type,
                                                    user_metadata,
@@ -82,6 +311,31 @@ impl DirectoryHelper {
         }
     }
 
+    /// Copies the directory represented by `source_directory_key` along with its entire subtree
+    /// into the destination directory under `new_name`.
+    /// Every copied directory gets a fresh DirectoryKey. The AccessLevel and versioning of the
+    /// copies can be changed by passing `Some` value, else those of the source are retained.
+    /// Files get fresh ids but share the DataMap of the source, so no content is re-uploaded.
+    /// Returns (copied_directory, Option<destination_directory's parent>)
+    pub fn copy_directory(&self,
+                          source_directory_key: &DirectoryKey,
+                          new_name: String,
+                          destination_directory: &mut DirectoryListing,
+                          access_level: Option<::AccessLevel>,
+                          versioned: Option<bool>)
+                          -> Result<(DirectoryListing, Option<DirectoryListing>), NfsError> {
+        let new_name = try!(self.name_validator
+                                .validate_directory_name(new_name, destination_directory, None));
+        try!(self.check_not_descendant(destination_directory, source_directory_key.get_id()));
+        let copied_directory = try!(self.copy_subtree(source_directory_key,
+                                                      new_name,
+                                                      destination_directory.get_key(),
+                                                      &access_level,
+                                                      versioned));
+        destination_directory.upsert_sub_directory(copied_directory.get_metadata().clone());
+        Ok((copied_directory, try!(self.update(destination_directory))))
+    }
+
     /// Deletes a sub directory
     /// The parent_directory's parent is also updated if present
     /// Returns Option<parent_directory's parent>
@@ -94,6 +348,194 @@ impl DirectoryHelper {
         self.update(&parent_directory)
     }
 
+    /// Creates a symlink named `symlink_name` pointing to `target` in the parent directory.
+    /// The parent_directory's parent is also updated if present
+    /// Returns Option<parent_directory's parent>
+    pub fn create_symlink(&self,
+                          symlink_name: String,
+                          target: String,
+                          parent_directory: &mut DirectoryListing)
+                          -> Result<Option<DirectoryListing>, NfsError> {
+        let symlink_name = try!(self.name_validator
+                                    .validate_file_name(symlink_name, parent_directory, None));
+        parent_directory.upsert_symlink(try!(Symlink::new(symlink_name, target)));
+        self.update(&parent_directory)
+    }
+
+    /// Deletes a symlink. The target is left untouched.
+    /// The parent_directory's parent is also updated if present
+    /// Returns Option<parent_directory's parent>
+    pub fn delete_symlink(&self,
+                          parent_directory: &mut DirectoryListing,
+                          symlink_name: &String)
+                          -> Result<Option<DirectoryListing>, NfsError> {
+        try!(parent_directory.remove_symlink(symlink_name));
+        parent_directory.get_mut_metadata().set_modified_time(::time::now_utc());
+        self.update(&parent_directory)
+    }
+
+    /// Moves a sub directory from the source directory to the destination directory under
+    /// `new_name`. If the destination is `None`, the sub directory is renamed within the source
+    /// directory.
+    /// The source listing, the destination listing and the parent_dir_key of the moved directory
+    /// are updated in the network.
+    pub fn move_directory(&self,
+                          directory_name: &String,
+                          new_name: String,
+                          source_directory: &mut DirectoryListing,
+                          destination_directory: Option<&mut DirectoryListing>)
+                          -> Result<(), NfsError> {
+        let directory_key =
+            try!(source_directory.find_sub_directory(directory_name)
+                                 .map(|metadata| metadata.get_key().clone())
+                                 .ok_or(NfsError::DirectoryNotFound(directory_name.clone())));
+        let mut directory = try!(self.get(&directory_key));
+        match destination_directory {
+            Some(destination_directory) => {
+                if destination_directory.get_key() == source_directory.get_key() {
+                    return Err(NfsError::DestinationAndSourceAreSame);
+                }
+                let new_name = try!(self.name_validator
+                                        .validate_directory_name(new_name,
+                                                                 destination_directory,
+                                                                 None));
+                try!(self.check_not_descendant(destination_directory, directory_key.get_id()));
+                debug!("Moving {:?} directory to destination directory ...",
+                       directory_name);
+                let source_metadata = directory.get_metadata().clone();
+                // The directory is added to the destination before it leaves the source, so that
+                // a failed move can at worst leave it listed in both directories, never in neither
+                directory.get_mut_metadata().set_name(new_name.clone());
+                directory.get_mut_metadata()
+                         .set_parent_dir_key(Some(destination_directory.get_key().clone()));
+                try!(self.update_directory_listing(&directory));
+                destination_directory.upsert_sub_directory(directory.get_metadata().clone());
+                destination_directory.get_mut_metadata().set_modified_time(::time::now_utc());
+                if let Err(error) = self.update(destination_directory) {
+                    let _ = destination_directory.remove_sub_directory(&new_name);
+                    self.restore_moved_directory(&mut directory, &source_metadata);
+                    return Err(error);
+                }
+                try!(source_directory.remove_sub_directory(directory_name));
+                source_directory.get_mut_metadata().set_modified_time(::time::now_utc());
+                if let Err(error) = self.update(source_directory) {
+                    debug!("Moving {:?} directory failed, taking it out of destination \
+                            directory ...",
+                           directory_name);
+                    source_directory.upsert_sub_directory(source_metadata.clone());
+                    if destination_directory.remove_sub_directory(&new_name).is_ok() {
+                        destination_directory.get_mut_metadata()
+                                             .set_modified_time(::time::now_utc());
+                        if let Err(rollback_error) = self.update(destination_directory) {
+                            debug!("Taking {:?} directory out of destination directory failed: \
+                                    {:?}",
+                                   new_name,
+                                   rollback_error);
+                        }
+                    }
+                    self.restore_moved_directory(&mut directory, &source_metadata);
+                    return Err(error);
+                }
+            }
+            None => {
+                let new_name = try!(self.name_validator
+                                        .validate_directory_name(new_name,
+                                                                 source_directory,
+                                                                 Some(directory_key.get_id())));
+                debug!("Renaming {:?} directory to {:?} ...", directory_name, new_name);
+                directory.get_mut_metadata().set_name(new_name);
+                try!(self.update_directory_listing(&directory));
+                source_directory.upsert_sub_directory(directory.get_metadata().clone());
+                source_directory.get_mut_metadata().set_modified_time(::time::now_utc());
+                let _ = try!(self.update(source_directory));
+            }
+        }
+        Ok(())
+    }
+
+    // Gives a sub directory whose move failed its former name and parent back. A failure is only
+    // logged, so that the caller can return the error the move failed with.
+    fn restore_moved_directory(&self,
+                               directory: &mut DirectoryListing,
+                               source_metadata: &DirectoryMetadata) {
+        debug!("Moving {:?} directory failed, restoring its name and parent ...",
+               source_metadata.get_name());
+        directory.get_mut_metadata().set_name(source_metadata.get_name().clone());
+        directory.get_mut_metadata()
+                 .set_parent_dir_key(source_metadata.get_parent_dir_key().cloned());
+        if let Err(error) = self.update_directory_listing(directory) {
+            debug!("Restoring {:?} directory failed: {:?}",
+                   source_metadata.get_name(),
+                   error);
+        }
+    }
+
+    /// Deletes a sub directory along with its entire subtree.
+    /// Unlike `delete`, the StructuredData of every directory in the subtree, and the version
+    /// history of every file, is deleted from the network too. ImmutableData is content addressed
+    /// and could be shared with other files, so the versions and chunks released are only
+    /// reported.
+    /// Linked files lose a link, and their chunks are only reported if all the links are in the
+    /// subtree. Files whose link or version history cannot be removed once the directories are
+    /// deleted are reported along with the error instead of failing the delete.
+    /// If `dry_run` is true nothing is modified and the returned summary lists what would be
+    /// removed.
+    pub fn delete_recursively(&self,
+                              parent_directory: &mut DirectoryListing,
+                              directory_to_delete: &String,
+                              dry_run: bool)
+                              -> Result<DeletedDirectoryTree, NfsError> {
+        let directory_key =
+            try!(parent_directory.find_sub_directory(directory_to_delete)
+                                 .map(|metadata| metadata.get_key().clone())
+                                 .ok_or(NfsError::DirectoryNotFound(directory_to_delete.clone())));
+        let mut deleted = DeletedDirectoryTree::default();
+        let mut visited = HashSet::new();
+        let mut chunks = HashSet::new();
+        try!(self.collect_subtree(&directory_key, &mut deleted, &mut visited, &mut chunks));
+        let mut links = HashMap::<XorName, (File, u64)>::new();
+        for file in deleted.files.iter() {
+            if let Some(link_id) = file.get_metadata().get_link_id() {
+                links.entry(link_id.clone()).or_insert((file.clone(), 0)).1 += 1;
+            }
+        }
+        for (file, links_in_subtree) in links.values().cloned() {
+            if try!(link::get_link_count(&self.client, &file)) <= links_in_subtree {
+                for chunk in try!(link::resolve(&self.client, &file)).get_chunk_names() {
+                    if chunks.insert(chunk.clone()) {
+                        deleted.chunks.push(chunk);
+                    }
+                }
+            }
+        }
+        if dry_run {
+            return Ok(deleted);
+        }
+        let _ = try!(self.delete(parent_directory, directory_to_delete));
+        // Children are collected after their parents, so delete in reverse to never leave a
+        // reachable directory pointing at deleted data
+        for directory_key in deleted.directories.iter().rev() {
+            try!(self.delete_structured_data(directory_key));
+        }
+        // The directories are gone by now, so every file is cleaned up even if some fail
+        let mut cleanup_failures = Vec::new();
+        for file in deleted.files.iter() {
+            if file.get_metadata().get_link_id().is_some() {
+                if let Err(error) = link::remove_link(&self.client, file) {
+                    debug!("Removing link of {:?} file failed: {:?}", file.get_name(), error);
+                    cleanup_failures.push((file.clone(), format!("{:?}", error)));
+                    continue;
+                }
+            }
+            if let Err(error) = history::delete(&self.client, file.get_id()) {
+                debug!("Deleting history of {:?} file failed: {:?}", file.get_name(), error);
+                cleanup_failures.push((file.clone(), format!("{:?}", error)));
+            }
+        }
+        deleted.cleanup_failures = cleanup_failures;
+        Ok(deleted)
+    }
+
     /// Updates an existing DirectoryListing in the network.
     /// The parent_directory's parent is also updated and the same is returned
     /// Returns Option<parent_directory's parent>
@@ -111,6 +553,152 @@ impl DirectoryHelper {
         }
     }
 
+    /// Updates an existing versioned DirectoryListing in the network, recording `message` in the
+    /// version log along with the appended version. The update is committed even if recording
+    /// the version fails, so that failure is returned alongside instead of as an error.
+    /// The parent_directory's parent is also updated and the same is returned
+    /// Returns (Option<parent_directory's parent>, Option<error recording the version failed with>)
+    pub fn update_with_message(&self,
+                               directory: &DirectoryListing,
+                               message: String)
+                               -> Result<(Option<DirectoryListing>, Option<NfsError>), NfsError> {
+        if !directory.get_key().is_versioned() {
+            return Err(NfsError::ParameterIsNotValid("Only versions of versioned directories \
+                                                      carry a message"
+                                                         .to_string()));
+        }
+        let log_error = try!(self.update_directory_listing_with_message(directory,
+                                                                        Some(message)));
+        if let Some(parent_dir_key) = directory.get_metadata().get_parent_dir_key() {
+            let mut parent_directory = try!(self.get(&parent_dir_key));
+            parent_directory.upsert_sub_directory(directory.get_metadata().clone());
+            try!(self.update_directory_listing(&parent_directory));
+            Ok((Some(parent_directory), log_error))
+        } else {
+            Ok((None, log_error))
+        }
+    }
+
+    /// Updates an existing DirectoryListing in the network only if its StructuredData is still at
+    /// `expected_version`, i.e. the version returned by `get_with_version` when the listing was
+    /// read. Fails with `NfsError::ConcurrentModification` if someone else updated it meanwhile.
+    /// The parent_directory's parent is also updated and the same is returned
+    /// Returns (updated version, Option<parent_directory's parent>)
+    pub fn update_if_unchanged(&self,
+                               directory: &DirectoryListing,
+                               expected_version: u64)
+                               -> Result<(u64, Option<DirectoryListing>), NfsError> {
+        let structured_data = try!(self.get_structured_data(directory.get_key().get_id(),
+                                                            directory.get_key().get_type_tag()));
+        if structured_data.get_version() != expected_version {
+            debug!("Directory was modified concurrently - expected version {:?}, found {:?}",
+                   expected_version,
+                   structured_data.get_version());
+            return Err(NfsError::ConcurrentModification);
+        }
+        let updated_version = try!(self.post_if_unchanged(directory,
+                                                          structured_data,
+                                                          expected_version));
+        if let Some(parent_dir_key) = directory.get_metadata().get_parent_dir_key() {
+            let mut parent_directory = try!(self.get(&parent_dir_key));
+            parent_directory.upsert_sub_directory(directory.get_metadata().clone());
+            try!(self.update_directory_listing(&parent_directory));
+            Ok((updated_version, Some(parent_directory)))
+        } else {
+            Ok((updated_version, None))
+        }
+    }
+
+    /// Reads the latest DirectoryListing, applies `apply_changes` to it and saves it with
+    /// `update_if_unchanged`. On a concurrent modification the listing is re-read and the changes
+    /// are re-applied, up to `max_attempts` times in total.
+    /// Returns (updated directory, Option<parent_directory's parent>)
+    pub fn update_with_retry<F>(&self,
+                                directory_key: &DirectoryKey,
+                                max_attempts: u32,
+                                mut apply_changes: F)
+                                -> Result<(DirectoryListing, Option<DirectoryListing>), NfsError>
+        where F: FnMut(&mut DirectoryListing) -> Result<(), NfsError>
+    {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let (mut directory, version) = try!(self.get_with_version(directory_key));
+            try!(apply_changes(&mut directory));
+            match self.update_if_unchanged(&directory, version) {
+                Ok((_, parent_directory)) => return Ok((directory, parent_directory)),
+                Err(NfsError::ConcurrentModification) if attempt < max_attempts => {
+                    debug!("Retrying update after concurrent modification (attempt {:?}) ...",
+                           attempt);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Saves `ours`, a modified copy of `base` which was read at `base_version` using
+    /// `get_with_version`. If the directory was modified concurrently, the latest listing is
+    /// merged three-way with ours and the save is retried, up to `max_attempts` times in total.
+    /// Fails with `NfsError::MergeConflicts` if the changes cannot be reconciled.
+    /// Returns (saved directory, Option<parent_directory's parent>)
+    pub fn update_with_merge(&self,
+                             base: &DirectoryListing,
+                             ours: &DirectoryListing,
+                             base_version: u64,
+                             max_attempts: u32)
+                             -> Result<(DirectoryListing, Option<DirectoryListing>), NfsError> {
+        let mut base = base.clone();
+        let mut ours = ours.clone();
+        let mut version = base_version;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match self.update_if_unchanged(&ours, version) {
+                Ok((_, parent_directory)) => return Ok((ours, parent_directory)),
+                Err(NfsError::ConcurrentModification) if attempt < max_attempts => {
+                    debug!("Merging concurrent modification (attempt {:?}) ...", attempt);
+                    let (theirs, their_version) = try!(self.get_with_version(ours.get_key()));
+                    ours = try!(merge::merge(&base, &ours, &theirs)
+                                    .map_err(NfsError::MergeConflicts));
+                    base = theirs;
+                    version = their_version;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Updates an existing DirectoryListing in the network and propagates its metadata to every
+    /// ancestor by following the parent_dir_key links up to the root directory.
+    /// The walk stops at the first directory which has no parent.
+    /// Returns the updated ancestors, starting with the immediate parent and ending with the root
+    pub fn update_recursively(&self,
+                              directory: &DirectoryListing)
+                              -> Result<Vec<DirectoryListing>, NfsError> {
+        try!(self.update_directory_listing(directory));
+        let mut ancestors = Vec::new();
+        let mut visited = HashSet::new();
+        let _ = visited.insert(directory.get_key().get_id().clone());
+        let mut child_metadata = directory.get_metadata().clone();
+        loop {
+            let parent_dir_key = match child_metadata.get_parent_dir_key() {
+                Some(parent_dir_key) => parent_dir_key.clone(),
+                None => break,
+            };
+            if !visited.insert(parent_dir_key.get_id().clone()) {
+                return Err(NfsError::CircularDirectoryReference);
+            }
+            debug!("Propagating directory metadata to ancestor {:?} ...",
+                   parent_dir_key.get_id());
+            let mut parent_directory = try!(self.get(&parent_dir_key));
+            parent_directory.upsert_sub_directory(child_metadata);
+            try!(self.update_directory_listing(&parent_directory));
+            child_metadata = parent_directory.get_metadata().clone();
+            ancestors.push(parent_directory);
+        }
+        Ok(ancestors)
+    }
+
     /// Return the versions of the directory
     pub fn get_versions(&self,
                         directory_id: &XorName,
@@ -121,6 +709,106 @@ impl DirectoryHelper {
                                             &structured_data)))
     }
 
+    /// Return the timestamp, signer and message of each version of a versioned directory, in the
+    /// order of `get_versions`. Versions without a record are left out: those appended before the
+    /// directory kept a version log, those whose recording failed and those whose record was
+    /// dropped once the log was full.
+    pub fn get_version_log(&self,
+                           directory_key: &DirectoryKey)
+                           -> Result<Vec<VersionRecord>, NfsError> {
+        if !directory_key.is_versioned() {
+            return Err(NfsError::ParameterIsNotValid("Only versioned directories have a version \
+                                                      log"
+                                                         .to_string()));
+        }
+        let versions = try!(self.get_versions(directory_key.get_id(),
+                                              directory_key.get_type_tag()));
+        let records = try!(version_log::get(&self.client, directory_key));
+        Ok(DirectoryHelper::match_version_records(&versions, &records)
+               .into_iter()
+               .filter_map(|record| record)
+               .collect())
+    }
+
+    /// Drops the versions of a versioned directory which are not kept by the retention policy of
+    /// the directory. Their records are removed from the version log as well. ImmutableData is
+    /// content addressed and could be shared, so the ImmutableData released is only reported.
+    /// Versions without a record in the version log are dated by the modified time of their
+    /// listing and reported as undated. A directory without a retention policy keeps all its
+    /// versions. If `dry_run` is true nothing is changed, and the returned summary lists what
+    /// would be dropped.
+    pub fn prune_versions(&self,
+                          directory_key: &DirectoryKey,
+                          dry_run: bool)
+                          -> Result<PrunedVersions, NfsError> {
+        if !directory_key.is_versioned() {
+            return Err(NfsError::ParameterIsNotValid("Only versioned directories have versions to \
+                                                      prune"
+                                                         .to_string()));
+        }
+        let directory = try!(self.get(directory_key));
+        let retention_policy = match directory.get_metadata().get_retention_policy() {
+            Some(retention_policy) => retention_policy.clone(),
+            None => return Ok(PrunedVersions::default()),
+        };
+        let structured_data = try!(self.get_structured_data(directory_key.get_id(),
+                                                            directory_key.get_type_tag()));
+        let versions = try!(versioned::get_all_versions(&mut *unwrap_result!(self.client.lock()),
+                                                        &structured_data));
+        let records = try!(version_log::get(&self.client, directory_key));
+        let records = DirectoryHelper::match_version_records(&versions, &records);
+        let mut timestamps = Vec::with_capacity(versions.len());
+        let mut undated = Vec::new();
+        for (version, record) in versions.iter().zip(records.iter()) {
+            let timestamp = match *record {
+                Some(ref record) => record.get_timestamp().clone(),
+                None => {
+                    undated.push(version.clone());
+                    try!(self.get_by_version(directory_key.get_id(),
+                                             directory_key.get_access_level(),
+                                             version.clone()))
+                        .get_metadata()
+                        .get_modified_time()
+                        .clone()
+                }
+            };
+            timestamps.push(timestamp);
+        }
+        let kept = retention_policy.get_kept_versions(&timestamps, &::time::now_utc());
+        let kept_versions = kept.iter()
+                                .map(|&position| versions[position].clone())
+                                .collect::<Vec<_>>();
+        let dropped_versions = (0..versions.len())
+                                   .filter(|position| !kept.contains(position))
+                                   .map(|position| versions[position].clone())
+                                   .collect::<Vec<_>>();
+        // Saving the same listing twice yields the same ImmutableData, which may still be kept
+        let mut seen = kept_versions.iter().cloned().collect::<HashSet<_>>();
+        let released = dropped_versions.iter()
+                                       .filter(|version| seen.insert((*version).clone()))
+                                       .cloned()
+                                       .collect();
+        let pruned_versions = PrunedVersions {
+            versions: dropped_versions,
+            released: released,
+            undated: undated,
+        };
+        if dry_run || pruned_versions.is_empty() {
+            return Ok(pruned_versions);
+        }
+        let pruned_structured_data = try!(storage::replace_versions(&self.client,
+                                                                    &structured_data,
+                                                                    &kept_versions));
+        debug!("Posting pruned versions of directory ...");
+        try!(unwrap_result!(self.client.lock())
+                 .post(Data::Structured(pruned_structured_data), None));
+        let kept_records = kept.iter()
+                               .filter_map(|&position| records[position].clone())
+                               .collect();
+        try!(version_log::replace(&self.client, directory_key, &kept_records));
+        Ok(pruned_versions)
+    }
+
     /// Return the DirectoryListing for the specified version
     pub fn get_by_version(&self,
                           directory_id: &XorName,
@@ -138,19 +826,162 @@ impl DirectoryHelper {
         }
     }
 
+    /// Makes a previous version of a versioned directory current again by appending its state as
+    /// a new version. History is never rewritten, so the versions in between remain available.
+    /// The name of the directory and its place in the tree are kept, and the parent is updated.
+    /// Sub directories deleted from the network since that version are left out. Files removed
+    /// since then come back with a history starting at the restored state, as their earlier
+    /// history was deleted along with them.
+    /// Returns (restored DirectoryListing, Option<restored directory's parent>)
+    pub fn restore_version(&self,
+                           directory_key: &DirectoryKey,
+                           version: XorName)
+                           -> Result<(DirectoryListing, Option<DirectoryListing>), NfsError> {
+        if !directory_key.is_versioned() {
+            return Err(NfsError::ParameterIsNotValid("Only versioned directories have versions to \
+                                                      restore"
+                                                         .to_string()));
+        }
+        let versions = try!(self.get_versions(directory_key.get_id(),
+                                              directory_key.get_type_tag()));
+        if !versions.contains(&version) {
+            return Err(NfsError::ParameterIsNotValid(format!("{:?} is not a version of the \
+                                                              directory",
+                                                             version)));
+        }
+        let current = try!(self.get(directory_key));
+        let mut restored = try!(self.get_by_version(directory_key.get_id(),
+                                                    directory_key.get_access_level(),
+                                                    version));
+        {
+            let metadata = restored.get_mut_metadata();
+            metadata.set_name(current.get_metadata().get_name().clone());
+            metadata.set_parent_dir_key(current.get_metadata().get_parent_dir_key().cloned());
+            metadata.set_modified_time(::time::now_utc());
+        }
+        // Linked files coming back take a link again. Their shared state may be gone along with
+        // its last link, in which case they are restored as files of their own.
+        for file in restored.get_mut_files().iter_mut() {
+            if file.get_metadata().get_link_id().is_some() &&
+               current.find_file_by_id(file.get_id()).is_none() &&
+               link::add_link(&self.client, file).is_err() {
+                file.get_mut_metadata().set_link_id(None);
+            }
+        }
+        let mut deleted_sub_directories = Vec::new();
+        for sub_directory in restored.get_sub_directories() {
+            let key = sub_directory.get_key();
+            if current.find_sub_directory_by_id(key.get_id()).is_none() &&
+               try!(storage::find_structured_data(&self.client,
+                                                  key.get_id(),
+                                                  key.get_type_tag()))
+                   .is_none() {
+                deleted_sub_directories.push(sub_directory.get_name().clone());
+            }
+        }
+        for name in deleted_sub_directories {
+            debug!("Leaving out deleted sub directory {:?} ...", name);
+            try!(restored.remove_sub_directory(&name));
+        }
+        debug!("Restoring version {:?} of directory ...", version);
+        let updated_parent = try!(self.update(&restored));
+        for file in current.get_files() {
+            if file.get_metadata().get_link_id().is_some() &&
+               restored.find_file_by_id(file.get_id()).is_none() {
+                let _ = try!(link::remove_link(&self.client, file));
+            }
+        }
+        for file in restored.get_files() {
+            if current.find_file_by_id(file.get_id()).is_none() {
+                try!(history::record(&self.client, file));
+            }
+        }
+        Ok((restored, updated_parent))
+    }
+
+    /// Compares two versions of a versioned directory, as returned by `get_versions`.
+    /// Entries are matched by their ids, so renamed entries are told apart from removed and added
+    /// ones. If `recursive` is true, the sub directories found in both versions are compared as
+    /// they were when each version was saved, as dated by the version logs. Versions without a
+    /// record are dated by the modified time cached in the parent. Only the history of versioned
+    /// sub directories is kept, so unversioned ones are not descended into. Linked files are
+    /// compared as they were saved with each version, which holds the content shared by their
+    /// links at that time.
+    pub fn diff_versions(&self,
+                         directory_key: &DirectoryKey,
+                         from_version: XorName,
+                         to_version: XorName,
+                         recursive: bool)
+                         -> Result<DirectoryDiff, NfsError> {
+        if !directory_key.is_versioned() {
+            return Err(NfsError::ParameterIsNotValid("Only versioned directories have versions to \
+                                                      compare"
+                                                         .to_string()));
+        }
+        let versions = try!(self.get_versions(directory_key.get_id(),
+                                              directory_key.get_type_tag()));
+        for version in &[&from_version, &to_version] {
+            if !versions.contains(*version) {
+                return Err(NfsError::ParameterIsNotValid(format!("{:?} is not a version of the \
+                                                                  directory",
+                                                                 version)));
+            }
+        }
+        let from = try!(self.get_by_version(directory_key.get_id(),
+                                            directory_key.get_access_level(),
+                                            from_version));
+        let to = try!(self.get_by_version(directory_key.get_id(),
+                                          directory_key.get_access_level(),
+                                          to_version));
+        if recursive {
+            let dated_versions = try!(self.date_versions(directory_key, versions));
+            let timestamp_of = |version: &XorName| {
+                dated_versions.iter()
+                              .find(|&&(ref dated_version, _)| dated_version == version)
+                              .and_then(|&(_, ref timestamp)| timestamp.clone())
+            };
+            let timestamps = (timestamp_of(&from_version), timestamp_of(&to_version));
+            let mut visited = HashSet::new();
+            let _ = visited.insert(directory_key.get_id().clone());
+            self.diff_recursively(&from,
+                                  &to,
+                                  timestamps,
+                                  &mut VersionCache::default(),
+                                  &mut visited)
+        } else {
+            Ok(diff::diff(&from, &to))
+        }
+    }
+
     /// Return the DirectoryListing for the latest version
     pub fn get(&self, directory_key: &DirectoryKey) -> Result<DirectoryListing, NfsError> {
+        let (directory, _) = try!(self.get_with_version(directory_key));
+        Ok(directory)
+    }
+
+    /// Return the DirectoryListing for the latest version along with the version of the
+    /// StructuredData it was read from. The version can be passed to `update_if_unchanged` to
+    /// detect concurrent modifications.
+    pub fn get_with_version(&self,
+                            directory_key: &DirectoryKey)
+                            -> Result<(DirectoryListing, u64), NfsError> {
         let directory_id = directory_key.get_id();
         let type_tag = directory_key.get_type_tag();
         let versioned = directory_key.is_versioned();
         let access_level = directory_key.get_access_level();
 
-        if versioned {
-            let versions = try!(self.get_versions(directory_id, type_tag));
+        let structured_data = try!(self.get_structured_data(directory_id, type_tag));
+        let structured_data_version = structured_data.get_version();
+        let mut directory = if DirectoryHelper::is_sharded(directory_key) {
+            try!(self.get_sharded_directory_listing(directory_key, &structured_data))
+        } else if versioned {
+            let versions = try!(versioned::get_all_versions(&mut *unwrap_result!(self.client
+                                                                                     .lock()),
+                                                            &structured_data));
             let latest_version = try!(versions.last().ok_or(NfsError::from("Programming Error \
                                                                             - Please report \
                                                                             this as a Bug.")));
-            self.get_by_version(directory_id, access_level, *latest_version)
+            try!(self.get_by_version(directory_id, access_level, *latest_version))
         } else {
             let private_key;
             let secret_key;
@@ -171,12 +1002,194 @@ impl DirectoryHelper {
                 ::AccessLevel::Public => None,
             };
 
-            let structured_data = try!(self.get_structured_data(directory_id, type_tag));
             let serialised_directory_listing = try!(unversioned::get_data(self.client.clone(),
                                                                           &structured_data,
                                                                           encryption_keys));
-            Ok(try!(deserialise(&serialised_directory_listing)))
+            try!(deserialise(&serialised_directory_listing))
+        };
+        try!(self.resolve_links(&mut directory));
+        Ok((directory, structured_data_version))
+    }
+
+    /// Resolves a slash separated path (e.g. `/Home/Pictures/2016`) to a DirectoryListing.
+    /// The path is resolved relative to the directory represented by `start_directory_key` or
+    /// relative to the user's root directory if `None` is passed.
+    /// An empty path (or `/`) resolves to the starting directory itself.
+    /// `.` and `..` components are honoured and symlinks are followed, failing with
+    /// `SymlinkLoop` after `MAX_SYMLINK_HOPS` symlinks.
+    pub fn get_by_path(&self,
+                       path: &str,
+                       start_directory_key: Option<&DirectoryKey>)
+                       -> Result<DirectoryListing, NfsError> {
+        let mut resolution = try!(self.start_path_resolution(start_directory_key));
+        try!(self.resolve_path(&mut resolution, DirectoryHelper::split_path(path)));
+        Ok(resolution.into_current())
+    }
+
+    /// Resolves a slash separated path (e.g. `/Home/Pictures/2016/a.jpg`) to a File.
+    /// The path is resolved relative to the directory represented by `start_directory_key` or
+    /// relative to the user's root directory if `None` is passed.
+    /// Symlinks are followed, including a symlink in place of the file itself.
+    /// Returns (file, file's parent_directory)
+    pub fn get_file_by_path(&self,
+                            path: &str,
+                            start_directory_key: Option<&DirectoryKey>)
+                            -> Result<(File, DirectoryListing), NfsError> {
+        let mut components = DirectoryHelper::split_path(path);
+        let mut file_name = match components.pop() {
+            Some(file_name) => file_name.to_string(),
+            None => return Err(NfsError::ParameterIsNotValid("Path has no file name".to_string())),
+        };
+        let mut resolution = try!(self.start_path_resolution(start_directory_key));
+        try!(self.resolve_path(&mut resolution, components));
+        loop {
+            let (file, symlink_target) = {
+                let directory = resolution.current();
+                (directory.find_file(&file_name).map(|file| file.clone()),
+                 directory.find_symlink(&file_name)
+                          .map(|symlink| symlink.get_target().to_string()))
+            };
+            if let Some(file) = file {
+                return Ok((file, resolution.into_current()));
+            }
+            let target = match symlink_target {
+                Some(target) => target,
+                None => {
+                    resolution.path.push(file_name);
+                    let path = DirectoryHelper::join_path(&resolution.path);
+                    return Err(NfsError::FileNotFound(path));
+                }
+            };
+            try!(resolution.follow_symlink(&file_name, &target));
+            let mut target_components = DirectoryHelper::split_path(&target);
+            file_name = match target_components.pop() {
+                Some(target_file_name) => target_file_name.to_string(),
+                None => return Err(NfsError::FileNotFound(target.clone())),
+            };
+            try!(self.resolve_path(&mut resolution, target_components));
+        }
+    }
+
+    /// Lists up to `limit` files of the directory, starting at `cursor` or at the first file if
+    /// `None` is passed. For sharded directories only the shards holding the page are fetched.
+    /// A page can hold fewer than `limit` files even when more pages follow.
+    /// Returns (files, Option<cursor of the next page>), the cursor is `None` after the last page
+    pub fn list_files(&self,
+                      directory_key: &DirectoryKey,
+                      cursor: Option<&ListingCursor>,
+                      limit: usize)
+                      -> Result<(Vec<File>, Option<ListingCursor>), NfsError> {
+        if limit == 0 {
+            return Err(NfsError::ParameterIsNotValid("Limit can not be zero".to_string()));
+        }
+        let mut position = cursor.cloned().unwrap_or_default();
+        if !DirectoryHelper::is_sharded(directory_key) {
+            let directory = try!(self.get(directory_key));
+            let files: Vec<File> = directory.get_files()
+                                            .iter()
+                                            .skip(position.offset)
+                                            .take(limit)
+                                            .cloned()
+                                            .collect();
+            position.offset += files.len();
+            let next_cursor = if position.offset < directory.get_files().len() {
+                Some(position)
+            } else {
+                None
+            };
+            return Ok((files, next_cursor));
+        }
+
+        let structured_data = try!(self.get_structured_data(directory_key.get_id(),
+                                                            directory_key.get_type_tag()));
+        let head = try!(self.get_sharded_listing_head(directory_key, &structured_data));
+        let shard_count = head.get_shard_count();
+        let mut files = Vec::with_capacity(limit);
+        while position.shard < shard_count && files.len() < limit {
+            let shard: Vec<File> = try!(self.get_shard(directory_key, &head, position.shard))
+                                       .get_files()
+                                       .iter()
+                                       .filter(|file| {
+                                           file.get_metadata().get_symlink_target().is_none()
+                                       })
+                                       .cloned()
+                                       .collect();
+            let shard_length = shard.len();
+            let taken = cmp::min(limit - files.len(),
+                                 shard_length.saturating_sub(position.offset));
+            for file in shard.into_iter().skip(position.offset).take(taken) {
+                files.push(try!(link::resolve(&self.client, &file)));
+            }
+            position.offset += taken;
+            if position.offset < shard_length {
+                return Ok((files, Some(position)));
+            }
+            position.shard += 1;
+            position.offset = 0;
+        }
+        let next_cursor = if position.shard < shard_count {
+            Some(position)
+        } else {
+            None
+        };
+        Ok((files, next_cursor))
+    }
+
+    /// Returns the file with the given name in the directory, if there is one. For sharded
+    /// directories only the head and the shard holding the name are fetched.
+    pub fn find_file(&self,
+                     directory_key: &DirectoryKey,
+                     file_name: &String)
+                     -> Result<Option<File>, NfsError> {
+        if !DirectoryHelper::is_sharded(directory_key) {
+            return Ok(try!(self.get(directory_key)).find_file(file_name).cloned());
+        }
+        let structured_data = try!(self.get_structured_data(directory_key.get_id(),
+                                                            directory_key.get_type_tag()));
+        let head = try!(self.get_sharded_listing_head(directory_key, &structured_data));
+        let index = shard::get_shard_index(file_name, head.get_shard_count());
+        let shard = try!(self.get_shard(directory_key, &head, index));
+        match shard.get_files().iter().find(|file| {
+            file.get_name() == file_name && file.get_metadata().get_symlink_target().is_none()
+        }) {
+            Some(file) => Ok(Some(try!(link::resolve(&self.client, file)))),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns an iterator over the directory represented by `directory_key` and its entire
+    /// subtree, visited in the given order. A directory which can not be fetched is reported as
+    /// an error for its path and the walk carries on with the rest of the tree.
+    pub fn walk(&self, directory_key: &DirectoryKey, order: WalkOrder) -> Walker {
+        Walker::new(self.client.clone(), directory_key.clone(), order)
+    }
+
+    /// Computes the storage consumed by the directory represented by `directory_key` and its
+    /// entire subtree. If `include_versions` is true, the files of the historical versions of
+    /// versioned directories are accounted for too. Chunks shared between files are counted once.
+    pub fn get_usage(&self,
+                     directory_key: &DirectoryKey,
+                     include_versions: bool)
+                     -> Result<DirectoryUsage, NfsError> {
+        let mut usage = DirectoryUsage::default();
+        let mut chunks = HashSet::new();
+        for entry in self.walk(directory_key, WalkOrder::DepthFirst) {
+            match try!(entry.map_err(WalkError::into_error)) {
+                WalkEntry::Directory(_, directory) => {
+                    usage.directory_count += 1;
+                    if include_versions && directory.get_key().is_versioned() {
+                        try!(self.add_versions_usage(&directory, &mut usage, &mut chunks));
+                    }
+                }
+                WalkEntry::File(_, file) => {
+                    usage.file_count += 1;
+                    usage.size += file.get_metadata().get_size();
+                    DirectoryHelper::add_chunks_usage(&file, &mut usage, &mut chunks);
+                }
+                WalkEntry::Symlink(..) => (),
+            }
         }
+        Ok(usage)
     }
 
     /// Returns the Root Directory
@@ -239,13 +1252,12 @@ impl DirectoryHelper {
                 created_directory
             }
         };
-        match config_directory_listing.get_sub_directories()
-                                      .iter()
-                                      .position(|metadata| *metadata.get_name() == directory_name) {
-            Some(index) => {
+        let directory_key = config_directory_listing.find_sub_directory(&directory_name)
+                                                    .map(|metadata| metadata.get_key().clone());
+        match directory_key {
+            Some(directory_key) => {
                 debug!("Retrieving {:?} specific configuration directory ...",
                        directory_name);
-                let directory_key = config_directory_listing.get_sub_directories()[index].get_key();
                 Ok(try!(self.get(&directory_key)))
             }
             None => {
@@ -262,31 +1274,405 @@ impl DirectoryHelper {
         }
     }
 
-    /// Creates a StructuredData in the Network
-    /// The StructuredData is created based on the version and AccessLevel of the DirectoryListing
-    fn save_directory_listing(&self,
-                              directory: &DirectoryListing)
-                              -> Result<StructuredData, NfsError> {
-        let signing_key = try!(unwrap_result!(self.client.lock()).get_secret_signing_key()).clone();
-        let owner_key = try!(unwrap_result!(self.client.lock()).get_public_signing_key()).clone();
-        let access_level = directory.get_key().get_access_level();
-        let versioned = directory.get_key().is_versioned();
+    fn copy_subtree(&self,
+                    source_directory_key: &DirectoryKey,
+                    name: String,
+                    parent_dir_key: &DirectoryKey,
+                    access_level: &Option<::AccessLevel>,
+                    versioned: Option<bool>)
+                    -> Result<DirectoryListing, NfsError> {
+        let source_directory = try!(self.get(source_directory_key));
+        let tag_type = match versioned {
+            Some(true) => ::VERSIONED_DIRECTORY_LISTING_TAG,
+            Some(false) => ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+            None => source_directory_key.get_type_tag(),
+        };
+        let access_level_of_copy = match *access_level {
+            Some(ref access_level) => access_level.clone(),
+            None => source_directory_key.get_access_level().clone(),
+        };
+        let mut copied_directory =
+            try!(DirectoryListing::new(name,
+                                       tag_type,
+                                       source_directory.get_metadata()
+                                                       .get_user_metadata()
+                                                       .clone(),
+                                       versioned.unwrap_or(source_directory_key.is_versioned()),
+                                       access_level_of_copy,
+                                       Some(parent_dir_key.clone())));
+        for file in source_directory.get_files() {
+            // Copies of linked files are not linked to the originals
+            let file = try!(link::resolve(&self.client, file));
+            let mut metadata = file.get_metadata().clone();
+            metadata.set_link_id(None);
+            copied_directory.get_mut_files()
+                            .push(try!(File::new(metadata, file.get_datamap().clone())));
+        }
+        for symlink in source_directory.get_symlinks() {
+            copied_directory.upsert_symlink(try!(Symlink::new(symlink.get_name().clone(),
+                                                              symlink.get_target().to_string())));
+        }
+        let structured_data = try!(self.save_directory_listing(&copied_directory));
+        debug!("Posting PUT request to network to save structured data for copied directory ...");
+        try!(unwrap_result!(self.client.lock()).put(Data::Structured(structured_data), None));
 
-        if versioned {
-            let serialised_data = match *access_level {
-                ::AccessLevel::Private => try!(directory.encrypt(self.client.clone())),
-                ::AccessLevel::Public => try!(serialise(&directory)),
+        if !source_directory.get_sub_directories().is_empty() {
+            for sub_directory in source_directory.get_sub_directories() {
+                let copied_sub_directory = try!(self.copy_subtree(sub_directory.get_key(),
+                                                                  sub_directory.get_name()
+                                                                               .clone(),
+                                                                  copied_directory.get_key(),
+                                                                  access_level,
+                                                                  versioned));
+                copied_directory.upsert_sub_directory(copied_sub_directory.get_metadata()
+                                                                          .clone());
+            }
+            try!(self.update_directory_listing(&copied_directory));
+        }
+        Ok(copied_directory)
+    }
+
+    // `timestamps` are those of the records of the `from` and `to` versions in the version log.
+    // The state of a sub directory in a version of its parent is the latest version of the sub
+    // directory recorded before the parent's. Versions without a record fall back on the modified
+    // time the parent caches for the sub directory.
+    fn diff_recursively(&self,
+                        from: &DirectoryListing,
+                        to: &DirectoryListing,
+                        timestamps: (Option<Tm>, Option<Tm>),
+                        cache: &mut VersionCache,
+                        visited: &mut HashSet<XorName>)
+                        -> Result<DirectoryDiff, NfsError> {
+        diff::diff_recursively(from, to, |older, newer| {
+            if older == newer || !newer.is_versioned() {
+                return Ok(None);
+            }
+            if !visited.insert(newer.get_id().clone()) {
+                return Err(NfsError::CircularDirectoryReference);
+            }
+            let key = newer.get_key();
+            let cached_versions = cache.versions.get(key.get_id()).cloned();
+            let versions = match cached_versions {
+                Some(versions) => versions,
+                None => {
+                    let versions = try!(self.get_versions(key.get_id(), key.get_type_tag()));
+                    let versions = try!(self.date_versions(key, versions));
+                    let _ = cache.versions.insert(key.get_id().clone(), versions.clone());
+                    versions
+                }
             };
-            let version = try!(self.save_as_immutable_data(serialised_data,
-                                                           ImmutableDataType::Normal));
-            Ok(try!(versioned::create(&*unwrap_result!(self.client.lock()),
-                                      version,
-                                      directory.get_key().get_type_tag(),
-                                      directory.get_key().get_id().clone(),
-                                      0,
-                                      vec![owner_key],
-                                      Vec::new(),
-                                      &signing_key)))
+            let older_version = try!(self.find_version_at(key,
+                                                          &versions,
+                                                          &timestamps.0,
+                                                          older.get_modified_time(),
+                                                          &mut cache.listings));
+            let newer_version = try!(self.find_version_at(key,
+                                                          &versions,
+                                                          &timestamps.1,
+                                                          newer.get_modified_time(),
+                                                          &mut cache.listings));
+            let sub_directory_diff = match (older_version, newer_version) {
+                (Some(older_version), Some(newer_version)) => {
+                    if older_version.0 == newer_version.0 {
+                        None
+                    } else {
+                        let older_listing = try!(self.get_cached_version(key,
+                                                                         older_version.0,
+                                                                         &mut cache.listings));
+                        let newer_listing = try!(self.get_cached_version(key,
+                                                                         newer_version.0,
+                                                                         &mut cache.listings));
+                        Some(try!(self.diff_recursively(&older_listing,
+                                                        &newer_listing,
+                                                        (older_version.1, newer_version.1),
+                                                        cache,
+                                                        visited)))
+                    }
+                }
+                _ => None,
+            };
+            let _ = visited.remove(newer.get_id());
+            Ok(sub_directory_diff)
+        })
+    }
+
+    // Pairs each version of a versioned directory with the timestamp of its record in the version
+    // log, if it has one
+    fn date_versions(&self,
+                     directory_key: &DirectoryKey,
+                     versions: Vec<XorName>)
+                     -> Result<Vec<(XorName, Option<Tm>)>, NfsError> {
+        let records = try!(version_log::get(&self.client, directory_key));
+        let timestamps = DirectoryHelper::match_version_records(&versions, &records)
+                             .into_iter()
+                             .map(|record| record.map(|record| record.get_timestamp().clone()))
+                             .collect::<Vec<_>>();
+        Ok(versions.into_iter().zip(timestamps.into_iter()).collect())
+    }
+
+    // Finds the latest of the dated `versions` of a sub directory which was saved when its parent
+    // was saved at `parent_timestamp`, or with `modified_time` cached for the sub directory
+    fn find_version_at(&self,
+                       directory_key: &DirectoryKey,
+                       versions: &Vec<(XorName, Option<Tm>)>,
+                       parent_timestamp: &Option<Tm>,
+                       modified_time: &Tm,
+                       listings: &mut HashMap<XorName, DirectoryListing>)
+                       -> Result<Option<(XorName, Option<Tm>)>, NfsError> {
+        for &(ref version, ref timestamp) in versions.iter().rev() {
+            let saved_in_time = match (timestamp.as_ref(), parent_timestamp.as_ref()) {
+                (Some(timestamp), Some(parent_timestamp)) => timestamp <= parent_timestamp,
+                _ => {
+                    let listing = try!(self.get_cached_version(directory_key,
+                                                               version.clone(),
+                                                               listings));
+                    listing.get_metadata().get_modified_time() <= modified_time
+                }
+            };
+            if saved_in_time {
+                return Ok(Some((version.clone(), timestamp.clone())));
+            }
+        }
+        Ok(None)
+    }
+
+    fn get_cached_version(&self,
+                          directory_key: &DirectoryKey,
+                          version: XorName,
+                          listings: &mut HashMap<XorName, DirectoryListing>)
+                          -> Result<DirectoryListing, NfsError> {
+        if let Some(listing) = listings.get(&version) {
+            return Ok(listing.clone());
+        }
+        let listing = try!(self.get_by_version(directory_key.get_id(),
+                                               directory_key.get_access_level(),
+                                               version.clone()));
+        let _ = listings.insert(version, listing.clone());
+        Ok(listing)
+    }
+
+    fn collect_subtree(&self,
+                       directory_key: &DirectoryKey,
+                       deleted: &mut DeletedDirectoryTree,
+                       visited: &mut HashSet<XorName>,
+                       chunks: &mut HashSet<XorName>)
+                       -> Result<(), NfsError> {
+        if !visited.insert(directory_key.get_id().clone()) {
+            return Err(NfsError::CircularDirectoryReference);
+        }
+        if directory_key.is_versioned() {
+            deleted.versions.extend(try!(self.get_versions(directory_key.get_id(),
+                                                           directory_key.get_type_tag())));
+        }
+        let directory = try!(self.get(directory_key));
+        deleted.directories.push(directory_key.clone());
+        for file in directory.get_files() {
+            // Chunks of linked files are accounted for once all their links have been collected
+            if file.get_metadata().get_link_id().is_none() {
+                for chunk in file.get_chunk_names() {
+                    if chunks.insert(chunk.clone()) {
+                        deleted.chunks.push(chunk);
+                    }
+                }
+            }
+            deleted.files.push(file.clone());
+        }
+        for sub_directory in directory.get_sub_directories() {
+            try!(self.collect_subtree(sub_directory.get_key(), deleted, visited, chunks));
+        }
+        Ok(())
+    }
+
+    /// Deletes the StructuredData backing a directory, including its shards, from the network
+    fn delete_structured_data(&self, directory_key: &DirectoryKey) -> Result<(), NfsError> {
+        let structured_data = try!(self.get_structured_data(directory_key.get_id(),
+                                                            directory_key.get_type_tag()));
+        if DirectoryHelper::is_sharded(directory_key) {
+            let head = try!(self.get_sharded_listing_head(directory_key, &structured_data));
+            for index in 0..head.get_shard_count() {
+                try!(self.delete_shard(directory_key, &head, index));
+            }
+        }
+        if directory_key.is_versioned() {
+            try!(version_log::delete(&self.client, directory_key));
+        }
+        debug!("Deleting structured data of directory {:?} ...",
+               directory_key.get_id());
+        self.delete_unversioned_data(directory_key.get_id().clone(),
+                                     directory_key.get_type_tag(),
+                                     structured_data.get_version())
+    }
+
+    /// Deletes a StructuredData currently at `version` by posting a DELETE for its successor
+    fn delete_unversioned_data(&self,
+                               id: XorName,
+                               type_tag: u64,
+                               version: u64)
+                               -> Result<(), NfsError> {
+        storage::delete_unversioned_data(&self.client, id, type_tag, version)
+    }
+
+    fn add_versions_usage(&self,
+                          directory: &DirectoryListing,
+                          usage: &mut DirectoryUsage,
+                          chunks: &mut HashSet<XorName>)
+                          -> Result<(), NfsError> {
+        let directory_key = directory.get_key();
+        let versions = try!(self.get_versions(directory_key.get_id(),
+                                              directory_key.get_type_tag()));
+        let current_files: BTreeSet<&File> = directory.get_files().iter().collect();
+        let mut counted_files = BTreeSet::new();
+        // The last version is the latest listing, which the walk accounts for
+        for version in versions.iter().take(versions.len().saturating_sub(1)) {
+            usage.version_count += 1;
+            let listing = try!(self.get_by_version(directory_key.get_id(),
+                                                   directory_key.get_access_level(),
+                                                   *version));
+            for file in listing.get_files() {
+                if !current_files.contains(file) && counted_files.insert(file.clone()) {
+                    usage.versions_size += file.get_metadata().get_size();
+                    DirectoryHelper::add_chunks_usage(file, usage, chunks);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_chunks_usage(file: &File, usage: &mut DirectoryUsage, chunks: &mut HashSet<XorName>) {
+        for (name, size) in file.get_chunk_sizes() {
+            if chunks.insert(name) {
+                usage.chunk_count += 1;
+                usage.chunks_size += size;
+            }
+        }
+    }
+
+    /// Fails if the directory identified by `ancestor_id` is the directory itself or one of its
+    /// ancestors, as moving it there would detach it from the root.
+    fn check_not_descendant(&self,
+                            directory: &DirectoryListing,
+                            ancestor_id: &XorName)
+                            -> Result<(), NfsError> {
+        let mut visited = HashSet::new();
+        let mut current_key = directory.get_key().clone();
+        let mut parent_dir_key = directory.get_metadata()
+                                          .get_parent_dir_key()
+                                          .map(|key| key.clone());
+        loop {
+            if current_key.get_id() == ancestor_id ||
+               !visited.insert(current_key.get_id().clone()) {
+                return Err(NfsError::CircularDirectoryReference);
+            }
+            current_key = match parent_dir_key {
+                Some(key) => key,
+                None => return Ok(()),
+            };
+            parent_dir_key = try!(self.get(&current_key))
+                                 .get_metadata()
+                                 .get_parent_dir_key()
+                                 .map(|key| key.clone());
+        }
+    }
+
+    fn start_path_resolution(&self,
+                             start_directory_key: Option<&DirectoryKey>)
+                             -> Result<PathResolution, NfsError> {
+        let directory = match start_directory_key {
+            Some(directory_key) => try!(self.get(directory_key)),
+            None => try!(self.get_user_root_directory_listing()),
+        };
+        Ok(PathResolution::new(directory))
+    }
+
+    /// Walks the path components one sub directory at a time, expanding symlinks in place.
+    /// On failure the path up to and including the missing component is reported, with the
+    /// symlinks followed so far replaced by their targets.
+    fn resolve_path(&self,
+                    resolution: &mut PathResolution,
+                    components: Vec<&str>)
+                    -> Result<(), NfsError> {
+        let mut pending: VecDeque<String> = components.into_iter()
+                                                      .map(|component| component.to_string())
+                                                      .collect();
+        while let Some(component) = pending.pop_front() {
+            if component == "." {
+                continue;
+            }
+            if component == ".." {
+                resolution.leave();
+                continue;
+            }
+            let (directory_key, symlink_target) = {
+                let directory = resolution.current();
+                (directory.find_sub_directory(&component)
+                          .map(|metadata| metadata.get_key().clone()),
+                 directory.find_symlink(&component)
+                          .map(|symlink| symlink.get_target().to_string()))
+            };
+            if let Some(directory_key) = directory_key {
+                debug!("Resolving path component {:?} ...", component);
+                let directory = try!(self.get(&directory_key));
+                resolution.enter(component, directory);
+            } else if let Some(target) = symlink_target {
+                debug!("Following symlink {:?} to {:?} ...", component, target);
+                try!(resolution.follow_symlink(&component, &target));
+                for target_component in DirectoryHelper::split_path(&target).into_iter().rev() {
+                    pending.push_front(target_component.to_string());
+                }
+            } else {
+                resolution.path.push(component);
+                let path = DirectoryHelper::join_path(&resolution.path);
+                return Err(NfsError::DirectoryNotFound(path));
+            }
+        }
+        Ok(())
+    }
+
+    fn split_path(path: &str) -> Vec<&str> {
+        path.split('/').filter(|component| !component.is_empty()).collect()
+    }
+
+    fn join_path<S: AsRef<str>>(components: &[S]) -> String {
+        let mut path = String::new();
+        for component in components {
+            path.push('/');
+            path.push_str(component.as_ref());
+        }
+        path
+    }
+
+    /// Creates a StructuredData in the Network
+    /// The StructuredData is created based on the version and AccessLevel of the DirectoryListing
+    fn save_directory_listing(&self,
+                              directory: &DirectoryListing)
+                              -> Result<StructuredData, NfsError> {
+        if DirectoryHelper::is_sharded(directory.get_key()) {
+            return self.save_sharded_directory_listing(directory);
+        }
+        let signing_key = try!(unwrap_result!(self.client.lock()).get_secret_signing_key()).clone();
+        let owner_key = try!(unwrap_result!(self.client.lock()).get_public_signing_key()).clone();
+        let access_level = directory.get_key().get_access_level();
+        let versioned = directory.get_key().is_versioned();
+
+        if versioned {
+            let serialised_data = match *access_level {
+                ::AccessLevel::Private => try!(directory.encrypt(self.client.clone())),
+                ::AccessLevel::Public => try!(serialise(&directory)),
+            };
+            let version = try!(self.save_as_immutable_data(serialised_data,
+                                                           ImmutableDataType::Normal));
+            let structured_data = try!(versioned::create(&*unwrap_result!(self.client.lock()),
+                                                         version.clone(),
+                                                         directory.get_key().get_type_tag(),
+                                                         directory.get_key().get_id().clone(),
+                                                         0,
+                                                         vec![owner_key],
+                                                         Vec::new(),
+                                                         &signing_key));
+            if let Some(error) = self.record_version(directory.get_key(), version, None) {
+                debug!("Failed to record version in the version log of directory - {:?}", error);
+            }
+            Ok(structured_data)
         } else {
             let private_key = try!(unwrap_result!(self.client.lock()).get_public_encryption_key())
                                   .clone();
@@ -312,25 +1698,88 @@ impl DirectoryHelper {
     }
 
     fn update_directory_listing(&self, directory: &DirectoryListing) -> Result<(), NfsError> {
+        if let Some(error) = try!(self.update_directory_listing_with_message(directory, None)) {
+            debug!("Failed to record version in the version log of directory - {:?}", error);
+        }
+        Ok(())
+    }
+
+    /// Returns the error recording the version in the version log failed with, if it did
+    fn update_directory_listing_with_message(&self,
+                                             directory: &DirectoryListing,
+                                             message: Option<String>)
+                                             -> Result<Option<NfsError>, NfsError> {
         let structured_data = try!(self.get_structured_data(directory.get_key().get_id(),
                                                             directory.get_key().get_type_tag()));
+        let (_, log_error) = try!(self.post_directory_listing(directory, structured_data, message));
+        Ok(log_error)
+    }
 
+    // Posts the listing as the successor of `structured_data`, which was read at
+    // `expected_version`. Another writer can still post between that read and this post, in
+    // which case the network rejects ours and the failure is reported as
+    // `NfsError::ConcurrentModification` once the version is found to have moved on.
+    fn post_if_unchanged(&self,
+                         directory: &DirectoryListing,
+                         structured_data: StructuredData,
+                         expected_version: u64)
+                         -> Result<u64, NfsError> {
+        match self.post_directory_listing(directory, structured_data, None) {
+            Ok((updated_version, log_error)) => {
+                if let Some(error) = log_error {
+                    debug!("Failed to record version in the version log of directory - {:?}",
+                           error);
+                }
+                Ok(updated_version)
+            }
+            Err(error) => {
+                match self.get_structured_data(directory.get_key().get_id(),
+                                               directory.get_key().get_type_tag()) {
+                    Ok(ref current) if current.get_version() != expected_version => {
+                        debug!("Directory was modified concurrently - expected version {:?}, \
+                                found {:?}",
+                               expected_version,
+                               current.get_version());
+                        Err(NfsError::ConcurrentModification)
+                    }
+                    _ => Err(error),
+                }
+            }
+        }
+    }
+
+    /// Posts the DirectoryListing as the successor of the StructuredData passed.
+    /// For versioned directories the appended version is recorded in the version log along with
+    /// `message`.
+    /// Returns (version of the updated StructuredData, Option<error recording the version failed
+    /// with>)
+    fn post_directory_listing(&self,
+                              directory: &DirectoryListing,
+                              structured_data: StructuredData,
+                              message: Option<String>)
+                              -> Result<(u64, Option<NfsError>), NfsError> {
+        if DirectoryHelper::is_sharded(directory.get_key()) {
+            let updated_version = try!(self.post_sharded_directory_listing(directory,
+                                                                           structured_data));
+            return Ok((updated_version, None));
+        }
         let signing_key = try!(unwrap_result!(self.client.lock()).get_secret_signing_key()).clone();
         let owner_key = try!(unwrap_result!(self.client.lock()).get_public_signing_key()).clone();
         let access_level = directory.get_key().get_access_level();
         let versioned = directory.get_key().is_versioned();
 
-        let updated_structured_data = if versioned {
+        let (updated_structured_data, appended_version) = if versioned {
             let serialised_data = match *access_level {
                 ::AccessLevel::Private => try!(directory.encrypt(self.client.clone())),
                 ::AccessLevel::Public => try!(serialise(&directory)),
             };
             let version = try!(self.save_as_immutable_data(serialised_data,
                                                            ImmutableDataType::Normal));
-            try!(versioned::append_version(&mut *unwrap_result!(self.client.lock()),
-                                           structured_data,
-                                           version,
-                                           &signing_key))
+            (try!(versioned::append_version(&mut *unwrap_result!(self.client.lock()),
+                                            structured_data,
+                                            version.clone(),
+                                            &signing_key)),
+             Some(version))
         } else {
             let private_key = try!(unwrap_result!(self.client.lock()).get_public_encryption_key())
                                   .clone();
@@ -343,43 +1792,273 @@ impl DirectoryHelper {
                 ::AccessLevel::Private => Some((&private_key, &secret_key, &nonce)),
                 ::AccessLevel::Public => None,
             };
-            try!(unversioned::create(self.client.clone(),
-                                     directory.get_key().get_type_tag(),
-                                     directory.get_key().get_id().clone(),
-                                     structured_data.get_version() + 1,
-                                     serialised_data,
-                                     vec![owner_key.clone()],
-                                     Vec::new(),
-                                     &signing_key,
-                                     encryption_keys))
+            (try!(unversioned::create(self.client.clone(),
+                                      directory.get_key().get_type_tag(),
+                                      directory.get_key().get_id().clone(),
+                                      structured_data.get_version() + 1,
+                                      serialised_data,
+                                      vec![owner_key.clone()],
+                                      Vec::new(),
+                                      &signing_key,
+                                      encryption_keys)),
+             None)
         };
+        let updated_version = updated_structured_data.get_version();
         debug!("Posting updated structured data to the network ...");
         try!(unwrap_result!(self.client.lock())
                  .post(Data::Structured(updated_structured_data), None));
+        let log_error = appended_version.and_then(|version| {
+            self.record_version(directory.get_key(), version, message)
+        });
+        Ok((updated_version, log_error))
+    }
+
+    /// Replaces the content of linked files by the shared state of their links, so that content
+    /// written through one link is seen through all of them
+    fn resolve_links(&self, directory: &mut DirectoryListing) -> Result<(), NfsError> {
+        // Handing out the files mutably drops their index, so only do so if there are links
+        if directory.get_files().iter().all(|file| file.get_metadata().get_link_id().is_none()) {
+            return Ok(());
+        }
+        for file in directory.get_mut_files().iter_mut() {
+            if file.get_metadata().get_link_id().is_some() {
+                *file = try!(link::resolve(&self.client, file));
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends a record for the version to the version log of the directory. Saving the version
+    /// does not depend on the log, so a failure is returned rather than reported as an error:
+    /// versions without a record are tolerated when the log is read, whereas an error would make
+    /// callers retry an update which was committed.
+    /// Returns the error recording the version failed with, if it did
+    fn record_version(&self,
+                      directory_key: &DirectoryKey,
+                      version: XorName,
+                      message: Option<String>)
+                      -> Option<NfsError> {
+        version_log::append(&self.client, directory_key, version, message).err()
+    }
+
+    /// Pairs each version with its record in the version log. Both are in the order the versions
+    /// were appended. Records whose version never made it into the directory, e.g. after a failed
+    /// POST, are skipped.
+    fn match_version_records(versions: &Vec<XorName>,
+                             records: &Vec<VersionRecord>)
+                             -> Vec<Option<VersionRecord>> {
+        let mut matched = Vec::with_capacity(versions.len());
+        let mut next_record = 0;
+        for version in versions.iter() {
+            match records[next_record..].iter().position(|record| record.get_version() == version) {
+                Some(position) => {
+                    matched.push(Some(records[next_record + position].clone()));
+                    next_record += position + 1;
+                }
+                None => matched.push(None),
+            }
+        }
+        matched
+    }
+
+    fn is_sharded(directory_key: &DirectoryKey) -> bool {
+        directory_key.get_type_tag() == ::SHARDED_DIRECTORY_LISTING_TAG
+    }
+
+    /// Creates the StructuredData of a sharded directory. The shards are PUT to the network and
+    /// the StructuredData holding the head of the directory is returned.
+    fn save_sharded_directory_listing(&self,
+                                      directory: &DirectoryListing)
+                                      -> Result<StructuredData, NfsError> {
+        let directory_key = directory.get_key();
+        let access_level = directory_key.get_access_level();
+        let sharded_listing = try!(ShardedListing::split(directory,
+                                                         ::DIRECTORY_LISTING_SHARD_COUNT,
+                                                         None,
+                                                         0));
+        for (index, serialised_shard) in sharded_listing.get_shards().iter().enumerate() {
+            try!(self.put_shard(directory_key,
+                                sharded_listing.get_head(),
+                                index as u32,
+                                serialised_shard));
+        }
+        self.create_unversioned_data(directory_key.get_id().clone(),
+                                     directory_key.get_type_tag(),
+                                     0,
+                                     try!(serialise(sharded_listing.get_head())),
+                                     access_level)
+    }
+
+    /// Saves the shards whose entries changed since the StructuredData passed under a new
+    /// generation, then posts the head of the directory referring to them. Readers of the former
+    /// head still find the shards it refers to until the head is replaced, after which those
+    /// shards are deleted.
+    /// Returns the version of the updated StructuredData holding the head
+    fn post_sharded_directory_listing(&self,
+                                      directory: &DirectoryListing,
+                                      structured_data: StructuredData)
+                                      -> Result<u64, NfsError> {
+        let directory_key = directory.get_key();
+        let access_level = directory_key.get_access_level();
+        let current_head = try!(self.get_sharded_listing_head(directory_key, &structured_data));
+        let generation = structured_data.get_version() + 1;
+        let sharded_listing = try!(ShardedListing::split(directory,
+                                                         current_head.get_shard_count(),
+                                                         Some(&current_head),
+                                                         generation));
+        let head = sharded_listing.get_head();
+        for (index, serialised_shard) in sharded_listing.get_shards().iter().enumerate() {
+            if head.get_shard_generation(index as u32) == Some(generation) {
+                try!(self.put_shard(directory_key, head, index as u32, serialised_shard));
+            }
+        }
+        let updated_structured_data =
+            try!(self.create_unversioned_data(directory_key.get_id().clone(),
+                                              directory_key.get_type_tag(),
+                                              generation,
+                                              try!(serialise(head)),
+                                              access_level));
+        debug!("Posting updated head of sharded directory to the network ...");
+        try!(unwrap_result!(self.client.lock())
+                 .post(Data::Structured(updated_structured_data), None));
+        let current_shard_ids = (0..head.get_shard_count())
+                                    .filter_map(|index| {
+                                        head.get_shard_id(directory_key.get_id(), index)
+                                    })
+                                    .collect::<HashSet<_>>();
+        for index in 0..current_head.get_shard_count() {
+            let replaced = current_head.get_shard_id(directory_key.get_id(), index)
+                                       .map_or(false, |shard_id| {
+                                           !current_shard_ids.contains(&shard_id)
+                                       });
+            if replaced {
+                if let Err(error) = self.delete_shard(directory_key, &current_head, index) {
+                    debug!("Failed to delete replaced shard {:?} of directory - {:?}",
+                           index,
+                           error);
+                }
+            }
+        }
+        Ok(generation)
+    }
+
+    fn get_sharded_directory_listing(&self,
+                                     directory_key: &DirectoryKey,
+                                     structured_data: &StructuredData)
+                                     -> Result<DirectoryListing, NfsError> {
+        let head = try!(self.get_sharded_listing_head(directory_key, structured_data));
+        let mut shards = Vec::with_capacity(head.get_shard_count() as usize);
+        for index in 0..head.get_shard_count() {
+            shards.push(try!(self.get_shard(directory_key, &head, index)));
+        }
+        Ok(shard::join(head, shards))
+    }
+
+    fn get_sharded_listing_head(&self,
+                                directory_key: &DirectoryKey,
+                                structured_data: &StructuredData)
+                                -> Result<ShardedListingHead, NfsError> {
+        let serialised_head = try!(self.get_unversioned_data(directory_key.get_id(),
+                                                             structured_data,
+                                                             directory_key.get_access_level()));
+        Ok(try!(deserialise(&serialised_head)))
+    }
+
+    /// Returns the entries held in one shard of a sharded directory, after checking them against
+    /// the digest in the head
+    fn get_shard(&self,
+                 directory_key: &DirectoryKey,
+                 head: &ShardedListingHead,
+                 index: u32)
+                 -> Result<Shard, NfsError> {
+        let shard_id = try!(DirectoryHelper::get_shard_id(directory_key, head, index));
+        let structured_data = try!(self.get_structured_data(&shard_id,
+                                                            ::DIRECTORY_LISTING_SHARD_TAG));
+        let serialised_shard = try!(self.get_unversioned_data(&shard_id,
+                                                              &structured_data,
+                                                              directory_key.get_access_level()));
+        if !head.verify_shard(index, &serialised_shard) {
+            return Err(NfsError::Unexpected(format!("Shard {} does not match the head of the \
+                                                     directory",
+                                                    index)));
+        }
+        Ok(try!(deserialise(&serialised_shard)))
+    }
+
+    /// Saves a shard of a sharded directory under the generation given by the head
+    fn put_shard(&self,
+                 directory_key: &DirectoryKey,
+                 head: &ShardedListingHead,
+                 index: u32,
+                 serialised_shard: &Vec<u8>)
+                 -> Result<(), NfsError> {
+        let shard_id = try!(DirectoryHelper::get_shard_id(directory_key, head, index));
+        let structured_data = try!(self.create_unversioned_data(shard_id,
+                                                                ::DIRECTORY_LISTING_SHARD_TAG,
+                                                                0,
+                                                                serialised_shard.clone(),
+                                                                directory_key.get_access_level()));
+        debug!("Posting PUT request to network to save shard {:?} of directory ...",
+               index);
+        try!(unwrap_result!(self.client.lock()).put(Data::Structured(structured_data), None));
         Ok(())
     }
 
+    /// Deletes a shard of a sharded directory, as referred to by the head
+    fn delete_shard(&self,
+                    directory_key: &DirectoryKey,
+                    head: &ShardedListingHead,
+                    index: u32)
+                    -> Result<(), NfsError> {
+        let shard_id = try!(DirectoryHelper::get_shard_id(directory_key, head, index));
+        let structured_data = try!(self.get_structured_data(&shard_id,
+                                                            ::DIRECTORY_LISTING_SHARD_TAG));
+        self.delete_unversioned_data(shard_id,
+                                     ::DIRECTORY_LISTING_SHARD_TAG,
+                                     structured_data.get_version())
+    }
+
+    fn get_shard_id(directory_key: &DirectoryKey,
+                    head: &ShardedListingHead,
+                    index: u32)
+                    -> Result<XorName, NfsError> {
+        head.get_shard_id(directory_key.get_id(), index)
+            .ok_or(NfsError::Unexpected(format!("Directory has no shard {}", index)))
+    }
+
+    /// Creates an unversioned StructuredData holding the data, encrypted if the AccessLevel is
+    /// Private
+    fn create_unversioned_data(&self,
+                               id: XorName,
+                               type_tag: u64,
+                               version: u64,
+                               data: Vec<u8>,
+                               access_level: &::AccessLevel)
+                               -> Result<StructuredData, NfsError> {
+        storage::create_unversioned_data(&self.client, id, type_tag, version, data, access_level)
+    }
+
+    /// Returns the data held in an unversioned StructuredData, decrypted if the AccessLevel is
+    /// Private
+    fn get_unversioned_data(&self,
+                            id: &XorName,
+                            structured_data: &StructuredData,
+                            access_level: &::AccessLevel)
+                            -> Result<Vec<u8>, NfsError> {
+        storage::get_unversioned_data(&self.client, id, structured_data, access_level)
+    }
+
     /// Saves the data as ImmutableData in the network and returns the name
     fn save_as_immutable_data(&self,
                               data: Vec<u8>,
                               data_type: ImmutableDataType)
                               -> Result<XorName, NfsError> {
-        let immutable_data = ImmutableData::new(data_type, data);
-        let name = immutable_data.name();
-        debug!("Posting PUT request to save immutable data to the network ...");
-        try!(unwrap_result!(self.client.lock()).put(Data::Immutable(immutable_data), None));
-        Ok(name)
+        storage::save_as_immutable_data(&self.client, data, data_type)
     }
 
     /// Get StructuredData from the Network
     fn get_structured_data(&self, id: &XorName, type_tag: u64) -> Result<StructuredData, NfsError> {
-        let request = DataRequest::Structured(id.clone(), type_tag);
-        debug!("Getting structured data from the network ...");
-        let response_getter = try!(unwrap_result!(self.client.lock()).get(request, None));
-        match try!(response_getter.get()) {
-            Data::Structured(structured_data) => Ok(structured_data),
-            _ => Err(NfsError::from(CoreError::ReceivedUnexpectedData)),
-        }
+        storage::get_structured_data(&self.client, id, type_tag)
     }
 
     /// Get ImmutableData from the Network
@@ -387,13 +2066,7 @@ impl DirectoryHelper {
                           id: XorName,
                           data_type: ImmutableDataType)
                           -> Result<ImmutableData, NfsError> {
-        let request = DataRequest::Immutable(id, data_type);
-        debug!("Getting immutable data from the network ...");
-        let response_getter = try!(unwrap_result!(self.client.lock()).get(request, None));
-        match try!(response_getter.get()) {
-            Data::Immutable(immutable_data) => Ok(immutable_data),
-            _ => Err(NfsError::from(CoreError::ReceivedUnexpectedData)),
-        }
+        storage::get_immutable_data(&self.client, id, data_type)
     }
 }
 
@@ -401,7 +2074,18 @@ impl DirectoryHelper {
 mod test {
     use super::*;
     use std::sync::{Arc, Mutex};
+    use directory_listing::DirectoryListing;
+    use directory_listing::shard::{self, ShardedListingHead};
+    use errors::NfsError;
+    use file::File;
+    use helper::file_helper::FileHelper;
+    use helper::name_validator::NameValidator;
+    use helper::writer::Mode;
+    use metadata::directory_key::DirectoryKey;
+    use metadata::file_metadata::FileMetadata;
+    use metadata::retention_policy::RetentionPolicy;
     use safe_core::utility::test_utils;
+    use self_encryption::DataMap;
 
     #[test]
     fn create_dir_listing() {
@@ -575,6 +2259,1051 @@ mod test {
                    "DirName2".to_string());
     }
 
+    #[test]
+    fn restore_directory_version() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+        let file_helper = FileHelper::new(client.clone());
+
+        let (directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                            ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                            Vec::new(),
+                                                            true,
+                                                            ::AccessLevel::Private,
+                                                            None));
+        let writer = unwrap_result!(file_helper.create("hello.txt".to_string(),
+                                                       Vec::new(),
+                                                       directory));
+        let (mut directory, _) = unwrap_result!(writer.close());
+        let versions = unwrap_result!(dir_helper.get_versions(directory.get_key().get_id(),
+                                                              directory.get_key().get_type_tag()));
+        assert_eq!(versions.len(), 2);
+
+        let _ = unwrap_result!(file_helper.delete("hello.txt".to_string(), &mut directory));
+        directory.get_mut_metadata().set_name("Renamed".to_string());
+        let _ = unwrap_result!(dir_helper.update(&directory));
+
+        let (restored, _) = unwrap_result!(dir_helper.restore_version(directory.get_key(),
+                                                                      versions[1].clone()));
+        assert!(restored.find_file(&"hello.txt".to_string()).is_some());
+        assert_eq!(*restored.get_metadata().get_name(), "Renamed".to_string());
+        assert_eq!(unwrap_result!(dir_helper.get(directory.get_key())), restored);
+        // The restored state is appended, so nothing in between is lost
+        let restored_versions =
+            unwrap_result!(dir_helper.get_versions(directory.get_key().get_id(),
+                                                   directory.get_key().get_type_tag()));
+        assert_eq!(restored_versions.len(), 5);
+        assert_eq!(&restored_versions[..2], &versions[..]);
+
+        // The history of the deleted file starts again at the restored state
+        let restored_file = unwrap_option!(restored.find_file(&"hello.txt".to_string()),
+                                           "File not found");
+        assert_eq!(unwrap_result!(file_helper.get_versions(restored_file, &restored)).len(),
+                   1);
+
+        match dir_helper.restore_version(directory.get_key(), restored.get_key().get_id().clone()) {
+            Err(NfsError::ParameterIsNotValid(_)) => (),
+            _ => panic!("Expected ParameterIsNotValid"),
+        }
+    }
+
+    #[test]
+    fn restore_directory_version_without_deleted_sub_directories() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+
+        let (mut directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                                ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                                Vec::new(),
+                                                                true,
+                                                                ::AccessLevel::Private,
+                                                                None));
+        for name in vec!["Deleted", "Removed"] {
+            let _ = unwrap_result!(dir_helper.create(name.to_string(),
+                                                     ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                     Vec::new(),
+                                                     true,
+                                                     ::AccessLevel::Private,
+                                                     Some(&mut directory)));
+        }
+        let versions = unwrap_result!(dir_helper.get_versions(directory.get_key().get_id(),
+                                                              directory.get_key().get_type_tag()));
+
+        let _ = unwrap_result!(dir_helper.delete_recursively(&mut directory,
+                                                             &"Deleted".to_string(),
+                                                             false));
+        let _ = unwrap_result!(dir_helper.delete(&mut directory, &"Removed".to_string()));
+
+        let (restored, _) =
+            unwrap_result!(dir_helper.restore_version(directory.get_key(),
+                                                      unwrap_option!(versions.last(),
+                                                                     "No versions")
+                                                          .clone()));
+        // Only the listing of "Removed" is still in the network
+        assert!(restored.find_sub_directory(&"Deleted".to_string()).is_none());
+        let removed = unwrap_option!(restored.find_sub_directory(&"Removed".to_string()),
+                                     "Sub directory not restored");
+        let _ = unwrap_result!(dir_helper.get(removed.get_key()));
+    }
+
+    #[test]
+    fn version_log() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let signer = unwrap_result!(test_client.get_public_signing_key()).clone();
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+
+        let (mut directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                                ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                                Vec::new(),
+                                                                true,
+                                                                ::AccessLevel::Private,
+                                                                None));
+        directory.get_mut_metadata().set_user_metadata(vec![1u8; 10]);
+        let _ = unwrap_result!(dir_helper.update(&directory));
+        directory.get_mut_metadata().set_name("Renamed".to_string());
+        let (_, log_error) = unwrap_result!(dir_helper.update_with_message(&directory,
+                                                                           "Rename".to_string()));
+        assert!(log_error.is_none());
+
+        let versions = unwrap_result!(dir_helper.get_versions(directory.get_key().get_id(),
+                                                              directory.get_key().get_type_tag()));
+        let log = unwrap_result!(dir_helper.get_version_log(directory.get_key()));
+        assert_eq!(log.len(), 3);
+        for (record, version) in log.iter().zip(versions.iter()) {
+            assert_eq!(record.get_version(), version);
+            assert_eq!(*record.get_signer(), signer);
+        }
+        assert!(log[0].get_timestamp() <= log[1].get_timestamp());
+        assert!(log[1].get_timestamp() <= log[2].get_timestamp());
+        assert!(log[0].get_message().is_none());
+        assert!(log[1].get_message().is_none());
+        assert_eq!(*unwrap_option!(log[2].get_message(), "Message should be recorded"),
+                   "Rename".to_string());
+
+        let (unversioned, _) = unwrap_result!(dir_helper.create("Unversioned".to_string(),
+                                                              ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                              Vec::new(),
+                                                              false,
+                                                              ::AccessLevel::Private,
+                                                              None));
+        match dir_helper.update_with_message(&unversioned, "Message".to_string()) {
+            Err(NfsError::ParameterIsNotValid(_)) => (),
+            _ => panic!("Expected ParameterIsNotValid"),
+        }
+        match dir_helper.get_version_log(unversioned.get_key()) {
+            Err(NfsError::ParameterIsNotValid(_)) => (),
+            _ => panic!("Expected ParameterIsNotValid"),
+        }
+    }
+
+    #[test]
+    fn prune_directory_versions() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+
+        let (mut directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                                ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                                Vec::new(),
+                                                                true,
+                                                                ::AccessLevel::Private,
+                                                                None));
+        for index in 0..4 {
+            directory.get_mut_metadata().set_user_metadata(vec![index; 10]);
+            let _ = unwrap_result!(dir_helper.update(&directory));
+        }
+        // Without a retention policy every version is kept
+        assert!(unwrap_result!(dir_helper.prune_versions(directory.get_key(), false)).is_empty());
+
+        let mut retention_policy = RetentionPolicy::new();
+        retention_policy.set_keep_last(Some(2));
+        directory.get_mut_metadata().set_retention_policy(Some(retention_policy));
+        let _ = unwrap_result!(dir_helper.update(&directory));
+        let versions = unwrap_result!(dir_helper.get_versions(directory.get_key().get_id(),
+                                                              directory.get_key().get_type_tag()));
+        assert_eq!(versions.len(), 6);
+
+        let dropped = unwrap_result!(dir_helper.prune_versions(directory.get_key(), true));
+        assert_eq!(&dropped.get_versions()[..], &versions[..4]);
+        assert_eq!(&dropped.get_released()[..], &versions[..4]);
+        assert!(dropped.get_undated().is_empty());
+        assert_eq!(unwrap_result!(dir_helper.get_versions(directory.get_key().get_id(),
+                                                          directory.get_key().get_type_tag())),
+                   versions);
+
+        let dropped = unwrap_result!(dir_helper.prune_versions(directory.get_key(), false));
+        assert_eq!(&dropped.get_versions()[..], &versions[..4]);
+        // The ImmutableData of the dropped versions is only reported, not deleted
+        for version in dropped.get_released() {
+            let _ = unwrap_result!(dir_helper.get_by_version(directory.get_key().get_id(),
+                                                             directory.get_key().get_access_level(),
+                                                             version.clone()));
+        }
+        let pruned_versions =
+            unwrap_result!(dir_helper.get_versions(directory.get_key().get_id(),
+                                                   directory.get_key().get_type_tag()));
+        assert_eq!(&pruned_versions[..], &versions[4..]);
+        let log = unwrap_result!(dir_helper.get_version_log(directory.get_key()));
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].get_version(), &versions[4]);
+        assert_eq!(log[1].get_version(), &versions[5]);
+        assert_eq!(unwrap_result!(dir_helper.get(directory.get_key())), directory);
+        assert!(unwrap_result!(dir_helper.prune_versions(directory.get_key(), false)).is_empty());
+
+        // Versions appended after pruning follow on from the kept ones
+        directory.get_mut_metadata().set_user_metadata(vec![9u8; 10]);
+        let _ = unwrap_result!(dir_helper.update(&directory));
+        let versions = unwrap_result!(dir_helper.get_versions(directory.get_key().get_id(),
+                                                              directory.get_key().get_type_tag()));
+        assert_eq!(&versions[..2], &pruned_versions[..]);
+        assert_eq!(versions.len(), 3);
+
+        let (unversioned, _) = unwrap_result!(dir_helper.create("Unversioned".to_string(),
+                                                              ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                              Vec::new(),
+                                                              false,
+                                                              ::AccessLevel::Private,
+                                                              None));
+        match dir_helper.prune_versions(unversioned.get_key(), false) {
+            Err(NfsError::ParameterIsNotValid(_)) => (),
+            _ => panic!("Expected ParameterIsNotValid"),
+        }
+    }
+
+    #[test]
+    fn diff_directory_versions() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+        let file_helper = FileHelper::new(client.clone());
+
+        let (mut home, _) = unwrap_result!(dir_helper.create("Home".to_string(),
+                                                           ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                           Vec::new(),
+                                                           true,
+                                                           ::AccessLevel::Private,
+                                                           None));
+        let (child, _) = unwrap_result!(dir_helper.create("Child".to_string(),
+                                                        ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                        Vec::new(),
+                                                        true,
+                                                        ::AccessLevel::Private,
+                                                        Some(&mut home)));
+        let writer = unwrap_result!(file_helper.create("hello.txt".to_string(),
+                                                       Vec::new(),
+                                                       child));
+        let _ = unwrap_result!(writer.close());
+        let versions = unwrap_result!(dir_helper.get_versions(home.get_key().get_id(),
+                                                              home.get_key().get_type_tag()));
+        assert_eq!(versions.len(), 3);
+
+        let home_diff = unwrap_result!(dir_helper.diff_versions(home.get_key(),
+                                                                versions[0].clone(),
+                                                                versions[1].clone(),
+                                                                true));
+        assert_eq!(home_diff.get_sub_directories().get_added().len(), 1);
+        assert!(home_diff.get_sub_directory_diffs().is_empty());
+
+        let home_diff = unwrap_result!(dir_helper.diff_versions(home.get_key(),
+                                                                versions[1].clone(),
+                                                                versions[2].clone(),
+                                                                false));
+        assert_eq!(home_diff.get_sub_directories().get_metadata_changed().len(), 1);
+        assert!(home_diff.get_sub_directory_diffs().is_empty());
+
+        let home_diff = unwrap_result!(dir_helper.diff_versions(home.get_key(),
+                                                                versions[1].clone(),
+                                                                versions[2].clone(),
+                                                                true));
+        assert_eq!(home_diff.get_sub_directory_diffs().len(), 1);
+        let (ref name, ref child_diff) = home_diff.get_sub_directory_diffs()[0];
+        assert_eq!(*name, "Child".to_string());
+        assert_eq!(child_diff.get_files().get_added().len(), 1);
+        assert_eq!(*child_diff.get_files().get_added()[0].get_name(),
+                   "hello.txt".to_string());
+    }
+
+    #[test]
+    fn diff_directory_versions_with_equal_modified_times() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+
+        let (mut home, _) = unwrap_result!(dir_helper.create("Home".to_string(),
+                                                           ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                           Vec::new(),
+                                                           true,
+                                                           ::AccessLevel::Private,
+                                                           None));
+        let (mut child, _) = unwrap_result!(dir_helper.create("Child".to_string(),
+                                                            ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                            Vec::new(),
+                                                            true,
+                                                            ::AccessLevel::Private,
+                                                            Some(&mut home)));
+        let modified_time = child.get_metadata().get_modified_time().clone();
+        let _ = unwrap_result!(dir_helper.create("Grand Child".to_string(),
+                                                 ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                 Vec::new(),
+                                                 true,
+                                                 ::AccessLevel::Private,
+                                                 Some(&mut child)));
+        // Every version of the child now carries the same modified time
+        child.get_mut_metadata().set_modified_time(modified_time);
+        child.get_mut_metadata().set_user_metadata(vec![1u8; 10]);
+        let _ = unwrap_result!(dir_helper.update(&child));
+        let versions = unwrap_result!(dir_helper.get_versions(home.get_key().get_id(),
+                                                              home.get_key().get_type_tag()));
+        assert_eq!(versions.len(), 4);
+
+        // The version log tells the states of the child apart
+        let home_diff = unwrap_result!(dir_helper.diff_versions(home.get_key(),
+                                                                versions[1].clone(),
+                                                                versions[3].clone(),
+                                                                true));
+        assert_eq!(home_diff.get_sub_directory_diffs().len(), 1);
+        let (_, ref child_diff) = home_diff.get_sub_directory_diffs()[0];
+        assert_eq!(child_diff.get_sub_directories().get_added().len(), 1);
+    }
+
+    #[test]
+    fn resolve_by_path() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+
+        let mut root_dir = unwrap_result!(dir_helper.get_user_root_directory_listing());
+        let (mut home_dir, _) = unwrap_result!(dir_helper.create("Home".to_string(),
+                                                               ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                               Vec::new(),
+                                                               true,
+                                                               ::AccessLevel::Private,
+                                                               Some(&mut root_dir)));
+        let (pictures_dir, _) = unwrap_result!(dir_helper.create("Pictures".to_string(),
+                                                               ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                               Vec::new(),
+                                                               true,
+                                                               ::AccessLevel::Private,
+                                                               Some(&mut home_dir)));
+
+        let resolved = unwrap_result!(dir_helper.get_by_path("/Home/Pictures", None));
+        assert_eq!(resolved, pictures_dir);
+        let resolved = unwrap_result!(dir_helper.get_by_path("Pictures/",
+                                                             Some(home_dir.get_key())));
+        assert_eq!(resolved, pictures_dir);
+        let resolved = unwrap_result!(dir_helper.get_by_path("/", Some(home_dir.get_key())));
+        assert_eq!(*resolved.get_key(), *home_dir.get_key());
+
+        match dir_helper.get_by_path("/Home/Music/2016", None) {
+            Err(NfsError::DirectoryNotFound(path)) => assert_eq!(path, "/Home/Music"),
+            _ => panic!("Expected DirectoryNotFound"),
+        }
+        match dir_helper.get_file_by_path("/Home/Pictures/a.jpg", None) {
+            Err(NfsError::FileNotFound(path)) => assert_eq!(path, "/Home/Pictures/a.jpg"),
+            _ => panic!("Expected FileNotFound"),
+        }
+        assert!(dir_helper.get_file_by_path("/", None).is_err());
+    }
+
+    #[test]
+    fn resolve_symlinks() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+        let file_helper = FileHelper::new(client.clone());
+
+        let mut root_dir = unwrap_result!(dir_helper.get_user_root_directory_listing());
+        let (mut home_dir, _) = unwrap_result!(dir_helper.create("Home".to_string(),
+                                                               ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                               Vec::new(),
+                                                               false,
+                                                               ::AccessLevel::Private,
+                                                               Some(&mut root_dir)));
+        let (pictures_dir, _) = unwrap_result!(dir_helper.create("Pictures".to_string(),
+                                                               ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                               Vec::new(),
+                                                               false,
+                                                               ::AccessLevel::Private,
+                                                               Some(&mut home_dir)));
+        let writer = unwrap_result!(file_helper.create("a.jpg".to_string(),
+                                                       Vec::new(),
+                                                       pictures_dir.clone()));
+        let _ = unwrap_result!(writer.close());
+
+        let mut home_dir = unwrap_result!(dir_helper.get(home_dir.get_key()));
+        for &(name, target) in &[("pics", "Pictures"),
+                                 ("photo", "/Home/pics/a.jpg"),
+                                 ("home", "./../Home/"),
+                                 ("loop", "loop"),
+                                 ("dangling", "Music")] {
+            let _ = unwrap_result!(dir_helper.create_symlink(name.to_string(),
+                                                             target.to_string(),
+                                                             &mut home_dir));
+        }
+        match dir_helper.create_symlink("pics".to_string(), "Music".to_string(), &mut home_dir) {
+            Err(NfsError::FileAlreadyExistsWithSameName) => (),
+            _ => panic!("Expected FileAlreadyExistsWithSameName"),
+        }
+
+        let resolved = unwrap_result!(dir_helper.get_by_path("/Home/pics", None));
+        assert_eq!(*resolved.get_key(), *pictures_dir.get_key());
+        let resolved = unwrap_result!(dir_helper.get_by_path("/Home/home/home/pics/..", None));
+        assert_eq!(*resolved.get_key(), *home_dir.get_key());
+        let (file, parent) = unwrap_result!(dir_helper.get_file_by_path("/Home/photo", None));
+        assert_eq!(*file.get_name(), "a.jpg".to_string());
+        assert_eq!(*parent.get_key(), *pictures_dir.get_key());
+
+        match dir_helper.get_by_path("/Home/loop", None) {
+            Err(NfsError::SymlinkLoop(path)) => assert_eq!(path, "/Home/loop"),
+            _ => panic!("Expected SymlinkLoop"),
+        }
+        match dir_helper.get_by_path("/Home/dangling/2016", None) {
+            Err(NfsError::DirectoryNotFound(path)) => assert_eq!(path, "/Home/Music"),
+            _ => panic!("Expected DirectoryNotFound"),
+        }
+
+        let _ = unwrap_result!(dir_helper.delete_symlink(&mut home_dir, &"dangling".to_string()));
+        let home_dir = unwrap_result!(dir_helper.get(home_dir.get_key()));
+        assert!(home_dir.find_symlink(&"dangling".to_string()).is_none());
+        assert_eq!(home_dir.get_symlinks().len(), 4);
+    }
+
+    #[test]
+    fn update_recursively() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+
+        let (mut directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                                ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                                Vec::new(),
+                                                                true,
+                                                                ::AccessLevel::Private,
+                                                                None));
+        let (mut child_directory, _) =
+            unwrap_result!(dir_helper.create("Child".to_string(),
+                                             ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                             Vec::new(),
+                                             false,
+                                             ::AccessLevel::Private,
+                                             Some(&mut directory)));
+        let (mut grand_child_directory, _) =
+            unwrap_result!(dir_helper.create("Grand Child".to_string(),
+                                             ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                             Vec::new(),
+                                             true,
+                                             ::AccessLevel::Private,
+                                             Some(&mut child_directory)));
+
+        let modified_time = ::time::now_utc();
+        grand_child_directory.get_mut_metadata().set_user_metadata(vec![7u8; 10]);
+        grand_child_directory.get_mut_metadata().set_modified_time(modified_time.clone());
+        let ancestors = unwrap_result!(dir_helper.update_recursively(&grand_child_directory));
+        assert_eq!(ancestors.len(), 2);
+        assert_eq!(*ancestors[0].get_key(), *child_directory.get_key());
+        assert_eq!(*ancestors[1].get_key(), *directory.get_key());
+
+        let root = unwrap_result!(dir_helper.get(directory.get_key()));
+        assert_eq!(*root.get_metadata().get_modified_time(), modified_time);
+        let child_metadata = unwrap_option!(root.find_sub_directory(&"Child".to_string()),
+                                            "Child directory not found");
+        assert_eq!(*child_metadata.get_modified_time(), modified_time);
+        let child = unwrap_result!(dir_helper.get(child_metadata.get_key()));
+        let grand_child_metadata =
+            unwrap_option!(child.find_sub_directory(&"Grand Child".to_string()),
+                           "Grand Child directory not found");
+        assert_eq!(*grand_child_metadata.get_user_metadata(), vec![7u8; 10]);
+
+        // A directory which is its own parent must not loop forever
+        let key = directory.get_key().clone();
+        directory.get_mut_metadata().set_parent_dir_key(Some(key));
+        match dir_helper.update_recursively(&directory) {
+            Err(NfsError::CircularDirectoryReference) => (),
+            _ => panic!("Expected CircularDirectoryReference"),
+        }
+    }
+
+    #[test]
+    fn move_and_rename_directory() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+
+        let (mut source, _) = unwrap_result!(dir_helper.create("Source".to_string(),
+                                                             ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                             Vec::new(),
+                                                             true,
+                                                             ::AccessLevel::Private,
+                                                             None));
+        let (mut destination, _) = unwrap_result!(dir_helper.create("Destination".to_string(),
+                                                                  ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                                  Vec::new(),
+                                                                  true,
+                                                                  ::AccessLevel::Private,
+                                                                  None));
+        let (mut child, _) = unwrap_result!(dir_helper.create("Child".to_string(),
+                                                            ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                            Vec::new(),
+                                                            true,
+                                                            ::AccessLevel::Private,
+                                                            Some(&mut source)));
+        let (_, _) = unwrap_result!(dir_helper.create("Grand Child".to_string(),
+                                                    ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                    Vec::new(),
+                                                    true,
+                                                    ::AccessLevel::Private,
+                                                    Some(&mut child)));
+        let child_name = "Child".to_string();
+
+        // Rename within the same directory
+        unwrap_result!(dir_helper.move_directory(&child_name,
+                                                 "Renamed".to_string(),
+                                                 &mut source,
+                                                 None));
+        assert!(source.find_sub_directory(&child_name).is_none());
+        let renamed = unwrap_result!(dir_helper.get(child.get_key()));
+        assert_eq!(*renamed.get_metadata().get_name(), "Renamed".to_string());
+
+        // Moving a directory into its own descendant must fail
+        let mut grand_child = unwrap_result!(dir_helper.get_by_path("Renamed/Grand Child",
+                                                                    Some(source.get_key())));
+        match dir_helper.move_directory(&"Renamed".to_string(),
+                                        "Renamed".to_string(),
+                                        &mut source,
+                                        Some(&mut grand_child)) {
+            Err(NfsError::CircularDirectoryReference) => (),
+            _ => panic!("Expected CircularDirectoryReference"),
+        }
+
+        // Move across directories
+        let (_, _) = unwrap_result!(dir_helper.create("Child".to_string(),
+                                                    ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                    Vec::new(),
+                                                    true,
+                                                    ::AccessLevel::Private,
+                                                    Some(&mut destination)));
+        match dir_helper.move_directory(&"Renamed".to_string(),
+                                        child_name.clone(),
+                                        &mut source,
+                                        Some(&mut destination)) {
+            Err(NfsError::DirectoryAlreadyExistsWithSameName) => (),
+            _ => panic!("Expected DirectoryAlreadyExistsWithSameName"),
+        }
+        unwrap_result!(dir_helper.move_directory(&"Renamed".to_string(),
+                                                 "Moved".to_string(),
+                                                 &mut source,
+                                                 Some(&mut destination)));
+        assert!(source.get_sub_directories().is_empty());
+        let source = unwrap_result!(dir_helper.get(source.get_key()));
+        assert!(source.get_sub_directories().is_empty());
+        let destination = unwrap_result!(dir_helper.get(destination.get_key()));
+        assert_eq!(destination.get_sub_directories().len(), 2);
+        let moved = unwrap_result!(dir_helper.get(child.get_key()));
+        assert_eq!(*moved.get_metadata().get_name(), "Moved".to_string());
+        assert_eq!(*unwrap_option!(moved.get_metadata().get_parent_dir_key(),
+                                   "Parent should be set"),
+                   *destination.get_key());
+        assert!(dir_helper.get_by_path("Moved/Grand Child", Some(destination.get_key())).is_ok());
+    }
+
+    #[test]
+    fn delete_directory_recursively() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+
+        let (mut directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                                ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                                Vec::new(),
+                                                                true,
+                                                                ::AccessLevel::Private,
+                                                                None));
+        let (mut child_directory, _) =
+            unwrap_result!(dir_helper.create("Child".to_string(),
+                                             ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                             Vec::new(),
+                                             true,
+                                             ::AccessLevel::Private,
+                                             Some(&mut directory)));
+        let (grand_child_directory, _) =
+            unwrap_result!(dir_helper.create("Grand Child".to_string(),
+                                             ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                             Vec::new(),
+                                             false,
+                                             ::AccessLevel::Private,
+                                             Some(&mut child_directory)));
+        let child_name = "Child".to_string();
+
+        let deleted = unwrap_result!(dir_helper.delete_recursively(&mut directory,
+                                                                   &child_name,
+                                                                   true));
+        assert_eq!(deleted.get_directories().len(), 2);
+        // Child was created and then updated once when Grand Child was added
+        assert_eq!(deleted.get_versions().len(), 2);
+        assert!(directory.find_sub_directory(&child_name).is_some());
+        assert!(dir_helper.get(grand_child_directory.get_key()).is_ok());
+
+        let deleted = unwrap_result!(dir_helper.delete_recursively(&mut directory,
+                                                                   &child_name,
+                                                                   false));
+        assert_eq!(deleted.get_directories().len(), 2);
+        assert!(deleted.get_cleanup_failures().is_empty());
+        assert!(directory.find_sub_directory(&child_name).is_none());
+        let directory = unwrap_result!(dir_helper.get(directory.get_key()));
+        assert!(directory.find_sub_directory(&child_name).is_none());
+        assert!(dir_helper.get(child_directory.get_key()).is_err());
+        assert!(dir_helper.get(grand_child_directory.get_key()).is_err());
+    }
+
+    #[test]
+    fn copy_directory() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+
+        let (mut directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                                ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                                Vec::new(),
+                                                                true,
+                                                                ::AccessLevel::Private,
+                                                                None));
+        let (mut child_directory, _) =
+            unwrap_result!(dir_helper.create("Child".to_string(),
+                                             ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                             vec![1u8; 10],
+                                             true,
+                                             ::AccessLevel::Private,
+                                             Some(&mut directory)));
+        let (_, _) = unwrap_result!(dir_helper.create("Grand Child".to_string(),
+                                                    ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                    Vec::new(),
+                                                    true,
+                                                    ::AccessLevel::Private,
+                                                    Some(&mut child_directory)));
+        let file = unwrap_result!(File::new(FileMetadata::new("hello.txt".to_string(),
+                                                              Vec::new()),
+                                            DataMap::Content(vec![1u8; 5])));
+        child_directory.upsert_file(file.clone());
+        let _ = unwrap_result!(dir_helper.update(&child_directory));
+
+        let (copied, _) = unwrap_result!(dir_helper.copy_directory(child_directory.get_key(),
+                                                                   "Copy".to_string(),
+                                                                   &mut directory,
+                                                                   Some(::AccessLevel::Public),
+                                                                   Some(false)));
+        assert!(copied.get_key() != child_directory.get_key());
+        assert_eq!(*copied.get_key().get_access_level(), ::AccessLevel::Public);
+        assert!(!copied.get_key().is_versioned());
+        assert_eq!(*copied.get_metadata().get_user_metadata(), vec![1u8; 10]);
+        assert_eq!(*unwrap_option!(copied.get_metadata().get_parent_dir_key(),
+                                   "Parent should be set"),
+                   *directory.get_key());
+
+        let copied_file = unwrap_option!(copied.find_file(&"hello.txt".to_string()),
+                                         "File not found");
+        assert!(copied_file.get_id() != file.get_id());
+        assert!(*copied_file.get_datamap() == *file.get_datamap());
+
+        let grand_child_copy = unwrap_result!(dir_helper.get_by_path("Copy/Grand Child",
+                                                                     Some(directory.get_key())));
+        assert_eq!(*unwrap_option!(grand_child_copy.get_metadata().get_parent_dir_key(),
+                                   "Parent should be set"),
+                   *copied.get_key());
+        assert!(dir_helper.get_by_path("Child/Grand Child", Some(directory.get_key())).is_ok());
+
+        match dir_helper.copy_directory(directory.get_key(),
+                                        "Recursive".to_string(),
+                                        &mut child_directory,
+                                        None,
+                                        None) {
+            Err(NfsError::CircularDirectoryReference) => (),
+            _ => panic!("Expected CircularDirectoryReference"),
+        }
+    }
+
+    #[test]
+    fn concurrent_modification() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+
+        let (directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                            ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                            Vec::new(),
+                                                            true,
+                                                            ::AccessLevel::Private,
+                                                            None));
+        let (mut first, first_version) =
+            unwrap_result!(dir_helper.get_with_version(directory.get_key()));
+        let (mut second, second_version) =
+            unwrap_result!(dir_helper.get_with_version(directory.get_key()));
+        assert_eq!(first_version, second_version);
+
+        first.get_mut_metadata().set_user_metadata(vec![1u8; 10]);
+        let (updated_version, _) = unwrap_result!(dir_helper.update_if_unchanged(&first,
+                                                                                 first_version));
+        assert!(updated_version > first_version);
+
+        second.get_mut_metadata().set_user_metadata(vec![2u8; 10]);
+        match dir_helper.update_if_unchanged(&second, second_version) {
+            Err(NfsError::ConcurrentModification) => (),
+            _ => panic!("Expected ConcurrentModification"),
+        }
+        assert_eq!(*unwrap_result!(dir_helper.get(directory.get_key()))
+                        .get_metadata()
+                        .get_user_metadata(),
+                   vec![1u8; 10]);
+
+        // Another writer posting between the version check and the post is caught too
+        let (mut third, third_version) =
+            unwrap_result!(dir_helper.get_with_version(directory.get_key()));
+        let stale_structured_data =
+            unwrap_result!(dir_helper.get_structured_data(directory.get_key().get_id(),
+                                                          directory.get_key().get_type_tag()));
+        assert_eq!(stale_structured_data.get_version(), third_version);
+        let (mut other, _) = unwrap_result!(dir_helper.get_with_version(directory.get_key()));
+        other.get_mut_metadata().set_user_metadata(vec![4u8; 10]);
+        let _ = unwrap_result!(dir_helper.update_if_unchanged(&other, third_version));
+        third.get_mut_metadata().set_user_metadata(vec![5u8; 10]);
+        match dir_helper.post_if_unchanged(&third, stale_structured_data, third_version) {
+            Err(NfsError::ConcurrentModification) => (),
+            _ => panic!("Expected ConcurrentModification"),
+        }
+        assert_eq!(*unwrap_result!(dir_helper.get(directory.get_key()))
+                        .get_metadata()
+                        .get_user_metadata(),
+                   vec![4u8; 10]);
+
+        let mut attempts = 0;
+        let (updated, _) = {
+            let apply_changes = |directory: &mut DirectoryListing| {
+                attempts += 1;
+                if attempts == 1 {
+                    // Simulate another writer sneaking in between the read and the write
+                    let mut other = try!(dir_helper.get(directory.get_key()));
+                    other.get_mut_metadata().set_name("Renamed".to_string());
+                    let _ = try!(dir_helper.update(&other));
+                }
+                let mut user_metadata = directory.get_metadata().get_user_metadata().clone();
+                user_metadata.push(3u8);
+                directory.get_mut_metadata().set_user_metadata(user_metadata);
+                Ok(())
+            };
+            unwrap_result!(dir_helper.update_with_retry(directory.get_key(), 3, apply_changes))
+        };
+        assert_eq!(attempts, 2);
+        assert_eq!(*updated.get_metadata().get_name(), "Renamed".to_string());
+        let mut expected = vec![4u8; 10];
+        expected.push(3u8);
+        assert_eq!(*updated.get_metadata().get_user_metadata(), expected);
+    }
+
+    #[test]
+    fn update_with_merge() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+
+        let (directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                            ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                            Vec::new(),
+                                                            false,
+                                                            ::AccessLevel::Private,
+                                                            None));
+        let (base, base_version) = unwrap_result!(dir_helper.get_with_version(directory.get_key()));
+
+        let mut theirs = base.clone();
+        theirs.upsert_file(unwrap_result!(File::new(FileMetadata::new("theirs.txt".to_string(),
+                                                                      Vec::new()),
+                                                    DataMap::None)));
+        let _ = unwrap_result!(dir_helper.update_if_unchanged(&theirs, base_version));
+
+        let mut ours = base.clone();
+        ours.upsert_file(unwrap_result!(File::new(FileMetadata::new("ours.txt".to_string(),
+                                                                    Vec::new()),
+                                                  DataMap::None)));
+        let (merged, _) = unwrap_result!(dir_helper.update_with_merge(&base,
+                                                                      &ours,
+                                                                      base_version,
+                                                                      2));
+        assert_eq!(merged.get_files().len(), 2);
+        let saved = unwrap_result!(dir_helper.get(directory.get_key()));
+        assert!(saved.find_file(&"ours.txt".to_string()).is_some());
+        assert!(saved.find_file(&"theirs.txt".to_string()).is_some());
+
+        // Both sides adding a file with the same name can not be merged
+        let (base, base_version) = unwrap_result!(dir_helper.get_with_version(directory.get_key()));
+        let mut theirs = base.clone();
+        theirs.upsert_file(unwrap_result!(File::new(FileMetadata::new("same.txt".to_string(),
+                                                                      Vec::new()),
+                                                    DataMap::None)));
+        let _ = unwrap_result!(dir_helper.update_if_unchanged(&theirs, base_version));
+        let mut ours = base.clone();
+        ours.upsert_file(unwrap_result!(File::new(FileMetadata::new("same.txt".to_string(),
+                                                                    Vec::new()),
+                                                  DataMap::None)));
+        match dir_helper.update_with_merge(&base, &ours, base_version, 2) {
+            Err(NfsError::MergeConflicts(conflicts)) => assert_eq!(conflicts.len(), 1),
+            _ => panic!("Expected MergeConflicts"),
+        }
+    }
+
+    #[test]
+    fn validate_directory_names() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let mut dir_helper = DirectoryHelper::new(client.clone());
+        let (mut parent_directory, _) =
+            unwrap_result!(dir_helper.create("Parent".to_string(),
+                                             ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                             Vec::new(),
+                                             false,
+                                             ::AccessLevel::Private,
+                                             None));
+        for name in vec!["", ".", "..", "a/b"] {
+            match dir_helper.create(name.to_string(),
+                                    ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                    Vec::new(),
+                                    false,
+                                    ::AccessLevel::Private,
+                                    Some(&mut parent_directory)) {
+                Err(NfsError::ParameterIsNotValid(_)) => (),
+                _ => panic!("Expected ParameterIsNotValid for {:?}", name),
+            }
+        }
+
+        let _ = unwrap_result!(dir_helper.create("Photos".to_string(),
+                                                 ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                 Vec::new(),
+                                                 false,
+                                                 ::AccessLevel::Private,
+                                                 Some(&mut parent_directory)));
+        let mut name_validator = NameValidator::new();
+        name_validator.set_case_insensitive(true);
+        dir_helper.set_name_validator(name_validator);
+        match dir_helper.create("PHOTOS".to_string(),
+                                ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                Vec::new(),
+                                false,
+                                ::AccessLevel::Private,
+                                Some(&mut parent_directory)) {
+            Err(NfsError::DirectoryAlreadyExistsWithSameName) => (),
+            _ => panic!("Expected DirectoryAlreadyExistsWithSameName"),
+        }
+        // Changing only the case of its own name is not a collision
+        unwrap_result!(dir_helper.move_directory(&"Photos".to_string(),
+                                                 "PHOTOS".to_string(),
+                                                 &mut parent_directory,
+                                                 None));
+        assert!(parent_directory.find_sub_directory(&"PHOTOS".to_string()).is_some());
+    }
+
+    #[test]
+    fn directory_usage() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+        let file_helper = FileHelper::new(client.clone());
+
+        let (mut directory, _) = unwrap_result!(dir_helper.create("Home".to_string(),
+                                                                  ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                                  Vec::new(),
+                                                                  true,
+                                                                  ::AccessLevel::Private,
+                                                                  None));
+        let (sub_directory, _) =
+            unwrap_result!(dir_helper.create("Documents".to_string(),
+                                             ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                             Vec::new(),
+                                             false,
+                                             ::AccessLevel::Private,
+                                             Some(&mut directory)));
+        let sub_directory_key = sub_directory.get_key().clone();
+        // Identical content self-encrypts to the same chunks
+        let content = vec![7u8; 4096];
+        let mut writer = unwrap_result!(file_helper.create("a.bin".to_string(),
+                                                           Vec::new(),
+                                                           sub_directory));
+        writer.write(&content, 0);
+        let (sub_directory, _) = unwrap_result!(writer.close());
+        let mut writer = unwrap_result!(file_helper.create("b.bin".to_string(),
+                                                           Vec::new(),
+                                                           sub_directory));
+        writer.write(&content, 0);
+        let _ = unwrap_result!(writer.close());
+
+        let usage = unwrap_result!(dir_helper.get_usage(directory.get_key(), false));
+        assert_eq!(usage.get_directory_count(), 2);
+        assert_eq!(usage.get_file_count(), 2);
+        assert_eq!(usage.get_size(), 2 * content.len() as u64);
+        assert_eq!(usage.get_version_count(), 0);
+        assert!(usage.get_chunk_count() > 0);
+        let documents = unwrap_result!(dir_helper.get(&sub_directory_key));
+        let chunks_of_one_file = unwrap_option!(documents.find_file(&"a.bin".to_string()),
+                                                "File not found")
+                                     .get_chunk_names()
+                                     .len();
+        assert_eq!(usage.get_chunk_count(), chunks_of_one_file as u64);
+
+        // Historical versions of the versioned directory
+        let mut writer = unwrap_result!(file_helper.create("c.txt".to_string(),
+                                                           Vec::new(),
+                                                           directory));
+        writer.write(&[1u8; 10], 0);
+        let (updated_directory, _) = unwrap_result!(writer.close());
+        directory = updated_directory;
+        let file = unwrap_option!(directory.find_file(&"c.txt".to_string()), "File not found")
+                       .clone();
+        let mut writer = unwrap_result!(file_helper.update_content(file,
+                                                                   Mode::Overwrite,
+                                                                   directory));
+        writer.write(&[2u8; 20], 0);
+        let (updated_directory, _) = unwrap_result!(writer.close());
+        directory = updated_directory;
+
+        let usage = unwrap_result!(dir_helper.get_usage(directory.get_key(), true));
+        assert_eq!(usage.get_file_count(), 3);
+        assert_eq!(usage.get_size(), 2 * content.len() as u64 + 20);
+        let versions = unwrap_result!(dir_helper.get_versions(directory.get_key().get_id(),
+                                                              directory.get_key().get_type_tag()));
+        assert_eq!(usage.get_version_count(), versions.len() as u64 - 1);
+        assert_eq!(usage.get_versions_size(), 10);
+    }
+
+    fn get_shard_head(dir_helper: &DirectoryHelper,
+                      directory_key: &DirectoryKey)
+                      -> ShardedListingHead {
+        let structured_data =
+            unwrap_result!(dir_helper.get_structured_data(directory_key.get_id(),
+                                                          directory_key.get_type_tag()));
+        unwrap_result!(dir_helper.get_sharded_listing_head(directory_key, &structured_data))
+    }
+
+    // Checks that only the shard holding `name` was rewritten between the heads, and that the
+    // shard it replaced is gone
+    fn assert_only_shard_rewritten(dir_helper: &DirectoryHelper,
+                                   directory_key: &DirectoryKey,
+                                   head_before: &ShardedListingHead,
+                                   head_after: &ShardedListingHead,
+                                   name: &String) {
+        assert_eq!(head_after.get_shard_count(), ::DIRECTORY_LISTING_SHARD_COUNT);
+        let changed_shard = shard::get_shard_index(name, ::DIRECTORY_LISTING_SHARD_COUNT);
+        for index in 0..::DIRECTORY_LISTING_SHARD_COUNT {
+            assert_eq!(head_after.get_shard_generation(index) ==
+                       head_before.get_shard_generation(index),
+                       index != changed_shard);
+            let shard_id = unwrap_option!(head_after.get_shard_id(directory_key.get_id(), index),
+                                          "Shard should exist");
+            assert!(dir_helper.get_structured_data(&shard_id, ::DIRECTORY_LISTING_SHARD_TAG)
+                              .is_ok());
+        }
+        let replaced_shard_id =
+            unwrap_option!(head_before.get_shard_id(directory_key.get_id(), changed_shard),
+                           "Shard should exist");
+        assert!(dir_helper.get_structured_data(&replaced_shard_id, ::DIRECTORY_LISTING_SHARD_TAG)
+                          .is_err());
+    }
+
+    #[test]
+    fn sharded_directory() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+
+        // Sharded directories can not be versioned
+        match dir_helper.create("Sharded".to_string(),
+                                ::SHARDED_DIRECTORY_LISTING_TAG,
+                                Vec::new(),
+                                true,
+                                ::AccessLevel::Private,
+                                None) {
+            Err(NfsError::ParameterIsNotValid(_)) => (),
+            _ => panic!("Expected ParameterIsNotValid"),
+        }
+
+        let (mut directory, _) = unwrap_result!(dir_helper.create("Sharded".to_string(),
+                                                                  ::SHARDED_DIRECTORY_LISTING_TAG,
+                                                                  Vec::new(),
+                                                                  false,
+                                                                  ::AccessLevel::Private,
+                                                                  None));
+        for i in 0..20 {
+            directory.upsert_file(unwrap_result!(File::new(FileMetadata::new(format!("{}.txt", i),
+                                                                             Vec::new()),
+                                                           DataMap::None)));
+        }
+        let _ = unwrap_result!(dir_helper.update(&directory));
+        let retrieved_directory = unwrap_result!(dir_helper.get(directory.get_key()));
+        assert_eq!(retrieved_directory.get_files().len(), 20);
+        for i in 0..20 {
+            assert!(retrieved_directory.find_file(&format!("{}.txt", i)).is_some());
+        }
+
+        // Only the shard holding the new file is rewritten
+        let head_before = get_shard_head(&dir_helper, directory.get_key());
+        let file_name = "new.txt".to_string();
+        directory.upsert_file(unwrap_result!(File::new(FileMetadata::new(file_name.clone(),
+                                                                         Vec::new()),
+                                                       DataMap::None)));
+        let _ = unwrap_result!(dir_helper.update(&directory));
+        let head_after = get_shard_head(&dir_helper, directory.get_key());
+        assert_only_shard_rewritten(&dir_helper,
+                                    directory.get_key(),
+                                    &head_before,
+                                    &head_after,
+                                    &file_name);
+
+        // A single file is found by fetching the shard holding it
+        let found = unwrap_option!(unwrap_result!(dir_helper.find_file(directory.get_key(),
+                                                                       &file_name)),
+                                   "File not found");
+        assert_eq!(*found.get_name(), file_name);
+        assert!(unwrap_result!(dir_helper.find_file(directory.get_key(),
+                                                    &"missing.txt".to_string()))
+                    .is_none());
+
+        // Paginated listing visits every file once
+        let mut names = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (files, next_cursor) = unwrap_result!(dir_helper.list_files(directory.get_key(),
+                                                                            cursor.as_ref(),
+                                                                            4));
+            assert!(files.len() <= 4);
+            names.extend(files.iter().map(|file| file.get_name().clone()));
+            cursor = match next_cursor {
+                Some(next_cursor) => Some(next_cursor),
+                None => break,
+            };
+        }
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), 21);
+        assert!(dir_helper.list_files(directory.get_key(), None, 0).is_err());
+
+        // Sub directories are sharded by name as well
+        let head_before = get_shard_head(&dir_helper, directory.get_key());
+        let sub_directory_name = "Sub".to_string();
+        let _ = unwrap_result!(dir_helper.create(sub_directory_name.clone(),
+                                                 ::VERSIONED_DIRECTORY_LISTING_TAG,
+                                                 Vec::new(),
+                                                 true,
+                                                 ::AccessLevel::Private,
+                                                 Some(&mut directory)));
+        let head_after = get_shard_head(&dir_helper, directory.get_key());
+        assert_only_shard_rewritten(&dir_helper,
+                                    directory.get_key(),
+                                    &head_before,
+                                    &head_after,
+                                    &sub_directory_name);
+        let retrieved_directory = unwrap_result!(dir_helper.get(directory.get_key()));
+        assert!(retrieved_directory.find_sub_directory(&sub_directory_name).is_some());
+        assert_eq!(retrieved_directory.get_files().len(), 21);
+    }
+
     #[test]
     fn delete_directory() {
         let test_client = unwrap_result!(test_utils::get_client());
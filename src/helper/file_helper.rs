@@ -15,27 +15,46 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
+use std::cmp;
 use std::sync::{Arc, Mutex};
 
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+
 use directory_listing::DirectoryListing;
 use errors::NfsError;
 use file::File;
 use helper::directory_helper::DirectoryHelper;
+use helper::history;
+use helper::link;
+use helper::name_validator::NameValidator;
 use helper::reader::Reader;
 use helper::writer::{Mode, Writer};
 use metadata::file_metadata::FileMetadata;
 use safe_core::client::Client;
 use self_encryption::DataMap;
 
+/// Size of the pieces content is read in by `verify`
+const VERIFY_READ_LENGTH: u64 = 1024 * 1024;
+
 /// File provides helper functions to perform Operations on Files
 pub struct FileHelper {
     client: Arc<Mutex<Client>>,
+    name_validator: NameValidator,
 }
 
 impl FileHelper {
     /// Create a new FileHelper instance
     pub fn new(client: Arc<Mutex<Client>>) -> FileHelper {
-        FileHelper { client: client }
+        FileHelper {
+            client: client,
+            name_validator: NameValidator::new(),
+        }
+    }
+
+    /// Replaces the rules the names of created, moved and renamed files are validated against
+    pub fn set_name_validator(&mut self, name_validator: NameValidator) {
+        self.name_validator = name_validator;
     }
 
     /// Helper function to create a file in a directory listing
@@ -48,44 +67,186 @@ impl FileHelper {
                   user_metatdata: Vec<u8>,
                   parent_directory: DirectoryListing)
                   -> Result<Writer, NfsError> {
-        match parent_directory.find_file(&name) {
-            Some(_) => Err(NfsError::FileAlreadyExistsWithSameName),
-            None => {
-                let file = try!(File::new(FileMetadata::new(name, user_metatdata), DataMap::None));
-                Ok(Writer::new(self.client.clone(), Mode::Overwrite, parent_directory, file))
-            }
-        }
+        let name = try!(self.name_validator.validate_file_name(name, &parent_directory, None));
+        let file = try!(File::new(FileMetadata::new(name, user_metatdata), DataMap::None));
+        Ok(Writer::new(self.client.clone(), Mode::Overwrite, parent_directory, file))
     }
 
-    /// Delete a file from the DirectoryListing
+    /// Delete a file from the DirectoryListing along with its version history
+    /// If the file is linked, the shared state of the links is only deleted along with the last
+    /// link
     /// Returns Option<parent_directory's parent>
     pub fn delete(&self,
                   file_name: String,
                   parent_directory: &mut DirectoryListing)
                   -> Result<Option<DirectoryListing>, NfsError> {
         debug!("Deleting {:?} file from directory listing ...", file_name);
+        let file = try!(parent_directory.find_file(&file_name)
+                                        .map(|file| file.clone())
+                                        .ok_or(NfsError::FileNotFound(file_name.clone())));
         try!(parent_directory.remove_file(&file_name));
         let directory_helper = DirectoryHelper::new(self.client.clone());
-        directory_helper.update(&parent_directory)
+        let updated_grand_parent = try!(directory_helper.update(&parent_directory));
+        if file.get_metadata().get_link_id().is_some() {
+            let _ = try!(link::remove_link(&self.client, &file));
+        }
+        try!(history::delete(&self.client, file.get_id()));
+        Ok(updated_grand_parent)
     }
 
-    /// Updates the file metadata.
+    /// Updates the file metadata and, once the parent directory is saved, appends the updated
+    /// file to its version history.
     /// Returns Option<parent_directory's parent>
     pub fn update_metadata(&self,
-                           file: File,
+                           mut file: File,
                            parent_directory: &mut DirectoryListing)
                            -> Result<Option<DirectoryListing>, NfsError> {
-        {
+        let renamed = {
             let existing_file = try!(parent_directory.find_file_by_id(file.get_id())
-                                                     .ok_or(NfsError::FileNotFound));
-            if existing_file.get_name() != file.get_name() &&
-               parent_directory.find_file(file.get_name()).is_some() {
-                return Err(NfsError::FileAlreadyExistsWithSameName);
+                                                     .ok_or(NfsError::FileNotFound(file.get_name()
+                                                                                       .clone())));
+            existing_file.get_name() != file.get_name()
+        };
+        if renamed {
+            let name = try!(self.name_validator.validate_file_name(file.get_name().clone(),
+                                                                   parent_directory,
+                                                                   Some(file.get_id())));
+            file.get_mut_metadata().set_name(name);
+        }
+        parent_directory.upsert_file(file.clone());
+        let directory_helper = DirectoryHelper::new(self.client.clone());
+        let updated_grand_parent = try!(directory_helper.update(&parent_directory));
+        try!(history::record(&self.client, &file));
+        Ok(updated_grand_parent)
+    }
+
+    /// Moves a file from the source directory to the destination directory under `new_name`.
+    /// If the destination is `None`, the file is renamed within the source directory.
+    /// Both the source and the destination listings are updated in the network.
+    pub fn move_file(&self,
+                     file_name: &String,
+                     new_name: String,
+                     source_directory: &mut DirectoryListing,
+                     destination_directory: Option<&mut DirectoryListing>)
+                     -> Result<(), NfsError> {
+        let mut file = try!(source_directory.find_file(file_name)
+                                            .map(|file| file.clone())
+                                            .ok_or(NfsError::FileNotFound(file_name.clone())));
+        let directory_helper = DirectoryHelper::new(self.client.clone());
+        match destination_directory {
+            Some(destination_directory) => {
+                if destination_directory.get_key() == source_directory.get_key() {
+                    return Err(NfsError::DestinationAndSourceAreSame);
+                }
+                let new_name = try!(self.name_validator
+                                        .validate_file_name(new_name, destination_directory, None));
+                let source_file = file.clone();
+                file.get_mut_metadata().set_name(new_name.clone());
+                debug!("Moving {:?} file to destination directory ...", file_name);
+                // The file is added to the destination before it leaves the source, so that a
+                // failed move can at worst leave it listed in both directories, never in neither
+                destination_directory.upsert_file(file);
+                destination_directory.get_mut_metadata().set_modified_time(::time::now_utc());
+                if let Err(error) = directory_helper.update(destination_directory) {
+                    let _ = destination_directory.remove_file(&new_name);
+                    return Err(error);
+                }
+                try!(source_directory.remove_file(file_name));
+                source_directory.get_mut_metadata().set_modified_time(::time::now_utc());
+                if let Err(error) = directory_helper.update(source_directory) {
+                    debug!("Moving {:?} file failed, taking it out of destination directory ...",
+                           file_name);
+                    source_directory.upsert_file(source_file);
+                    if destination_directory.remove_file(&new_name).is_ok() {
+                        destination_directory.get_mut_metadata()
+                                             .set_modified_time(::time::now_utc());
+                        let result = directory_helper.update(destination_directory);
+                        if let Err(rollback_error) = result {
+                            debug!("Taking {:?} file out of destination directory failed: {:?}",
+                                   new_name,
+                                   rollback_error);
+                        }
+                    }
+                    return Err(error);
+                }
+            }
+            None => {
+                let new_name = try!(self.name_validator.validate_file_name(new_name,
+                                                                           source_directory,
+                                                                           Some(file.get_id())));
+                debug!("Renaming {:?} file to {:?} ...", file_name, new_name);
+                file.get_mut_metadata().set_name(new_name);
+                source_directory.upsert_file(file);
+                source_directory.get_mut_metadata().set_modified_time(::time::now_utc());
+                let _ = try!(directory_helper.update(source_directory));
             }
         }
-        parent_directory.upsert_file(file);
+        Ok(())
+    }
+
+    /// Creates a hard link named `link_name` to a file in the source directory, in the destination
+    /// directory or, if the destination is `None`, in the source directory itself.
+    /// All links share the content of the file and the metadata derived from it, so that content
+    /// written through one link is seen through all of them. Each link has its own name and id.
+    /// The listings changed are updated in the network.
+    pub fn link(&self,
+                file_name: &String,
+                link_name: String,
+                source_directory: &mut DirectoryListing,
+                destination_directory: Option<&mut DirectoryListing>)
+                -> Result<(), NfsError> {
+        let mut file = try!(source_directory.find_file(file_name)
+                                            .map(|file| file.clone())
+                                            .ok_or(NfsError::FileNotFound(file_name.clone())));
+        let link_name = match destination_directory {
+            Some(ref destination_directory) => {
+                if destination_directory.get_key() == source_directory.get_key() {
+                    return Err(NfsError::DestinationAndSourceAreSame);
+                }
+                try!(self.name_validator.validate_file_name(link_name, destination_directory, None))
+            }
+            None => try!(self.name_validator.validate_file_name(link_name, source_directory, None)),
+        };
         let directory_helper = DirectoryHelper::new(self.client.clone());
-        directory_helper.update(&parent_directory)
+        // The count is raised before the new link is saved, so that a failure can only leave the
+        // shared state behind, never delete it while a link still refers to it
+        if file.get_metadata().get_link_id().is_some() {
+            file = try!(link::resolve(&self.client, &file));
+            let _ = try!(link::add_link(&self.client, &file));
+        } else {
+            debug!("Creating shared state for {:?} file ...", file_name);
+            let link_id = try!(link::create(&self.client, &file, 2));
+            file.get_mut_metadata().set_link_id(Some(link_id));
+            source_directory.upsert_file(file.clone());
+            let _ = try!(directory_helper.update(source_directory));
+        }
+        let mut metadata = file.get_metadata().clone();
+        metadata.set_name(link_name);
+        let linked_file = try!(File::new(metadata, file.get_datamap().clone()));
+        debug!("Linking {:?} file as {:?} ...", file_name, linked_file.get_name());
+        let directory = match destination_directory {
+            Some(destination_directory) => destination_directory,
+            None => source_directory,
+        };
+        directory.upsert_file(linked_file);
+        directory.get_mut_metadata().set_modified_time(::time::now_utc());
+        let _ = try!(directory_helper.update(directory));
+        Ok(())
+    }
+
+    /// Returns the file with the content shared by its links. Files which are not linked are
+    /// returned unchanged.
+    pub fn resolve_link(&self, file: &File) -> Result<File, NfsError> {
+        link::resolve(&self.client, file)
+    }
+
+    /// Returns the number of directory entries linking to the content of the file
+    pub fn get_link_count(&self, file: &File) -> Result<u64, NfsError> {
+        if file.get_metadata().get_link_id().is_some() {
+            link::get_link_count(&self.client, file)
+        } else {
+            Ok(1)
+        }
     }
 
     /// Helper function to Update content of a file in a directory listing
@@ -93,6 +254,8 @@ impl FileHelper {
     /// can be written to the network
     /// The file is actually saved in the directory listing only after
     /// `writer.close()` is invoked
+    /// Passing `Mode::Append` makes every write land at the end of the existing content
+    /// The content of linked files is updated for all their links
     pub fn update_content(&self,
                           file: File,
                           mode: Mode,
@@ -100,20 +263,88 @@ impl FileHelper {
                           -> Result<Writer, NfsError> {
         {
             let existing_file = try!(parent_directory.find_file(file.get_name())
-                                                     .ok_or(NfsError::FileNotFound));
-            if *existing_file != file {
+                                                     .ok_or(NfsError::FileNotFound(file.get_name()
+                                                                                       .clone())));
+            // The entry of a linked file may hold older content than the one shared by its links
+            if *existing_file != file && try!(link::resolve(&self.client, existing_file)) != file {
                 return Err(NfsError::FileDoesNotMatch);
             }
         }
+        let file = try!(link::resolve(&self.client, &file));
         Ok(Writer::new(self.client.clone(), mode, parent_directory, file))
     }
 
 
-    /// Return the versions of a directory containing modified versions of a file
+    /// Returns the versions of the file, oldest first.
+    /// A version is appended to the history of a file, kept under its id, whenever its content or
+    /// metadata is saved. The history follows the file when it is renamed or moved and does not
+    /// depend on the parent directory being versioned. Each link to a file has its own history.
+    /// Files saved before histories were kept are looked up in the versions of the parent
+    /// directory instead, any other failure to read the history is returned.
     pub fn get_versions(&self,
                         file: &File,
                         parent_directory: &DirectoryListing)
                         -> Result<Vec<File>, NfsError> {
+        match try!(history::get_versions(&self.client, file.get_id())) {
+            Some(versions) => {
+                versions.into_iter()
+                        .map(|version| {
+                            history::get_by_version(&self.client, file.get_id(), version)
+                        })
+                        .collect()
+            }
+            None => self.get_versions_from_directory(file, parent_directory),
+        }
+    }
+
+    /// Makes a version of the file, as returned by `get_versions`, current again by saving its
+    /// content and metadata as a new version. History is never rewritten. The file keeps its
+    /// current name and, if linked, the restored content is seen through all its links.
+    /// Returns Option<parent_directory's parent>
+    pub fn restore_version(&self,
+                           file: &File,
+                           version: &File,
+                           parent_directory: &mut DirectoryListing)
+                           -> Result<Option<DirectoryListing>, NfsError> {
+        if version.get_id() != file.get_id() {
+            return Err(NfsError::ParameterIsNotValid(format!("Not a version of {:?}",
+                                                             file.get_name())));
+        }
+        let existing_file = try!(parent_directory.find_file_by_id(file.get_id())
+                                                 .map(|file| file.clone())
+                                                 .ok_or(NfsError::FileNotFound(file.get_name()
+                                                                                   .clone())));
+        let mut restored_file = version.clone();
+        {
+            let metadata = restored_file.get_mut_metadata();
+            metadata.set_name(existing_file.get_name().clone());
+            metadata.set_link_id(existing_file.get_metadata().get_link_id().cloned());
+            metadata.set_modified_time(::time::now_utc());
+        }
+        debug!("Restoring a version of {:?} file ...", restored_file.get_name());
+        if restored_file.get_metadata().get_link_id().is_some() {
+            try!(link::update_content(&self.client, &restored_file));
+        }
+        parent_directory.upsert_file(restored_file.clone());
+        let directory_helper = DirectoryHelper::new(self.client.clone());
+        let updated_grand_parent = try!(directory_helper.update(&parent_directory));
+        try!(history::record(&self.client, &restored_file));
+        Ok(updated_grand_parent)
+    }
+
+    /// Returns the number of versions in the history of the file, without fetching them. A file
+    /// without a history has none.
+    pub fn get_version_count(&self, file: &File) -> Result<usize, NfsError> {
+        Ok(try!(history::get_versions(&self.client, file.get_id())).map_or(0, |versions| {
+            versions.len()
+        }))
+    }
+
+    /// Return the versions of a directory containing modified versions of a file
+    fn get_versions_from_directory(&self,
+                                   file: &File,
+                                   parent_directory: &DirectoryListing)
+                                   -> Result<Vec<File>, NfsError> {
         let mut versions = Vec::<File>::new();
         let directory_helper = DirectoryHelper::new(self.client.clone());
 
@@ -143,11 +374,34 @@ impl FileHelper {
     pub fn read<'a>(&self, file: &'a File) -> Reader<'a> {
         Reader::new(self.client.clone(), file)
     }
+
+    /// Reads the file content back from the network and compares its SHA-256 digest with the one
+    /// recorded when the content was written.
+    /// Returns true if the content is intact
+    pub fn verify(&self, file: &File) -> Result<bool, NfsError> {
+        let expected_hash = try!(file.get_metadata().get_content_hash().ok_or_else(|| {
+            NfsError::ParameterIsNotValid(format!("No content hash is recorded for {:?}",
+                                                  file.get_name()))
+        }));
+        let mut reader = self.read(file);
+        let size = reader.size();
+        let mut hasher = Sha256::new();
+        let mut position = 0;
+        while position < size {
+            let length = cmp::min(VERIFY_READ_LENGTH, size - position);
+            hasher.input(&try!(reader.read(position, length)));
+            position += length;
+        }
+        let mut content_hash = vec![0u8; hasher.output_bytes()];
+        hasher.result(&mut content_hash);
+        Ok(content_hash == *expected_hash)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use std::sync::{Arc, Mutex};
+    use errors::NfsError;
     use helper::directory_helper::DirectoryHelper;
     use helper::file_helper::FileHelper;
     use helper::writer::Mode;
@@ -237,10 +491,243 @@ mod test {
                                       "File not found");
             assert_eq!(*file.get_metadata().get_user_metadata(), vec![12u8; 10]);
         }
+        {
+            // Rename
+            let new_name = "hello_renamed.txt".to_string();
+            unwrap_result!(file_helper.move_file(&file_name,
+                                                 new_name.clone(),
+                                                 &mut directory,
+                                                 None));
+            assert!(directory.find_file(&file_name).is_none());
+            assert!(directory.find_file(&new_name).is_some());
+            unwrap_result!(file_helper.move_file(&new_name,
+                                                 file_name.clone(),
+                                                 &mut directory,
+                                                 None));
+            assert!(directory.find_file(&file_name).is_some());
+        }
+        {
+            // Move
+            let (mut destination, _) =
+                unwrap_result!(dir_helper.create("Destination".to_string(),
+                                                 ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                 Vec::new(),
+                                                 false,
+                                                 ::AccessLevel::Private,
+                                                 None));
+            let mut writer = unwrap_result!(file_helper.create(file_name.clone(),
+                                                               Vec::new(),
+                                                               destination));
+            writer.write(&vec![3u8; 10], 0);
+            let (updated_destination, _) = unwrap_result!(writer.close());
+            destination = updated_destination;
+            match file_helper.move_file(&file_name,
+                                        file_name.clone(),
+                                        &mut directory,
+                                        Some(&mut destination)) {
+                Err(NfsError::FileAlreadyExistsWithSameName) => (),
+                _ => panic!("Expected FileAlreadyExistsWithSameName"),
+            }
+            let moved_name = "moved.txt".to_string();
+            unwrap_result!(file_helper.move_file(&file_name,
+                                                 moved_name.clone(),
+                                                 &mut directory,
+                                                 Some(&mut destination)));
+            assert!(directory.find_file(&file_name).is_none());
+            let destination = unwrap_result!(dir_helper.get(destination.get_key()));
+            let file = unwrap_option!(destination.find_file(&moved_name), "File not found");
+            let mut reader = file_helper.read(file);
+            let size = reader.size();
+            assert_eq!(&unwrap_result!(reader.read(0, size))[0..10], [2u8; 10]);
+            let source = unwrap_result!(dir_helper.get(directory.get_key()));
+            assert!(source.find_file(&file_name).is_none());
+            // Move it back so that it can be deleted below
+            let mut destination = destination;
+            unwrap_result!(file_helper.move_file(&moved_name,
+                                                 file_name.clone(),
+                                                 &mut destination,
+                                                 Some(&mut directory)));
+        }
         {
             // Delete
             let _ = unwrap_result!(file_helper.delete(file_name.clone(), &mut directory));
             assert!(directory.find_file(&file_name).is_none());
         }
     }
+
+    #[test]
+    fn verify_content() {
+        let client = get_client();
+        let dir_helper = DirectoryHelper::new(client.clone());
+        let (directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                            ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                            Vec::new(),
+                                                            false,
+                                                            ::AccessLevel::Private,
+                                                            None));
+        let file_helper = FileHelper::new(client);
+        let file_name = "photo.png".to_string();
+        let mut writer = unwrap_result!(file_helper.create(file_name.clone(),
+                                                           Vec::new(),
+                                                           directory));
+        writer.write(b"\x89PNG\r\n\x1a\n", 0);
+        writer.write(&[1u8; 100], 8);
+        let (directory, _) = unwrap_result!(writer.close());
+        let mut file = unwrap_option!(directory.find_file(&file_name), "File not found").clone();
+        assert_eq!(file.get_metadata().get_mime_type(), Some(&"image/png".to_string()));
+        assert_eq!(unwrap_option!(file.get_metadata().get_content_hash(), "Hash not found").len(),
+                   32);
+        assert!(unwrap_result!(file_helper.verify(&file)));
+
+        file.get_mut_metadata().set_content_hash(Some(vec![0u8; 32]));
+        assert!(!unwrap_result!(file_helper.verify(&file)));
+        file.get_mut_metadata().set_content_hash(None);
+        match file_helper.verify(&file) {
+            Err(NfsError::ParameterIsNotValid(_)) => (),
+            _ => panic!("Expected ParameterIsNotValid"),
+        }
+    }
+
+    #[test]
+    fn hard_links() {
+        let client = get_client();
+        let dir_helper = DirectoryHelper::new(client.clone());
+        let (directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                            ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                            Vec::new(),
+                                                            false,
+                                                            ::AccessLevel::Private,
+                                                            None));
+        let (mut destination, _) =
+            unwrap_result!(dir_helper.create("Destination".to_string(),
+                                             ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                             Vec::new(),
+                                             false,
+                                             ::AccessLevel::Private,
+                                             None));
+        let file_helper = FileHelper::new(client.clone());
+        let file_name = "hello.txt".to_string();
+        let link_name = "linked.txt".to_string();
+        let mut writer = unwrap_result!(file_helper.create(file_name.clone(),
+                                                           Vec::new(),
+                                                           directory));
+        writer.write(&vec![0u8; 100], 0);
+        let (mut directory, _) = unwrap_result!(writer.close());
+        {
+            let file = unwrap_option!(directory.find_file(&file_name), "File not found");
+            assert_eq!(unwrap_result!(file_helper.get_link_count(file)), 1);
+        }
+        unwrap_result!(file_helper.link(&file_name,
+                                        link_name.clone(),
+                                        &mut directory,
+                                        Some(&mut destination)));
+        let linked_file = unwrap_option!(destination.find_file(&link_name), "File not found")
+                              .clone();
+        assert_eq!(unwrap_result!(file_helper.get_link_count(&linked_file)), 2);
+        {
+            let file = unwrap_option!(directory.find_file(&file_name), "File not found");
+            assert!(file.get_id() != linked_file.get_id());
+            assert_eq!(file.get_metadata().get_link_id(),
+                       linked_file.get_metadata().get_link_id());
+        }
+
+        // Content written through the link is seen through the original entry
+        let mut writer = unwrap_result!(file_helper.update_content(linked_file,
+                                                                   Mode::Overwrite,
+                                                                   destination));
+        writer.write(&vec![1u8; 50], 0);
+        let (mut destination, _) = unwrap_result!(writer.close());
+        {
+            let file = unwrap_option!(directory.find_file(&file_name), "File not found");
+            let file = unwrap_result!(file_helper.resolve_link(file));
+            let mut reader = file_helper.read(&file);
+            let size = reader.size();
+            assert_eq!(unwrap_result!(reader.read(0, size)), vec![1u8; 50]);
+        }
+        {
+            // Reading the entry held before the write resolves the link too
+            let file = unwrap_option!(directory.find_file(&file_name), "File not found");
+            let mut reader = file_helper.read(file);
+            let size = reader.size();
+            assert_eq!(unwrap_result!(reader.read(0, size)), vec![1u8; 50]);
+        }
+        {
+            // Listings fetched from the network hold the shared content of their links
+            let fetched = unwrap_result!(dir_helper.get(directory.get_key()));
+            let file = unwrap_option!(fetched.find_file(&file_name), "File not found");
+            assert_eq!(file.get_metadata().get_size(), 50);
+            let mut reader = file_helper.read(file);
+            let size = reader.size();
+            assert_eq!(unwrap_result!(reader.read(0, size)), vec![1u8; 50]);
+        }
+
+        // The shared content outlives all but the last link
+        let _ = unwrap_result!(file_helper.delete(file_name.clone(), &mut directory));
+        let linked_file = unwrap_option!(destination.find_file(&link_name), "File not found")
+                              .clone();
+        assert_eq!(unwrap_result!(file_helper.get_link_count(&linked_file)), 1);
+        let _ = unwrap_result!(file_helper.delete(link_name.clone(), &mut destination));
+        match file_helper.get_link_count(&linked_file) {
+            Err(_) => (),
+            Ok(_) => panic!("Shared state of the links should have been deleted"),
+        }
+    }
+
+    #[test]
+    fn file_history() {
+        let client = get_client();
+        let dir_helper = DirectoryHelper::new(client.clone());
+        let (directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                            ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                            Vec::new(),
+                                                            false,
+                                                            ::AccessLevel::Private,
+                                                            None));
+        let file_helper = FileHelper::new(client.clone());
+        let file_name = "hello.txt".to_string();
+        let mut writer = unwrap_result!(file_helper.create(file_name.clone(),
+                                                           Vec::new(),
+                                                           directory));
+        writer.write(&vec![0u8; 100], 0);
+        let (mut directory, _) = unwrap_result!(writer.close());
+
+        // The history survives a rename
+        let new_name = "hello_renamed.txt".to_string();
+        unwrap_result!(file_helper.move_file(&file_name, new_name.clone(), &mut directory, None));
+        let file = unwrap_option!(directory.find_file(&new_name), "File not found").clone();
+        let mut writer = unwrap_result!(file_helper.update_content(file,
+                                                                   Mode::Overwrite,
+                                                                   directory));
+        writer.write(&vec![1u8; 50], 0);
+        let (mut directory, _) = unwrap_result!(writer.close());
+
+        let mut file = unwrap_option!(directory.find_file(&new_name), "File not found").clone();
+        file.get_mut_metadata().set_user_metadata(vec![2u8; 10]);
+        let _ = unwrap_result!(file_helper.update_metadata(file, &mut directory));
+
+        let file = unwrap_option!(directory.find_file(&new_name), "File not found").clone();
+        assert_eq!(unwrap_result!(file_helper.get_version_count(&file)), 3);
+        let versions = unwrap_result!(file_helper.get_versions(&file, &directory));
+        assert_eq!(versions.len(), 3);
+        assert_eq!(*versions[0].get_name(), file_name);
+        assert_eq!(versions[0].get_metadata().get_size(), 100);
+        assert_eq!(*versions[1].get_name(), new_name);
+        assert_eq!(versions[1].get_metadata().get_size(), 50);
+        assert!(versions[1].get_metadata().get_user_metadata().is_empty());
+        assert_eq!(versions[2], file);
+        {
+            let mut reader = file_helper.read(&versions[0]);
+            assert_eq!(unwrap_result!(reader.read(0, 100)), vec![0u8; 100]);
+        }
+
+        // Restoring appends the old state to the history under the current name
+        let _ = unwrap_result!(file_helper.restore_version(&file, &versions[0], &mut directory));
+        let restored_file = unwrap_option!(directory.find_file(&new_name), "File not found")
+                                .clone();
+        assert_eq!(restored_file.get_metadata().get_size(), 100);
+        assert!(*restored_file.get_datamap() == *versions[0].get_datamap());
+        let versions = unwrap_result!(file_helper.get_versions(&restored_file, &directory));
+        assert_eq!(versions.len(), 4);
+        assert_eq!(versions[3], restored_file);
+    }
 }
@@ -0,0 +1,112 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+
+use std::sync::{Arc, Mutex};
+
+use directory_listing::DirectoryListing;
+use errors::NfsError;
+use file::File;
+use helper::storage;
+use maidsafe_utilities::serialisation::{serialise, deserialise};
+use routing::{Data, ImmutableDataType};
+use safe_core::client::Client;
+use safe_core::structured_data_operations::versioned;
+use xor_name::XorName;
+
+// The history of a file is a versioned StructuredData of type `FILE_VERSIONS_TAG` named after the
+// id of the file. Every version is the File, as it was saved, held encrypted in an ImmutableData.
+
+/// Appends the current state of `file` to its history, creating the history on the first call
+pub fn record(client: &Arc<Mutex<Client>>, file: &File) -> Result<(), NfsError> {
+    let serialised_file = try!(serialise(file));
+    let nonce = DirectoryListing::generate_nonce(file.get_id());
+    let encrypted_file = try!(unwrap_result!(client.lock())
+                                  .hybrid_encrypt(&serialised_file, Some(&nonce)));
+    let version = try!(storage::save_as_immutable_data(client,
+                                                       encrypted_file,
+                                                       ImmutableDataType::Normal));
+    let signing_key = try!(unwrap_result!(client.lock()).get_secret_signing_key()).clone();
+    match try!(storage::find_structured_data(client, file.get_id(), ::FILE_VERSIONS_TAG)) {
+        Some(structured_data) => {
+            let updated_structured_data =
+                try!(versioned::append_version(&mut *unwrap_result!(client.lock()),
+                                               structured_data,
+                                               version,
+                                               &signing_key));
+            debug!("Posting updated history of file to the network ...");
+            try!(unwrap_result!(client.lock())
+                     .post(Data::Structured(updated_structured_data), None));
+        }
+        None => {
+            let owner_key = try!(unwrap_result!(client.lock()).get_public_signing_key()).clone();
+            let structured_data = try!(versioned::create(&*unwrap_result!(client.lock()),
+                                                         version,
+                                                         ::FILE_VERSIONS_TAG,
+                                                         file.get_id().clone(),
+                                                         0,
+                                                         vec![owner_key],
+                                                         Vec::new(),
+                                                         &signing_key));
+            debug!("Posting PUT request to save history of file to the network ...");
+            try!(unwrap_result!(client.lock()).put(Data::Structured(structured_data), None));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the names of the versions in the history of the file with `file_id`, oldest first, or
+/// None if the file has no history
+pub fn get_versions(client: &Arc<Mutex<Client>>,
+                    file_id: &XorName)
+                    -> Result<Option<Vec<XorName>>, NfsError> {
+    match try!(storage::find_structured_data(client, file_id, ::FILE_VERSIONS_TAG)) {
+        Some(structured_data) => {
+            Ok(Some(try!(versioned::get_all_versions(&mut *unwrap_result!(client.lock()),
+                                                     &structured_data))))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Returns the File saved as `version` in the history of the file with `file_id`
+pub fn get_by_version(client: &Arc<Mutex<Client>>,
+                      file_id: &XorName,
+                      version: XorName)
+                      -> Result<File, NfsError> {
+    let immutable_data = try!(storage::get_immutable_data(client,
+                                                          version,
+                                                          ImmutableDataType::Normal));
+    let nonce = DirectoryListing::generate_nonce(file_id);
+    let serialised_file = try!(unwrap_result!(client.lock())
+                                   .hybrid_decrypt(immutable_data.value(), Some(&nonce)));
+    Ok(try!(deserialise(&serialised_file)))
+}
+
+/// Deletes the history of the file with `file_id`, if it has one
+pub fn delete(client: &Arc<Mutex<Client>>, file_id: &XorName) -> Result<(), NfsError> {
+    if let Some(structured_data) = try!(storage::find_structured_data(client,
+                                                                      file_id,
+                                                                      ::FILE_VERSIONS_TAG)) {
+        debug!("Deleting history of file ...");
+        try!(storage::delete_unversioned_data(client,
+                                              file_id.clone(),
+                                              ::FILE_VERSIONS_TAG,
+                                              structured_data.get_version()));
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+
+use std::sync::{Arc, Mutex};
+
+use errors::NfsError;
+use file::File;
+use helper::storage;
+use maidsafe_utilities::serialisation::{serialise, deserialise};
+use routing::{Data, StructuredData};
+use safe_core::client::Client;
+use safe_core::utility;
+use xor_name::XorName;
+
+/// Shared state of a hard linked file. It is held in an unversioned, private StructuredData of
+/// type `FILE_LINK_TAG` and every directory entry linking to it carries its id in the metadata.
+/// The content and the metadata derived from it (size, modified time, content hash and MIME type)
+/// are shared by all links, while the name and the remaining metadata belong to each entry.
+#[derive(RustcEncodable, RustcDecodable, Debug, Clone)]
+struct FileLink {
+    file: File,
+    link_count: u64,
+}
+
+/// Creates the shared state for `file` with `link_count` links and returns its id
+pub fn create(client: &Arc<Mutex<Client>>,
+              file: &File,
+              link_count: u64)
+              -> Result<XorName, NfsError> {
+    let link_id = XorName(try!(utility::generate_random_array_u8_64()));
+    let file_link = FileLink {
+        file: file.clone(),
+        link_count: link_count,
+    };
+    let structured_data = try!(storage::create_unversioned_data(client,
+                                                                link_id.clone(),
+                                                                ::FILE_LINK_TAG,
+                                                                0,
+                                                                try!(serialise(&file_link)),
+                                                                &::AccessLevel::Private));
+    debug!("Posting PUT request to save shared state of linked file ...");
+    try!(unwrap_result!(client.lock()).put(Data::Structured(structured_data), None));
+    Ok(link_id)
+}
+
+/// Returns `file` with its content and the metadata derived from it replaced by the shared state
+/// of the links. Files which are not linked are returned unchanged.
+pub fn resolve(client: &Arc<Mutex<Client>>, file: &File) -> Result<File, NfsError> {
+    let mut resolved = file.clone();
+    if let Some(link_id) = file.get_metadata().get_link_id() {
+        let (file_link, _) = try!(get(client, link_id));
+        copy_content(&file_link.file, &mut resolved);
+    }
+    Ok(resolved)
+}
+
+/// Records the content of the linked `file` as the shared state of all its links
+pub fn update_content(client: &Arc<Mutex<Client>>, file: &File) -> Result<(), NfsError> {
+    let link_id = try!(get_link_id(file));
+    let (mut file_link, structured_data) = try!(get(client, &link_id));
+    copy_content(file, &mut file_link.file);
+    post(client, link_id, &file_link, &structured_data)
+}
+
+/// Increments the number of links to the shared state of `file`.
+/// Returns the updated number of links
+pub fn add_link(client: &Arc<Mutex<Client>>, file: &File) -> Result<u64, NfsError> {
+    let link_id = try!(get_link_id(file));
+    let (mut file_link, structured_data) = try!(get(client, &link_id));
+    file_link.link_count += 1;
+    try!(post(client, link_id, &file_link, &structured_data));
+    Ok(file_link.link_count)
+}
+
+/// Decrements the number of links to the shared state of `file`. The shared state is deleted from
+/// the network along with the last link.
+/// Returns the number of links left
+pub fn remove_link(client: &Arc<Mutex<Client>>, file: &File) -> Result<u64, NfsError> {
+    let link_id = try!(get_link_id(file));
+    let (mut file_link, structured_data) = try!(get(client, &link_id));
+    if file_link.link_count > 1 {
+        file_link.link_count -= 1;
+        try!(post(client, link_id, &file_link, &structured_data));
+        Ok(file_link.link_count)
+    } else {
+        debug!("Deleting shared state of linked file along with its last link ...");
+        try!(storage::delete_unversioned_data(client,
+                                              link_id,
+                                              ::FILE_LINK_TAG,
+                                              structured_data.get_version()));
+        Ok(0)
+    }
+}
+
+/// Returns the number of links to the shared state of `file`
+pub fn get_link_count(client: &Arc<Mutex<Client>>, file: &File) -> Result<u64, NfsError> {
+    let link_id = try!(get_link_id(file));
+    let (file_link, _) = try!(get(client, &link_id));
+    Ok(file_link.link_count)
+}
+
+fn get_link_id(file: &File) -> Result<XorName, NfsError> {
+    file.get_metadata()
+        .get_link_id()
+        .cloned()
+        .ok_or_else(|| NfsError::ParameterIsNotValid(format!("{:?} is not a linked file",
+                                                             file.get_name())))
+}
+
+fn copy_content(from: &File, to: &mut File) {
+    to.set_datamap(from.get_datamap().clone());
+    let from_metadata = from.get_metadata();
+    let to_metadata = to.get_mut_metadata();
+    to_metadata.set_size(from_metadata.get_size());
+    to_metadata.set_modified_time(from_metadata.get_modified_time().clone());
+    to_metadata.set_content_hash(from_metadata.get_content_hash().cloned());
+    to_metadata.set_mime_type(from_metadata.get_mime_type().cloned());
+}
+
+fn get(client: &Arc<Mutex<Client>>,
+       link_id: &XorName)
+       -> Result<(FileLink, StructuredData), NfsError> {
+    let structured_data = try!(storage::get_structured_data(client, link_id, ::FILE_LINK_TAG));
+    let serialised_file_link = try!(storage::get_unversioned_data(client,
+                                                                  link_id,
+                                                                  &structured_data,
+                                                                  &::AccessLevel::Private));
+    Ok((try!(deserialise(&serialised_file_link)), structured_data))
+}
+
+fn post(client: &Arc<Mutex<Client>>,
+        link_id: XorName,
+        file_link: &FileLink,
+        structured_data: &StructuredData)
+        -> Result<(), NfsError> {
+    let updated_structured_data =
+        try!(storage::create_unversioned_data(client,
+                                              link_id,
+                                              ::FILE_LINK_TAG,
+                                              structured_data.get_version() + 1,
+                                              try!(serialise(file_link)),
+                                              &::AccessLevel::Private));
+    debug!("Posting updated shared state of linked file ...");
+    try!(unwrap_result!(client.lock()).post(Data::Structured(updated_structured_data), None));
+    Ok(())
+}
@@ -0,0 +1,97 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+/// Number of leading content bytes `detect` inspects for magic numbers
+pub const MAGIC_BYTES_LENGTH: usize = 16;
+
+/// MIME type of content which could not be identified
+pub const DEFAULT_MIME_TYPE: &'static str = "application/octet-stream";
+
+const EXTENSIONS: &'static [(&'static str, &'static str)] = &[("bmp", "image/bmp"),
+                                                             ("css", "text/css"),
+                                                             ("csv", "text/csv"),
+                                                             ("gif", "image/gif"),
+                                                             ("gz", "application/gzip"),
+                                                             ("htm", "text/html"),
+                                                             ("html", "text/html"),
+                                                             ("ico", "image/x-icon"),
+                                                             ("jpeg", "image/jpeg"),
+                                                             ("jpg", "image/jpeg"),
+                                                             ("js", "application/javascript"),
+                                                             ("json", "application/json"),
+                                                             ("md", "text/markdown"),
+                                                             ("mp3", "audio/mpeg"),
+                                                             ("mp4", "video/mp4"),
+                                                             ("ogg", "audio/ogg"),
+                                                             ("pdf", "application/pdf"),
+                                                             ("png", "image/png"),
+                                                             ("svg", "image/svg+xml"),
+                                                             ("tar", "application/x-tar"),
+                                                             ("txt", "text/plain"),
+                                                             ("wav", "audio/wav"),
+                                                             ("webm", "video/webm"),
+                                                             ("webp", "image/webp"),
+                                                             ("xml", "application/xml"),
+                                                             ("zip", "application/zip")];
+
+const MAGIC_NUMBERS: &'static [(&'static [u8], &'static str)] =
+    &[(b"\x89PNG\r\n\x1a\n", "image/png"),
+      (b"\xff\xd8\xff", "image/jpeg"),
+      (b"GIF87a", "image/gif"),
+      (b"GIF89a", "image/gif"),
+      (b"%PDF-", "application/pdf"),
+      (b"PK\x03\x04", "application/zip"),
+      (b"\x1f\x8b", "application/gzip"),
+      (b"ID3", "audio/mpeg"),
+      (b"OggS", "audio/ogg"),
+      (b"\x1a\x45\xdf\xa3", "video/webm")];
+
+/// Returns the MIME type of a file from the extension of its name or, if the extension is missing
+/// or unknown, from the magic number at the start of its content. `content` need not hold more
+/// than the first `MAGIC_BYTES_LENGTH` bytes.
+pub fn detect(name: &str, content: &[u8]) -> String {
+    if let Some(index) = name.rfind('.') {
+        let extension = name[index + 1..].to_lowercase();
+        if let Some(&(_, mime_type)) = EXTENSIONS.iter().find(|entry| entry.0 == extension) {
+            return mime_type.to_string();
+        }
+    }
+
+    if let Some(&(_, mime_type)) = MAGIC_NUMBERS.iter().find(|entry| content.starts_with(entry.0)) {
+        return mime_type.to_string();
+    }
+    if content.len() >= 12 && &content[0..4] == b"RIFF" && &content[8..12] == b"WEBP" {
+        return "image/webp".to_string();
+    }
+    DEFAULT_MIME_TYPE.to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detect_mime_type() {
+        assert_eq!(detect("index.HTML", &[]), "text/html".to_string());
+        assert_eq!(detect("photo.jpg", b"\x89PNG\r\n\x1a\n"), "image/jpeg".to_string());
+        assert_eq!(detect("photo", b"\x89PNG\r\n\x1a\n\0\0\0\0"), "image/png".to_string());
+        assert_eq!(detect("scan.unknown", b"%PDF-1.4"), "application/pdf".to_string());
+        assert_eq!(detect("image", b"RIFF\0\0\0\0WEBPVP8 "), "image/webp".to_string());
+        assert_eq!(detect(".bashrc", b"export"), DEFAULT_MIME_TYPE.to_string());
+        assert_eq!(detect("", &[]), DEFAULT_MIME_TYPE.to_string());
+    }
+}
@@ -23,3 +23,13 @@ pub mod reader;
 pub mod file_helper;
 /// DirectoryHelper provides functions for CRUD on DirectoryListing
 pub mod directory_helper;
+/// NameValidator checks the names of files and directories
+pub mod name_validator;
+/// Walker iterates over a directory tree
+pub mod walker;
+/// Detection of the MIME type of file content
+pub mod mime_type;
+mod history;
+mod link;
+mod storage;
+mod version_log;
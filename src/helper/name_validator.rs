@@ -0,0 +1,245 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use unicode_normalization::UnicodeNormalization;
+
+use directory_listing::DirectoryListing;
+use errors::NfsError;
+use xor_name::XorName;
+
+/// Default maximum length of a name in bytes
+pub const DEFAULT_MAX_NAME_LENGTH: usize = 255;
+
+/// Rules the names of files and directories must satisfy.
+/// A name must not be empty, `.` or `..`, must not contain `/` or control characters and must not
+/// be longer than the maximum length. Optionally names can be normalised to Unicode NFC and
+/// compared case-insensitively, so that names which look alike on other platforms do not
+/// collide when synchronised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameValidator {
+    max_length: usize,
+    normalise_unicode: bool,
+    case_insensitive: bool,
+}
+
+impl NameValidator {
+    /// Create a new NameValidator with the default rules
+    pub fn new() -> NameValidator {
+        NameValidator {
+            max_length: DEFAULT_MAX_NAME_LENGTH,
+            normalise_unicode: false,
+            case_insensitive: false,
+        }
+    }
+
+    /// Set the maximum length of a name in bytes
+    pub fn set_max_length(&mut self, max_length: usize) {
+        self.max_length = max_length;
+    }
+
+    /// If set, names are converted to Unicode Normalization Form C before they are validated and
+    /// stored
+    pub fn set_normalise_unicode(&mut self, normalise_unicode: bool) {
+        self.normalise_unicode = normalise_unicode;
+    }
+
+    /// If set, names which only differ in case collide
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        self.case_insensitive = case_insensitive;
+    }
+
+    /// Validates the name.
+    /// Returns the name in the form it should be stored in
+    pub fn validate(&self, name: String) -> Result<String, NfsError> {
+        let name = if self.normalise_unicode {
+            name.nfc().collect::<String>()
+        } else {
+            name
+        };
+        if name.is_empty() {
+            return Err(NfsError::ParameterIsNotValid("Name can not be empty".to_string()));
+        }
+        if name == "." || name == ".." {
+            return Err(NfsError::ParameterIsNotValid(format!("Name can not be {:?}", name)));
+        }
+        if name.contains('/') {
+            return Err(NfsError::ParameterIsNotValid(format!("Name {:?} contains '/'", name)));
+        }
+        if name.chars().any(|character| character.is_control()) {
+            return Err(NfsError::ParameterIsNotValid(format!("Name {:?} contains control \
+                                                              characters",
+                                                             name)));
+        }
+        if name.len() > self.max_length {
+            return Err(NfsError::ParameterIsNotValid(format!("Name is {} bytes long, the \
+                                                              maximum is {}",
+                                                             name.len(),
+                                                             self.max_length)));
+        }
+        Ok(name)
+    }
+
+    /// Validates the name of a file or symlink to be stored in the directory. `file_id` is the id
+    /// of the file or symlink when it is renamed, so that it does not collide with itself.
+    /// Returns the name in the form it should be stored in
+    pub fn validate_file_name(&self,
+                              name: String,
+                              directory: &DirectoryListing,
+                              file_id: Option<&XorName>)
+                              -> Result<String, NfsError> {
+        let name = try!(self.validate(name));
+        let collides = if self.is_exact() {
+            directory.find_file(&name).map_or(false, |file| Some(file.get_id()) != file_id)
+        } else {
+            directory.get_files().iter().any(|file| {
+                Some(file.get_id()) != file_id && self.collides(file.get_name(), &name)
+            })
+        };
+        let collides_with_symlink = directory.get_symlinks().iter().any(|symlink| {
+            Some(symlink.get_id()) != file_id && self.collides(symlink.get_name(), &name)
+        });
+        if collides || collides_with_symlink {
+            Err(NfsError::FileAlreadyExistsWithSameName)
+        } else {
+            Ok(name)
+        }
+    }
+
+    /// Validates the name of a sub directory to be stored in the directory. `directory_id` is the
+    /// id of the sub directory when it is renamed, so that it does not collide with itself.
+    /// Returns the name in the form it should be stored in
+    pub fn validate_directory_name(&self,
+                                   name: String,
+                                   directory: &DirectoryListing,
+                                   directory_id: Option<&XorName>)
+                                   -> Result<String, NfsError> {
+        let name = try!(self.validate(name));
+        let collides = if self.is_exact() {
+            directory.find_sub_directory(&name)
+                     .map_or(false, |metadata| Some(metadata.get_id()) != directory_id)
+        } else {
+            directory.get_sub_directories().iter().any(|metadata| {
+                Some(metadata.get_id()) != directory_id && self.collides(metadata.get_name(), &name)
+            })
+        };
+        if collides {
+            Err(NfsError::DirectoryAlreadyExistsWithSameName)
+        } else {
+            Ok(name)
+        }
+    }
+
+    /// Returns true if the two names would collide under these rules
+    pub fn collides(&self, lhs: &str, rhs: &str) -> bool {
+        self.comparable_form(lhs) == self.comparable_form(rhs)
+    }
+
+    fn is_exact(&self) -> bool {
+        !self.normalise_unicode && !self.case_insensitive
+    }
+
+    fn comparable_form(&self, name: &str) -> String {
+        let name = if self.normalise_unicode {
+            name.nfc().collect::<String>()
+        } else {
+            name.to_string()
+        };
+        if self.case_insensitive {
+            name.to_lowercase()
+        } else {
+            name
+        }
+    }
+}
+
+impl Default for NameValidator {
+    fn default() -> NameValidator {
+        NameValidator::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use directory_listing::DirectoryListing;
+    use errors::NfsError;
+    use file::File;
+    use metadata::file_metadata::FileMetadata;
+    use self_encryption::DataMap;
+
+    fn is_not_valid(result: Result<String, NfsError>) -> bool {
+        match result {
+            Err(NfsError::ParameterIsNotValid(_)) => true,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn default_rules() {
+        let validator = NameValidator::new();
+        assert_eq!(unwrap_result!(validator.validate("hello.txt".to_string())),
+                   "hello.txt".to_string());
+        assert!(is_not_valid(validator.validate("".to_string())));
+        assert!(is_not_valid(validator.validate(".".to_string())));
+        assert!(is_not_valid(validator.validate("..".to_string())));
+        assert!(is_not_valid(validator.validate("a/b".to_string())));
+        assert!(is_not_valid(validator.validate("a\u{7}b".to_string())));
+        let longest_name: String = vec!['a'; DEFAULT_MAX_NAME_LENGTH].into_iter().collect();
+        assert!(validator.validate(longest_name.clone()).is_ok());
+        assert!(is_not_valid(validator.validate(longest_name + "a")));
+
+        let mut validator = NameValidator::new();
+        validator.set_max_length(3);
+        assert!(is_not_valid(validator.validate("abcd".to_string())));
+    }
+
+    #[test]
+    fn normalisation_and_collisions() {
+        let mut directory = unwrap_result!(DirectoryListing::new("Home".to_string(),
+                                                                 10,
+                                                                 Vec::new(),
+                                                                 false,
+                                                                 ::AccessLevel::Private,
+                                                                 None));
+        let file = unwrap_result!(File::new(FileMetadata::new("Caf\u{e9}.txt".to_string(),
+                                                              Vec::new()),
+                                            DataMap::None));
+        directory.upsert_file(file.clone());
+        // "e" followed by a combining acute accent
+        let decomposed = "cafe\u{301}.TXT".to_string();
+
+        let mut validator = NameValidator::new();
+        assert!(validator.validate_file_name(decomposed.clone(), &directory, None).is_ok());
+        match validator.validate_file_name("Caf\u{e9}.txt".to_string(), &directory, None) {
+            Err(NfsError::FileAlreadyExistsWithSameName) => (),
+            _ => panic!("Expected FileAlreadyExistsWithSameName"),
+        }
+
+        validator.set_normalise_unicode(true);
+        assert_eq!(unwrap_result!(validator.validate(decomposed.clone())),
+                   "caf\u{e9}.TXT".to_string());
+        assert!(validator.validate_file_name(decomposed.clone(), &directory, None).is_ok());
+
+        validator.set_case_insensitive(true);
+        match validator.validate_file_name(decomposed.clone(), &directory, None) {
+            Err(NfsError::FileAlreadyExistsWithSameName) => (),
+            _ => panic!("Expected FileAlreadyExistsWithSameName"),
+        }
+        // Renaming a file does not collide with its own name
+        assert!(validator.validate_file_name(decomposed, &directory, Some(file.get_id())).is_ok());
+    }
+}
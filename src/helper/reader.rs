@@ -15,32 +15,47 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
+use std::cmp;
+use std::io::{self, Read, Seek, SeekFrom};
 use std::sync::{Arc, Mutex};
 
 use errors::NfsError;
 use file::File;
+use helper::link;
 use safe_core::client::Client;
 use safe_core::SelfEncryptionStorage;
 use self_encryption::SelfEncryptor;
 
 /// Reader is used to read contents of a File. It can read in chunks if the file happens to be very
-/// large
+/// large.
+/// Reader also implements `std::io::Read` and `std::io::Seek`, which read from and move an internal
+/// cursor, so that it can be used wherever a stream is expected.
 #[allow(dead_code)]
 pub struct Reader<'a> {
     client: Arc<Mutex<Client>>,
     self_encryptor: SelfEncryptor<SelfEncryptionStorage>,
     file: &'a File,
+    position: u64,
 }
 
 impl<'a> Reader<'a> {
     /// Create a new instance of Reader
+    /// Linked files are read from the content shared by all their links
     pub fn new(client: Arc<Mutex<Client>>, file: &'a File) -> Reader {
         let se_storage = SelfEncryptionStorage::new(client.clone());
+        let datamap = match link::resolve(&client, file) {
+            Ok(resolved) => resolved.get_datamap().clone(),
+            Err(error) => {
+                debug!("Reading content held in the entry of linked file - {:?}", error);
+                file.get_datamap().clone()
+            }
+        };
 
         Reader {
             client: client.clone(),
-            self_encryptor: SelfEncryptor::new(se_storage, file.get_datamap().clone()),
+            self_encryptor: SelfEncryptor::new(se_storage, datamap),
             file: file,
+            position: 0,
         }
     }
 
@@ -62,3 +77,94 @@ impl<'a> Reader<'a> {
         }
     }
 }
+
+impl<'a> Read for Reader<'a> {
+    /// Reads from the current cursor position. Returns a short read at the end of the file and
+    /// `Ok(0)` once the cursor is at or past the end.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = self.size();
+        if self.position >= size {
+            return Ok(0);
+        }
+        let length = cmp::min(buf.len() as u64, size - self.position);
+        debug!("Streaming {len} bytes of data from file starting at offset of {pos} bytes ...",
+               len = length,
+               pos = self.position);
+        let data = self.self_encryptor.read(self.position, length);
+        for (dest, src) in buf.iter_mut().zip(data.iter()) {
+            *dest = *src;
+        }
+        self.position += data.len() as u64;
+        Ok(data.len())
+    }
+}
+
+impl<'a> Seek for Reader<'a> {
+    /// Moves the cursor. Seeking past the end of the file is allowed and subsequent reads return
+    /// `Ok(0)`, seeking before the start of the file is an error.
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_position = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::End(offset) => self.size() as i64 + offset,
+            SeekFrom::Current(offset) => self.position as i64 + offset,
+        };
+        if new_position < 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "Invalid seek to a negative position"));
+        }
+        self.position = new_position as u64;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, Read, Seek, SeekFrom};
+    use std::sync::{Arc, Mutex};
+    use file::File;
+    use helper::directory_helper::DirectoryHelper;
+    use helper::writer::{Mode, Writer};
+    use metadata::file_metadata::FileMetadata;
+    use safe_core::utility::test_utils;
+    use self_encryption::DataMap;
+    use super::Reader;
+
+    #[test]
+    fn read_and_seek_as_stream() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+        let (directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                            ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                            Vec::new(),
+                                                            false,
+                                                            ::AccessLevel::Private,
+                                                            None));
+        let file = unwrap_result!(File::new(FileMetadata::new("hello.txt".to_string(), Vec::new()),
+                                            DataMap::None));
+        let data: Vec<u8> = (0..100).collect();
+        let mut writer = Writer::new(client.clone(), Mode::Overwrite, directory, file);
+        writer.write(&data, 0);
+        let (directory, _) = unwrap_result!(writer.close());
+        let file = unwrap_option!(directory.find_file(&"hello.txt".to_string()),
+                                  "File not found");
+
+        let mut reader = Reader::new(client.clone(), file);
+        let mut output = Vec::new();
+        assert_eq!(unwrap_result!(io::copy(&mut reader, &mut output)), 100);
+        assert_eq!(output, data);
+        let mut buf = [0u8; 10];
+        assert_eq!(unwrap_result!(Read::read(&mut reader, &mut buf[..])), 0);
+
+        assert_eq!(unwrap_result!(reader.seek(SeekFrom::End(-4))), 96);
+        assert_eq!(unwrap_result!(Read::read(&mut reader, &mut buf[..])), 4);
+        assert_eq!(&buf[0..4], &data[96..100]);
+
+        assert_eq!(unwrap_result!(reader.seek(SeekFrom::Start(10))), 10);
+        assert_eq!(unwrap_result!(reader.seek(SeekFrom::Current(5))), 15);
+        assert_eq!(unwrap_result!(Read::read(&mut reader, &mut buf[..])), 10);
+        assert_eq!(&buf[..], &data[15..25]);
+
+        assert!(reader.seek(SeekFrom::Current(-100)).is_err());
+    }
+}
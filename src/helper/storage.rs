@@ -0,0 +1,185 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+
+use std::sync::{Arc, Mutex};
+
+use directory_listing::DirectoryListing;
+use errors::NfsError;
+use routing::{Data, DataRequest, ImmutableData, ImmutableDataType, StructuredData};
+use safe_core::client::Client;
+use safe_core::errors::CoreError;
+use safe_core::structured_data_operations::{unversioned, versioned};
+use xor_name::XorName;
+
+/// Creates an unversioned StructuredData holding the data, encrypted if the AccessLevel is Private
+pub fn create_unversioned_data(client: &Arc<Mutex<Client>>,
+                               id: XorName,
+                               type_tag: u64,
+                               version: u64,
+                               data: Vec<u8>,
+                               access_level: &::AccessLevel)
+                               -> Result<StructuredData, NfsError> {
+    let signing_key = try!(unwrap_result!(client.lock()).get_secret_signing_key()).clone();
+    let owner_key = try!(unwrap_result!(client.lock()).get_public_signing_key()).clone();
+    let private_key = try!(unwrap_result!(client.lock()).get_public_encryption_key()).clone();
+    let secret_key = try!(unwrap_result!(client.lock()).get_secret_encryption_key()).clone();
+    let nonce = DirectoryListing::generate_nonce(&id);
+
+    let encryption_keys = match *access_level {
+        ::AccessLevel::Private => Some((&private_key, &secret_key, &nonce)),
+        ::AccessLevel::Public => None,
+    };
+    Ok(try!(unversioned::create(client.clone(),
+                                type_tag,
+                                id,
+                                version,
+                                data,
+                                vec![owner_key],
+                                Vec::new(),
+                                &signing_key,
+                                encryption_keys)))
+}
+
+/// Returns the data held in an unversioned StructuredData, decrypted if the AccessLevel is
+/// Private
+pub fn get_unversioned_data(client: &Arc<Mutex<Client>>,
+                            id: &XorName,
+                            structured_data: &StructuredData,
+                            access_level: &::AccessLevel)
+                            -> Result<Vec<u8>, NfsError> {
+    let private_key = try!(unwrap_result!(client.lock()).get_public_encryption_key()).clone();
+    let secret_key = try!(unwrap_result!(client.lock()).get_secret_encryption_key()).clone();
+    let nonce = DirectoryListing::generate_nonce(id);
+
+    let encryption_keys = match *access_level {
+        ::AccessLevel::Private => Some((&private_key, &secret_key, &nonce)),
+        ::AccessLevel::Public => None,
+    };
+    Ok(try!(unversioned::get_data(client.clone(), structured_data, encryption_keys)))
+}
+
+/// Deletes a StructuredData currently at `version` by posting a DELETE for its successor
+pub fn delete_unversioned_data(client: &Arc<Mutex<Client>>,
+                               id: XorName,
+                               type_tag: u64,
+                               version: u64)
+                               -> Result<(), NfsError> {
+    let signing_key = try!(unwrap_result!(client.lock()).get_secret_signing_key()).clone();
+    let owner_key = try!(unwrap_result!(client.lock()).get_public_signing_key()).clone();
+    let structured_data_to_delete = try!(unversioned::create(client.clone(),
+                                                             type_tag,
+                                                             id,
+                                                             version + 1,
+                                                             Vec::new(),
+                                                             vec![owner_key],
+                                                             Vec::new(),
+                                                             &signing_key,
+                                                             None));
+    debug!("Posting DELETE request for structured data ...");
+    try!(unwrap_result!(client.lock()).delete(Data::Structured(structured_data_to_delete), None));
+    Ok(())
+}
+
+/// Get StructuredData from the Network
+pub fn get_structured_data(client: &Arc<Mutex<Client>>,
+                           id: &XorName,
+                           type_tag: u64)
+                           -> Result<StructuredData, NfsError> {
+    let request = DataRequest::Structured(id.clone(), type_tag);
+    debug!("Getting structured data from the network ...");
+    let response_getter = try!(unwrap_result!(client.lock()).get(request, None));
+    match try!(response_getter.get()) {
+        Data::Structured(structured_data) => Ok(structured_data),
+        _ => Err(NfsError::from(CoreError::ReceivedUnexpectedData)),
+    }
+}
+
+/// Get StructuredData from the Network, or None if the Network has no such data. Any other
+/// failure is returned as an error
+pub fn find_structured_data(client: &Arc<Mutex<Client>>,
+                            id: &XorName,
+                            type_tag: u64)
+                            -> Result<Option<StructuredData>, NfsError> {
+    match get_structured_data(client, id, type_tag) {
+        Ok(structured_data) => Ok(Some(structured_data)),
+        Err(NfsError::CoreError(CoreError::GetFailure { .. })) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// Saves the data as ImmutableData in the network and returns the name
+pub fn save_as_immutable_data(client: &Arc<Mutex<Client>>,
+                              data: Vec<u8>,
+                              data_type: ImmutableDataType)
+                              -> Result<XorName, NfsError> {
+    let immutable_data = ImmutableData::new(data_type, data);
+    let name = immutable_data.name();
+    debug!("Posting PUT request to save immutable data to the network ...");
+    try!(unwrap_result!(client.lock()).put(Data::Immutable(immutable_data), None));
+    Ok(name)
+}
+
+/// Get ImmutableData from the Network
+pub fn get_immutable_data(client: &Arc<Mutex<Client>>,
+                          id: XorName,
+                          data_type: ImmutableDataType)
+                          -> Result<ImmutableData, NfsError> {
+    let request = DataRequest::Immutable(id, data_type);
+    debug!("Getting immutable data from the network ...");
+    let response_getter = try!(unwrap_result!(client.lock()).get(request, None));
+    match try!(response_getter.get()) {
+        Data::Immutable(immutable_data) => Ok(immutable_data),
+        _ => Err(NfsError::from(CoreError::ReceivedUnexpectedData)),
+    }
+}
+
+/// Creates the successor of a versioned StructuredData, holding `versions` in place of the
+/// versions it currently holds
+pub fn replace_versions(client: &Arc<Mutex<Client>>,
+                        structured_data: &StructuredData,
+                        versions: &[XorName])
+                        -> Result<StructuredData, NfsError> {
+    let signing_key = try!(unwrap_result!(client.lock()).get_secret_signing_key()).clone();
+    let owner_key = try!(unwrap_result!(client.lock()).get_public_signing_key()).clone();
+    let (first_version, other_versions) = try!(versions.split_first().ok_or(
+        NfsError::ParameterIsNotValid("At least one version has to be kept".to_string())));
+    let mut rebuilt = try!(versioned::create(&*unwrap_result!(client.lock()),
+                                             first_version.clone(),
+                                             structured_data.get_type_tag(),
+                                             structured_data.get_identifier().clone(),
+                                             0,
+                                             vec![owner_key.clone()],
+                                             Vec::new(),
+                                             &signing_key));
+    for version in other_versions {
+        rebuilt = try!(versioned::append_version(&mut *unwrap_result!(client.lock()),
+                                                 rebuilt,
+                                                 version.clone(),
+                                                 &signing_key));
+    }
+    // Only the encoded versions are taken from the rebuilt StructuredData, as its own version
+    // counts the appends above rather than following on from `structured_data`
+    StructuredData::new(structured_data.get_type_tag(),
+                        structured_data.get_identifier().clone(),
+                        structured_data.get_version() + 1,
+                        rebuilt.get_data().clone(),
+                        vec![owner_key],
+                        Vec::new(),
+                        Some(&signing_key))
+        .map_err(|error| NfsError::Unexpected(format!("{:?}", error)))
+}
@@ -0,0 +1,146 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::sync::{Arc, Mutex};
+
+use errors::NfsError;
+use helper::storage;
+use maidsafe_utilities::serialisation::{serialise, deserialise};
+use metadata::directory_key::DirectoryKey;
+use metadata::version_record::VersionRecord;
+use routing::{Data, StructuredData};
+use safe_core::client::Client;
+use sodiumoxide::crypto::hash::sha512;
+use xor_name::XorName;
+
+// The version log of a directory is an unversioned StructuredData of type `VERSION_LOG_TAG`
+// holding the VersionRecords of the directory in the order the versions were appended. It is
+// encrypted like the directory and its name is derived from the id of the directory, so that
+// the two never share a nonce.
+
+/// Maximum number of records kept in the log of a directory. The oldest records are dropped
+/// beyond it, and their versions are dated as if they had never been recorded.
+const MAX_RECORDS: usize = 1000;
+
+/// Appends a record for `version` of the directory, creating the log on the first call. Only the
+/// latest `MAX_RECORDS` records are kept, so that the log stays within a single StructuredData.
+pub fn append(client: &Arc<Mutex<Client>>,
+              directory_key: &DirectoryKey,
+              version: XorName,
+              message: Option<String>)
+              -> Result<(), NfsError> {
+    let signer = try!(unwrap_result!(client.lock()).get_public_signing_key()).clone();
+    let record = VersionRecord::new(version, ::time::now_utc(), signer, message);
+    match try!(storage::find_structured_data(client,
+                                             &get_log_id(directory_key),
+                                             ::VERSION_LOG_TAG)) {
+        Some(structured_data) => {
+            let mut records = try!(get_records(client, directory_key, &structured_data));
+            records.push(record);
+            if records.len() > MAX_RECORDS {
+                let excess = records.len() - MAX_RECORDS;
+                let _ = records.drain(..excess);
+            }
+            post(client, directory_key, &records, Some(structured_data))
+        }
+        None => post(client, directory_key, &vec![record], None),
+    }
+}
+
+/// Returns the records of the directory in the order the versions were appended. Directories
+/// whose versions were all appended before logs were kept have an empty log.
+pub fn get(client: &Arc<Mutex<Client>>,
+           directory_key: &DirectoryKey)
+           -> Result<Vec<VersionRecord>, NfsError> {
+    match try!(storage::find_structured_data(client,
+                                             &get_log_id(directory_key),
+                                             ::VERSION_LOG_TAG)) {
+        Some(structured_data) => get_records(client, directory_key, &structured_data),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Replaces the records of the directory, if it has a version log
+pub fn replace(client: &Arc<Mutex<Client>>,
+               directory_key: &DirectoryKey,
+               records: &Vec<VersionRecord>)
+               -> Result<(), NfsError> {
+    match try!(storage::find_structured_data(client,
+                                             &get_log_id(directory_key),
+                                             ::VERSION_LOG_TAG)) {
+        Some(structured_data) => post(client, directory_key, records, Some(structured_data)),
+        None => Ok(()),
+    }
+}
+
+/// Deletes the version log of the directory, if it has one
+pub fn delete(client: &Arc<Mutex<Client>>, directory_key: &DirectoryKey) -> Result<(), NfsError> {
+    let log_id = get_log_id(directory_key);
+    if let Some(structured_data) = try!(storage::find_structured_data(client,
+                                                                      &log_id,
+                                                                      ::VERSION_LOG_TAG)) {
+        debug!("Deleting version log of directory ...");
+        try!(storage::delete_unversioned_data(client,
+                                              log_id,
+                                              ::VERSION_LOG_TAG,
+                                              structured_data.get_version()));
+    }
+    Ok(())
+}
+
+fn get_log_id(directory_key: &DirectoryKey) -> XorName {
+    let mut data = directory_key.get_id().0.to_vec();
+    data.extend_from_slice(b"version_log");
+    XorName(sha512::hash(&data).0)
+}
+
+fn get_records(client: &Arc<Mutex<Client>>,
+               directory_key: &DirectoryKey,
+               structured_data: &StructuredData)
+               -> Result<Vec<VersionRecord>, NfsError> {
+    let serialised_records = try!(storage::get_unversioned_data(client,
+                                                                &get_log_id(directory_key),
+                                                                structured_data,
+                                                                directory_key.get_access_level()));
+    Ok(try!(deserialise(&serialised_records)))
+}
+
+/// Saves the records as the successor of `structured_data`, or as a new log if there is none
+fn post(client: &Arc<Mutex<Client>>,
+        directory_key: &DirectoryKey,
+        records: &Vec<VersionRecord>,
+        structured_data: Option<StructuredData>)
+        -> Result<(), NfsError> {
+    let version = structured_data.as_ref().map_or(0, |structured_data| {
+        structured_data.get_version() + 1
+    });
+    let updated_structured_data =
+        try!(storage::create_unversioned_data(client,
+                                              get_log_id(directory_key),
+                                              ::VERSION_LOG_TAG,
+                                              version,
+                                              try!(serialise(records)),
+                                              directory_key.get_access_level()));
+    if structured_data.is_some() {
+        debug!("Posting updated version log of directory ...");
+        try!(unwrap_result!(client.lock()).post(Data::Structured(updated_structured_data), None));
+    } else {
+        debug!("Posting PUT request to save version log of directory ...");
+        try!(unwrap_result!(client.lock()).put(Data::Structured(updated_structured_data), None));
+    }
+    Ok(())
+}
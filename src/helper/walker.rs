@@ -0,0 +1,327 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use directory_listing::DirectoryListing;
+use errors::NfsError;
+use file::File;
+use helper::directory_helper::DirectoryHelper;
+use metadata::directory_key::DirectoryKey;
+use metadata::directory_metadata::DirectoryMetadata;
+use safe_core::client::Client;
+use symlink::Symlink;
+use xor_name::XorName;
+
+/// Order in which a Walker visits the directories of a tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkOrder {
+    /// Every subtree is visited completely before its next sibling
+    DepthFirst,
+    /// All directories at one depth are visited before any directory one level deeper
+    BreadthFirst,
+}
+
+/// Entry yielded by a Walker along with its slash separated path relative to the directory the
+/// walk started from, which itself has the path `/`
+#[derive(Debug)]
+pub enum WalkEntry {
+    /// A directory of the tree
+    Directory(String, DirectoryListing),
+    /// A file held in a directory of the tree
+    File(String, File),
+    /// A symlink held in a directory of the tree. Symlinks are not followed.
+    Symlink(String, Symlink),
+}
+
+/// Failure to visit a directory of the tree. The walk carries on with the remaining directories.
+#[derive(Debug)]
+pub struct WalkError {
+    path: String,
+    error: NfsError,
+}
+
+impl WalkError {
+    /// Returns the path of the directory which could not be visited
+    pub fn get_path(&self) -> &String {
+        &self.path
+    }
+
+    /// Returns the reason the directory could not be visited
+    pub fn get_error(&self) -> &NfsError {
+        &self.error
+    }
+
+    /// Converts into the reason the directory could not be visited
+    pub fn into_error(self) -> NfsError {
+        self.error
+    }
+}
+
+/// Iterator over a directory and its entire subtree, fetching one directory at a time.
+/// Each directory is yielded before the files it holds and before its sub directories.
+pub struct Walker {
+    directory_helper: DirectoryHelper,
+    order: WalkOrder,
+    max_depth: Option<usize>,
+    follow_filter: Option<Box<Fn(&str, &DirectoryMetadata) -> bool>>,
+    pending_directories: VecDeque<(String, DirectoryKey, usize)>,
+    ready_entries: VecDeque<Result<WalkEntry, WalkError>>,
+    visited: HashSet<XorName>,
+}
+
+impl Walker {
+    /// Create a new Walker starting from the directory represented by `directory_key`
+    pub fn new(client: Arc<Mutex<Client>>,
+               directory_key: DirectoryKey,
+               order: WalkOrder)
+               -> Walker {
+        let mut pending_directories = VecDeque::new();
+        pending_directories.push_back(("/".to_string(), directory_key, 0));
+        Walker {
+            directory_helper: DirectoryHelper::new(client),
+            order: order,
+            max_depth: None,
+            follow_filter: None,
+            pending_directories: pending_directories,
+            ready_entries: VecDeque::new(),
+            visited: HashSet::new(),
+        }
+    }
+
+    /// Limits how deep the walk descends. The starting directory is at depth 0, so a max_depth of
+    /// 0 only yields the starting directory and its files.
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    /// Only descends into the sub directories for which `follow_filter` returns true. It is
+    /// passed the path and the metadata of each sub directory.
+    pub fn set_follow_filter<F>(&mut self, follow_filter: F)
+        where F: Fn(&str, &DirectoryMetadata) -> bool + 'static
+    {
+        self.follow_filter = Some(Box::new(follow_filter));
+    }
+
+    fn visit(&mut self, path: String, directory_key: DirectoryKey, depth: usize) {
+        if !self.visited.insert(directory_key.get_id().clone()) {
+            self.ready_entries.push_back(Err(WalkError {
+                path: path,
+                error: NfsError::CircularDirectoryReference,
+            }));
+            return;
+        }
+        debug!("Walking directory {:?} ...", path);
+        let directory = match self.directory_helper.get(&directory_key) {
+            Ok(directory) => directory,
+            Err(error) => {
+                self.ready_entries.push_back(Err(WalkError {
+                    path: path,
+                    error: error,
+                }));
+                return;
+            }
+        };
+
+        for file in directory.get_files() {
+            self.ready_entries
+                .push_back(Ok(WalkEntry::File(Walker::join_path(&path, file.get_name()),
+                                              file.clone())));
+        }
+        for symlink in directory.get_symlinks() {
+            self.ready_entries
+                .push_back(Ok(WalkEntry::Symlink(Walker::join_path(&path, symlink.get_name()),
+                                                 symlink.clone())));
+        }
+        if self.max_depth.map_or(true, |max_depth| depth < max_depth) {
+            let mut sub_directories = Vec::new();
+            for metadata in directory.get_sub_directories() {
+                let sub_directory_path = Walker::join_path(&path, metadata.get_name());
+                let follow = match self.follow_filter {
+                    Some(ref follow_filter) => follow_filter(&sub_directory_path, metadata),
+                    None => true,
+                };
+                if follow {
+                    sub_directories.push((sub_directory_path,
+                                          metadata.get_key().clone(),
+                                          depth + 1));
+                }
+            }
+            match self.order {
+                // Pending directories are popped from the back, so push in reverse to visit the
+                // sub directories in listing order
+                WalkOrder::DepthFirst => {
+                    for sub_directory in sub_directories.into_iter().rev() {
+                        self.pending_directories.push_back(sub_directory);
+                    }
+                }
+                WalkOrder::BreadthFirst => self.pending_directories.extend(sub_directories),
+            }
+        }
+        self.ready_entries.push_front(Ok(WalkEntry::Directory(path, directory)));
+    }
+
+    fn join_path(path: &str, name: &str) -> String {
+        if path.ends_with('/') {
+            format!("{}{}", path, name)
+        } else {
+            format!("{}/{}", path, name)
+        }
+    }
+}
+
+impl Iterator for Walker {
+    type Item = Result<WalkEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Result<WalkEntry, WalkError>> {
+        loop {
+            if let Some(entry) = self.ready_entries.pop_front() {
+                return Some(entry);
+            }
+            let next_directory = match self.order {
+                WalkOrder::DepthFirst => self.pending_directories.pop_back(),
+                WalkOrder::BreadthFirst => self.pending_directories.pop_front(),
+            };
+            match next_directory {
+                Some((path, directory_key, depth)) => self.visit(path, directory_key, depth),
+                None => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use directory_listing::DirectoryListing;
+    use file::File;
+    use helper::directory_helper::DirectoryHelper;
+    use metadata::directory_metadata::DirectoryMetadata;
+    use metadata::file_metadata::FileMetadata;
+    use safe_core::utility::test_utils;
+    use self_encryption::DataMap;
+
+    fn create_directory(dir_helper: &DirectoryHelper,
+                        name: &str,
+                        parent: Option<&mut DirectoryListing>)
+                        -> DirectoryListing {
+        let (directory, _) = unwrap_result!(dir_helper.create(name.to_string(),
+                                                              ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                              Vec::new(),
+                                                              false,
+                                                              ::AccessLevel::Private,
+                                                              parent));
+        directory
+    }
+
+    fn add_file(dir_helper: &DirectoryHelper, directory: &mut DirectoryListing, name: &str) {
+        directory.upsert_file(unwrap_result!(File::new(FileMetadata::new(name.to_string(),
+                                                                         Vec::new()),
+                                                       DataMap::None)));
+        let _ = unwrap_result!(dir_helper.update(directory));
+    }
+
+    fn paths(walker: Walker) -> Vec<String> {
+        walker.map(|entry| {
+                  match unwrap_result!(entry) {
+                      WalkEntry::Directory(path, _) => path,
+                      WalkEntry::File(path, _) => path,
+                      WalkEntry::Symlink(path, _) => path,
+                  }
+              })
+              .collect()
+    }
+
+    #[test]
+    fn walk_directory_tree() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+
+        // Home
+        //   - Pictures
+        //       - 2016
+        //           - b.jpg
+        //       - a.jpg
+        //   - Music
+        let mut home = create_directory(&dir_helper, "Home", None);
+        let mut pictures = create_directory(&dir_helper, "Pictures", Some(&mut home));
+        let _ = create_directory(&dir_helper, "Music", Some(&mut home));
+        let mut year = create_directory(&dir_helper, "2016", Some(&mut pictures));
+        add_file(&dir_helper, &mut pictures, "a.jpg");
+        add_file(&dir_helper, &mut year, "b.jpg");
+
+        let walker = dir_helper.walk(home.get_key(), WalkOrder::DepthFirst);
+        assert_eq!(paths(walker),
+                   vec!["/",
+                        "/Pictures",
+                        "/Pictures/a.jpg",
+                        "/Pictures/2016",
+                        "/Pictures/2016/b.jpg",
+                        "/Music"]);
+
+        let walker = dir_helper.walk(home.get_key(), WalkOrder::BreadthFirst);
+        assert_eq!(paths(walker),
+                   vec!["/",
+                        "/Pictures",
+                        "/Pictures/a.jpg",
+                        "/Music",
+                        "/Pictures/2016",
+                        "/Pictures/2016/b.jpg"]);
+
+        let mut walker = dir_helper.walk(home.get_key(), WalkOrder::DepthFirst);
+        walker.set_max_depth(Some(1));
+        assert_eq!(paths(walker), vec!["/", "/Pictures", "/Pictures/a.jpg", "/Music"]);
+
+        let mut walker = dir_helper.walk(home.get_key(), WalkOrder::DepthFirst);
+        walker.set_follow_filter(|path, _| !path.starts_with("/Pictures"));
+        assert_eq!(paths(walker), vec!["/", "/Music"]);
+    }
+
+    #[test]
+    fn walk_reports_unreachable_directories() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+
+        let mut home = create_directory(&dir_helper, "Home", None);
+        let _ = create_directory(&dir_helper, "Reachable", Some(&mut home));
+        // Metadata of a directory that was never saved to the network
+        let missing = unwrap_result!(DirectoryMetadata::new("Missing".to_string(),
+                                                            ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                            false,
+                                                            ::AccessLevel::Private,
+                                                            Vec::new(),
+                                                            Some(home.get_key().clone())));
+        home.upsert_sub_directory(missing);
+        let _ = unwrap_result!(dir_helper.update(&home));
+
+        let entries: Vec<_> = dir_helper.walk(home.get_key(), WalkOrder::DepthFirst).collect();
+        assert_eq!(entries.len(), 3);
+        let errors: Vec<_> = entries.iter().filter_map(|entry| entry.as_ref().err()).collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(*errors[0].get_path(), "/Missing".to_string());
+        assert!(entries.iter().any(|entry| {
+            match *entry {
+                Ok(WalkEntry::Directory(ref path, _)) => *path == "/Reachable".to_string(),
+                _ => false,
+            }
+        }));
+    }
+}
@@ -15,30 +15,54 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
+use std::cmp;
+use std::io::{self, Seek, SeekFrom, Write};
 use std::sync::{Arc, Mutex};
 
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
 use errors::NfsError;
 use directory_listing::DirectoryListing;
 use file::File;
+use helper::mime_type;
 use safe_core::client::Client;
 use safe_core::SelfEncryptionStorage;
 use self_encryption::{DataMap, SelfEncryptor};
 
+/// Size of the pieces content is read back in when it has to be hashed on `close()`
+const HASH_READ_LENGTH: u64 = 1024 * 1024;
+
 /// Mode of the writter
 pub enum Mode {
     /// Will create new data
     Overwrite,
     /// Will modify the existing data
     Modify,
+    /// Will add to the existing data. Every write lands at the end of the content irrespective of
+    /// the position passed or the cursor
+    Append,
 }
 
 /// Writer is used to write contents to a File and especially in chunks if the file happens to be
-/// too large
+/// too large.
+/// Writer also implements `std::io::Write` and `std::io::Seek`, which write at and move an
+/// internal cursor, so that streaming code can write straight into the File before `close()`
+/// On `close()` the SHA-256 digest and the MIME type of the content are recorded in the
+/// FileMetadata. The digest is computed as the data streams through `write`; content which was
+/// not written sequentially from the start is read back and hashed instead.
+/// Closing a linked File updates the content shared by all its links.
+/// Every `close()` appends the saved File to its version history.
 pub struct Writer {
     client: Arc<Mutex<Client>>,
     file: File,
     parent_directory: DirectoryListing,
     self_encryptor: SelfEncryptor<SelfEncryptionStorage>,
+    position: u64,
+    append: bool,
+    hasher: Sha256,
+    hashed_length: u64,
+    hashing_in_order: bool,
+    mime_type: Option<String>,
 }
 
 impl Writer {
@@ -48,23 +72,82 @@ impl Writer {
                parent_directory: DirectoryListing,
                file: File)
                -> Writer {
-        let datamap = match mode {
-            Mode::Modify => file.get_datamap().clone(),
-            Mode::Overwrite => DataMap::None,
+        let (datamap, append) = match mode {
+            Mode::Modify => (file.get_datamap().clone(), false),
+            Mode::Append => (file.get_datamap().clone(), true),
+            Mode::Overwrite => (DataMap::None, false),
+        };
+        let self_encryptor = SelfEncryptor::new(SelfEncryptionStorage::new(client.clone()),
+                                                datamap);
+        let hashing_in_order = self_encryptor.len() == 0;
+        let position = if append {
+            self_encryptor.len()
+        } else {
+            0
         };
 
         Writer {
             client: client.clone(),
             file: file,
             parent_directory: parent_directory,
-            self_encryptor: SelfEncryptor::new(SelfEncryptionStorage::new(client.clone()), datamap),
+            self_encryptor: self_encryptor,
+            position: position,
+            append: append,
+            hasher: Sha256::new(),
+            hashed_length: 0,
+            hashing_in_order: hashing_in_order,
+            mime_type: None,
         }
     }
 
+    /// Records `mime_type` as the MIME type of the content on `close()` instead of detecting it
+    /// from the name and the content
+    pub fn set_mime_type(&mut self, mime_type: String) {
+        self.mime_type = Some(mime_type);
+    }
+
     /// Data of a file/blob can be written in smaller chunks
+    /// The cursor is moved to the end of the written data
+    /// In `Mode::Append` the position is ignored and the data is written at the end of the content
     pub fn write(&mut self, data: &[u8], position: u64) {
+        let position = if self.append {
+            self.self_encryptor.len()
+        } else {
+            position
+        };
         debug!("Writing file data at position {:?} ...", position);
+        if self.hashing_in_order && position == self.hashed_length {
+            self.hasher.input(data);
+            self.hashed_length += data.len() as u64;
+        } else {
+            self.hashing_in_order = false;
+        }
         self.self_encryptor.write(data, position);
+        self.position = position + data.len() as u64;
+    }
+
+    /// Returns the current size of the file/blob content, including data written so far
+    pub fn len(&self) -> u64 {
+        self.self_encryptor.len()
+    }
+
+    /// Returns true if the file/blob content is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Truncates or extends the file/blob content to `length` bytes.
+    /// The cursor is left untouched.
+    pub fn truncate(&mut self, length: u64) -> Result<(), NfsError> {
+        debug!("Truncating file data to {:?} bytes ...", length);
+        if length != self.hashed_length {
+            self.hashing_in_order = false;
+        }
+        if self.self_encryptor.truncate(length) {
+            Ok(())
+        } else {
+            Err(NfsError::from("Unable to truncate the content"))
+        }
     }
 
     /// close is invoked only after all the data is completely written
@@ -72,24 +155,211 @@ impl Writer {
     /// Returns the update DirectoryListing which owns the file and also the updated
     /// DirectoryListing of the file's parent
     /// Returns (files's parent_directory, Option<file's parent_directory's parent>)
-    pub fn close(self) -> Result<(DirectoryListing, Option<DirectoryListing>), NfsError> {
-        let mut file = self.file;
-        let mut directory = self.parent_directory;
+    pub fn close(mut self) -> Result<(DirectoryListing, Option<DirectoryListing>), NfsError> {
         let size = self.self_encryptor.len();
+        let content_hash = self.get_content_hash();
+        let mime_type = match self.mime_type.take() {
+            Some(mime_type) => mime_type,
+            None => {
+                let length = cmp::min(size, mime_type::MAGIC_BYTES_LENGTH as u64);
+                let content = self.self_encryptor.read(0, length);
+                mime_type::detect(self.file.get_name(), &content)
+            }
+        };
 
+        let mut file = self.file;
+        let mut directory = self.parent_directory;
         file.set_datamap(self.self_encryptor.close());
 
         file.get_mut_metadata().set_modified_time(::time::now_utc());
         file.get_mut_metadata().set_size(size);
+        file.get_mut_metadata().set_content_hash(Some(content_hash));
+        file.get_mut_metadata().set_mime_type(Some(mime_type));
+        if file.get_metadata().get_link_id().is_some() {
+            try!(::helper::link::update_content(&self.client, &file));
+        }
 
         directory.upsert_file(file.clone());
 
         let directory_helper = ::helper::directory_helper::DirectoryHelper::new(self.client
                                                                                     .clone());
-        if let Some(updated_grand_parent) = try!(directory_helper.update(&directory)) {
-            Ok((directory, Some(updated_grand_parent)))
-        } else {
-            Ok((directory, None))
+        let updated_grand_parent = try!(directory_helper.update(&directory));
+        try!(::helper::history::record(&self.client, &file));
+        Ok((directory, updated_grand_parent))
+    }
+
+    fn get_content_hash(&mut self) -> Vec<u8> {
+        let size = self.self_encryptor.len();
+        if !self.hashing_in_order || self.hashed_length != size {
+            debug!("Reading back file data to hash it ...");
+            self.hasher.reset();
+            let mut position = 0;
+            while position < size {
+                let length = cmp::min(HASH_READ_LENGTH, size - position);
+                self.hasher.input(&self.self_encryptor.read(position, length));
+                position += length;
+            }
         }
+        let mut content_hash = vec![0u8; self.hasher.output_bytes()];
+        self.hasher.result(&mut content_hash);
+        content_hash
+    }
+}
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let position = self.position;
+        Writer::write(self, buf, position);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for Writer {
+    /// Moves the cursor. Seeking past the end is allowed and a subsequent write extends the
+    /// content, seeking before the start is an error.
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_position = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::End(offset) => self.len() as i64 + offset,
+            SeekFrom::Current(offset) => self.position as i64 + offset,
+        };
+        if new_position < 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "Invalid seek to a negative position"));
+        }
+        self.position = new_position as u64;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, Seek, SeekFrom, Write};
+    use std::sync::{Arc, Mutex};
+    use crypto::digest::Digest;
+    use crypto::sha2::Sha256;
+    use file::File;
+    use helper::directory_helper::DirectoryHelper;
+    use helper::reader::Reader;
+    use metadata::file_metadata::FileMetadata;
+    use safe_core::utility::test_utils;
+    use self_encryption::DataMap;
+    use super::{Mode, Writer};
+
+    #[test]
+    fn write_and_seek_as_stream() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+        let (directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                            ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                            Vec::new(),
+                                                            false,
+                                                            ::AccessLevel::Private,
+                                                            None));
+        let file = unwrap_result!(File::new(FileMetadata::new("hello.txt".to_string(), Vec::new()),
+                                            DataMap::None));
+        let mut writer = Writer::new(client.clone(), Mode::Overwrite, directory, file);
+        assert!(writer.is_empty());
+        let data = vec![1u8; 100];
+        assert_eq!(unwrap_result!(io::copy(&mut &data[..], &mut writer)), 100);
+        unwrap_result!(Write::write_all(&mut writer, &[2u8; 10]));
+        assert_eq!(writer.len(), 110);
+
+        assert_eq!(unwrap_result!(writer.seek(SeekFrom::Start(5))), 5);
+        unwrap_result!(Write::write_all(&mut writer, &[3u8; 5]));
+        assert_eq!(unwrap_result!(writer.seek(SeekFrom::End(-20))), 90);
+        unwrap_result!(writer.truncate(95));
+        assert_eq!(writer.len(), 95);
+        assert!(writer.seek(SeekFrom::Current(-100)).is_err());
+        unwrap_result!(Write::flush(&mut writer));
+
+        let (directory, _) = unwrap_result!(writer.close());
+        let file = unwrap_option!(directory.find_file(&"hello.txt".to_string()),
+                                  "File not found");
+        assert_eq!(file.get_metadata().get_size(), 95);
+        let mut reader = Reader::new(client.clone(), file);
+        let content = unwrap_result!(reader.read(0, 95));
+        assert_eq!(&content[0..5], &[1u8; 5]);
+        assert_eq!(&content[5..10], &[3u8; 5]);
+        assert_eq!(&content[10..95], &[1u8; 85][..]);
+    }
+
+    #[test]
+    fn content_hash_and_mime_type() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+        let (directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                            ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                            Vec::new(),
+                                                            false,
+                                                            ::AccessLevel::Private,
+                                                            None));
+        let file = unwrap_result!(File::new(FileMetadata::new("page".to_string(), Vec::new()),
+                                            DataMap::None));
+        let data = b"%PDF-1.4 some document".to_vec();
+        let mut hasher = Sha256::new();
+        hasher.input(&data);
+        let mut expected_hash = vec![0u8; hasher.output_bytes()];
+        hasher.result(&mut expected_hash);
+
+        // Hashed as the data streams through
+        let mut writer = Writer::new(client.clone(), Mode::Overwrite, directory, file);
+        writer.write(&data[0..5], 0);
+        writer.write(&data[5..], 5);
+        let (directory, _) = unwrap_result!(writer.close());
+        let file = unwrap_option!(directory.find_file(&"page".to_string()), "File not found")
+                       .clone();
+        assert_eq!(file.get_metadata().get_content_hash(), Some(&expected_hash));
+        assert_eq!(file.get_metadata().get_mime_type(),
+                   Some(&"application/pdf".to_string()));
+
+        // Hashed from the content read back after writing out of order
+        let mut writer = Writer::new(client.clone(), Mode::Modify, directory, file);
+        writer.write(&data[5..], 5);
+        writer.set_mime_type("text/plain".to_string());
+        let (directory, _) = unwrap_result!(writer.close());
+        let file = unwrap_option!(directory.find_file(&"page".to_string()), "File not found");
+        assert_eq!(file.get_metadata().get_content_hash(), Some(&expected_hash));
+        assert_eq!(file.get_metadata().get_mime_type(), Some(&"text/plain".to_string()));
+    }
+
+    #[test]
+    fn append() {
+        let test_client = unwrap_result!(test_utils::get_client());
+        let client = Arc::new(Mutex::new(test_client));
+        let dir_helper = DirectoryHelper::new(client.clone());
+        let (directory, _) = unwrap_result!(dir_helper.create("DirName".to_string(),
+                                                            ::UNVERSIONED_DIRECTORY_LISTING_TAG,
+                                                            Vec::new(),
+                                                            false,
+                                                            ::AccessLevel::Private,
+                                                            None));
+        let file = unwrap_result!(File::new(FileMetadata::new("log.txt".to_string(), Vec::new()),
+                                            DataMap::None));
+        let mut writer = Writer::new(client.clone(), Mode::Overwrite, directory, file);
+        writer.write(&[1u8; 10], 0);
+        let (directory, _) = unwrap_result!(writer.close());
+        let file = unwrap_option!(directory.find_file(&"log.txt".to_string()), "File not found")
+                       .clone();
+
+        let mut writer = Writer::new(client.clone(), Mode::Append, directory, file);
+        // Position is ignored in append mode
+        writer.write(&[2u8; 5], 0);
+        let _ = unwrap_result!(writer.seek(SeekFrom::Start(0)));
+        unwrap_result!(Write::write_all(&mut writer, &[3u8; 5]));
+        let (directory, _) = unwrap_result!(writer.close());
+
+        let file = unwrap_option!(directory.find_file(&"log.txt".to_string()), "File not found");
+        let mut reader = Reader::new(client.clone(), file);
+        let content = unwrap_result!(reader.read(0, 20));
+        assert_eq!(&content[0..10], &[1u8; 10]);
+        assert_eq!(&content[10..15], &[2u8; 5]);
+        assert_eq!(&content[15..20], &[3u8; 5]);
     }
 }
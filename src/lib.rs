@@ -42,12 +42,14 @@
 #![cfg_attr(feature="clippy", deny(clippy, clippy_pedantic))]
 
 extern crate time;
+extern crate crypto;
 extern crate routing;
 extern crate xor_name;
 extern crate safe_core;
 extern crate sodiumoxide;
 extern crate rustc_serialize;
 extern crate self_encryption;
+extern crate unicode_normalization;
 #[macro_use]
 extern crate log;
 #[macro_use]
@@ -55,6 +57,8 @@ extern crate maidsafe_utilities;
 
 /// Module for File struct
 pub mod file;
+/// Module for Symlink struct
+pub mod symlink;
 /// Module for Restful interfaces for storage
 pub mod rest;
 /// Errors
@@ -74,6 +78,21 @@ pub const CONFIGURATION_DIRECTORY_NAME: &'static str = "CONFIGURATION_ROOT";
 pub const VERSIONED_DIRECTORY_LISTING_TAG: u64 = safe_core::CLIENT_STRUCTURED_DATA_TAG + 100;
 /// Tag representing the Versioned Directory Listing
 pub const UNVERSIONED_DIRECTORY_LISTING_TAG: u64 = VERSIONED_DIRECTORY_LISTING_TAG + 1;
+/// Tag representing the Unversioned Directory Listing whose entries are split across shards
+pub const SHARDED_DIRECTORY_LISTING_TAG: u64 = UNVERSIONED_DIRECTORY_LISTING_TAG + 1;
+/// Tag representing a shard holding part of the entries of a sharded Directory Listing
+pub const DIRECTORY_LISTING_SHARD_TAG: u64 = SHARDED_DIRECTORY_LISTING_TAG + 1;
+/// Tag representing the shared state of a hard linked file
+pub const FILE_LINK_TAG: u64 = DIRECTORY_LISTING_SHARD_TAG + 1;
+/// Tag representing the version history of a file
+pub const FILE_VERSIONS_TAG: u64 = FILE_LINK_TAG + 1;
+/// Tag representing the log of timestamps, signers and messages of a directory's versions
+pub const VERSION_LOG_TAG: u64 = FILE_VERSIONS_TAG + 1;
+/// Number of shards the entries of a newly created sharded Directory Listing are split across
+pub const DIRECTORY_LISTING_SHARD_COUNT: u32 = 16;
+/// Number of files and sub directories a shard of a sharded Directory Listing holds before the
+/// entries are split across twice as many shards
+pub const DIRECTORY_LISTING_SHARD_CAPACITY: usize = 1000;
 
 /// AccessLevel indicates whether the container is Private or Public shared
 #[derive(RustcEncodable, RustcDecodable, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
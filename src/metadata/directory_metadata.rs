@@ -19,8 +19,15 @@ use rustc_serialize::{Decodable, Decoder};
 
 use xor_name::XorName;
 use metadata::directory_key::DirectoryKey;
+use metadata::retention_policy::RetentionPolicy;
+use metadata::xattr::ExtendedAttributes;
 use safe_core::utility;
 
+/// Version of the encoding of DirectoryMetadata. The fields following the format version are only
+/// present from the version they were introduced in.
+/// Version 1 introduced the extended attributes and the retention policy.
+const FORMAT_VERSION: u32 = 1;
+
 /// Metadata about a File or a Directory
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub struct DirectoryMetadata {
@@ -29,7 +36,9 @@ pub struct DirectoryMetadata {
     created_time: ::time::Tm,
     modified_time: ::time::Tm,
     user_metadata: Vec<u8>,
+    xattrs: ExtendedAttributes,
     parent_dir_key: Option<DirectoryKey>,
+    retention_policy: Option<RetentionPolicy>,
 }
 
 impl DirectoryMetadata {
@@ -48,7 +57,9 @@ impl DirectoryMetadata {
             created_time: ::time::now_utc(),
             modified_time: ::time::now_utc(),
             user_metadata: user_metadata,
+            xattrs: ExtendedAttributes::new(),
             parent_dir_key: parent_dir_key,
+            retention_policy: None,
         })
     }
 
@@ -103,6 +114,16 @@ impl DirectoryMetadata {
         &self.user_metadata
     }
 
+    /// Get the extended attributes
+    pub fn get_xattrs(&self) -> &ExtendedAttributes {
+        &self.xattrs
+    }
+
+    /// Get the extended attributes for modification
+    pub fn get_mut_xattrs(&mut self) -> &mut ExtendedAttributes {
+        &mut self.xattrs
+    }
+
     /// Set name associated with the structure (file or directory) that this metadata is a part
     /// of
     pub fn set_name(&mut self, name: String) {
@@ -128,6 +149,16 @@ impl DirectoryMetadata {
     pub fn set_parent_dir_key(&mut self, parent_dir_key: Option<DirectoryKey>) {
         self.parent_dir_key = parent_dir_key;
     }
+
+    /// Returns the retention policy applied when the versions of the directory are pruned
+    pub fn get_retention_policy(&self) -> Option<&RetentionPolicy> {
+        self.retention_policy.as_ref()
+    }
+
+    /// Setter for the retention policy. Only versioned directories have versions to prune.
+    pub fn set_retention_policy(&mut self, retention_policy: Option<RetentionPolicy>) {
+        self.retention_policy = retention_policy;
+    }
 }
 
 impl ::rustc_serialize::Encodable for DirectoryMetadata {
@@ -135,7 +166,7 @@ impl ::rustc_serialize::Encodable for DirectoryMetadata {
         let created_time = self.created_time.to_timespec();
         let modified_time = self.modified_time.to_timespec();
 
-        e.emit_struct("DirectoryMetadata", 8, |e| {
+        e.emit_struct("DirectoryMetadata", 11, |e| {
             try!(e.emit_struct_field("key", 0, |e| self.key.encode(e)));
             try!(e.emit_struct_field("name", 1, |e| self.name.encode(e)));
             try!(e.emit_struct_field("created_time_sec", 2, |e| created_time.sec.encode(e)));
@@ -144,6 +175,11 @@ impl ::rustc_serialize::Encodable for DirectoryMetadata {
             try!(e.emit_struct_field("modified_time_nsec", 5, |e| modified_time.nsec.encode(e)));
             try!(e.emit_struct_field("user_metadata", 6, |e| self.user_metadata.encode(e)));
             try!(e.emit_struct_field("parent_dir_key", 7, |e| self.parent_dir_key.encode(e)));
+            try!(e.emit_struct_field("format_version", 8, |e| FORMAT_VERSION.encode(e)));
+            try!(e.emit_struct_field("xattrs", 9, |e| self.xattrs.encode(e)));
+            try!(e.emit_struct_field("retention_policy",
+                                     10,
+                                     |e| self.retention_policy.encode(e)));
 
             Ok(())
         })
@@ -152,8 +188,8 @@ impl ::rustc_serialize::Encodable for DirectoryMetadata {
 
 impl Decodable for DirectoryMetadata {
     fn decode<D: Decoder>(d: &mut D) -> Result<DirectoryMetadata, D::Error> {
-        d.read_struct("DirectoryMetadata", 8, |d| {
-            Ok(DirectoryMetadata {
+        d.read_struct("DirectoryMetadata", 11, |d| {
+            let mut metadata = DirectoryMetadata {
                 key: try!(d.read_struct_field("key", 0, |d| Decodable::decode(d))),
                 name: try!(d.read_struct_field("name", 1, |d| Decodable::decode(d))),
                 created_time: ::time::at_utc(::time::Timespec {
@@ -173,10 +209,24 @@ impl Decodable for DirectoryMetadata {
                 user_metadata: try!(d.read_struct_field("user_metadata",
                                                         6,
                                                         |d| Decodable::decode(d))),
+                xattrs: ExtendedAttributes::new(),
                 parent_dir_key: try!(d.read_struct_field("parent_dir_key",
                                                          7,
                                                          |d| Decodable::decode(d))),
-            })
+                retention_policy: None,
+            };
+            let format_version: u32 = try!(d.read_struct_field("format_version",
+                                                               8,
+                                                               |d| Decodable::decode(d)));
+            if format_version > FORMAT_VERSION {
+                return Err(d.error(&format!("Unsupported DirectoryMetadata format version {}",
+                                            format_version)));
+            }
+            metadata.xattrs = try!(d.read_struct_field("xattrs", 9, |d| Decodable::decode(d)));
+            metadata.retention_policy = try!(d.read_struct_field("retention_policy",
+                                                                 10,
+                                                                 |d| Decodable::decode(d)));
+            Ok(metadata)
         })
     }
 }
@@ -186,6 +236,8 @@ mod test {
     use super::*;
     use xor_name::XorName;
     use metadata::directory_key::DirectoryKey;
+    use metadata::retention_policy::RetentionPolicy;
+    use metadata::xattr::XattrValue;
     use maidsafe_utilities::serialisation::{serialise, deserialise};
     use safe_core::utility;
 
@@ -230,12 +282,42 @@ mod test {
                 Some(DirectoryKey::new(id, 100u64, false, ::AccessLevel::Private))));
         let user_metadata = "{mime: \"application/json\"}".to_string().into_bytes();
         obj_before.set_user_metadata(user_metadata.clone());
+        let _ = obj_before.get_mut_xattrs()
+                          .set("user.icon".to_string(), XattrValue::Binary(vec![1u8; 4]));
         obj_before.set_modified_time(modified_time.clone());
         obj_before.set_name("index.txt".to_string());
         let serialised_data = unwrap_result!(serialise(&obj_before));
         let obj_after: DirectoryMetadata = unwrap_result!(deserialise(&serialised_data));
         assert_eq!(user_metadata, *obj_after.get_user_metadata());
+        assert_eq!(obj_before.get_xattrs(), obj_after.get_xattrs());
         assert_eq!(modified_time, *obj_after.get_modified_time());
         assert_eq!("index.txt".to_string(), *obj_after.get_name());
     }
+
+    #[test]
+    fn serialise_retention_policy() {
+        let mut obj_before = unwrap_result!(DirectoryMetadata::new("hello".to_string(),
+                                                                   99u64,
+                                                                   true,
+                                                                   ::AccessLevel::Private,
+                                                                   Vec::new(),
+                                                                   None));
+        let serialised_data = unwrap_result!(serialise(&obj_before));
+        let obj_after: DirectoryMetadata = unwrap_result!(deserialise(&serialised_data));
+        assert!(obj_after.get_retention_policy().is_none());
+
+        obj_before.set_retention_policy(Some(RetentionPolicy::new()));
+        let serialised_data = unwrap_result!(serialise(&obj_before));
+        let obj_after: DirectoryMetadata = unwrap_result!(deserialise(&serialised_data));
+        assert_eq!(obj_after.get_retention_policy(), Some(&RetentionPolicy::new()));
+
+        let mut retention_policy = RetentionPolicy::new();
+        retention_policy.set_keep_newer_than(Some(::time::Duration::days(30)));
+        retention_policy.set_keep_weekly(Some(52));
+        obj_before.set_retention_policy(Some(retention_policy.clone()));
+        let serialised_data = unwrap_result!(serialise(&obj_before));
+        let obj_after: DirectoryMetadata = unwrap_result!(deserialise(&serialised_data));
+        assert_eq!(obj_after.get_retention_policy(), Some(&retention_policy));
+        assert_eq!(obj_before, obj_after);
+    }
 }
@@ -15,8 +15,21 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
+use metadata::xattr::ExtendedAttributes;
 use rustc_serialize::{Encodable, Encoder, Decodable, Decoder};
 use time::{self, Timespec, Tm};
+use xor_name::XorName;
+
+/// Version of the encoding of FileMetadata. The fields following the format version are only
+/// present from the version they were introduced in, so that fields can be added without
+/// misreading metadata saved by older clients.
+/// Version 1 introduced the extended attributes, the MIME type, the content hash, the POSIX
+/// mode, owner and access time, the symlink target and the link id.
+const FORMAT_VERSION: u32 = 1;
+
+/// Permission bits given to files which do not record their own when they are made executable
+pub const DEFAULT_FILE_MODE: u32 = 0o644;
+const EXECUTABLE_MODE_BITS: u32 = 0o111;
 
 /// FileMetadata about a File or a Directory
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
@@ -26,6 +39,14 @@ pub struct FileMetadata {
     created_time: Tm,
     modified_time: Tm,
     user_metadata: Vec<u8>,
+    xattrs: ExtendedAttributes,
+    mime_type: Option<String>,
+    content_hash: Option<Vec<u8>>,
+    mode: Option<u32>,
+    owner: Option<u32>,
+    accessed_time: Option<Tm>,
+    symlink_target: Option<String>,
+    link_id: Option<XorName>,
 }
 
 impl FileMetadata {
@@ -37,6 +58,14 @@ impl FileMetadata {
             created_time: time::now_utc(),
             modified_time: time::now_utc(),
             user_metadata: user_metadata,
+            xattrs: ExtendedAttributes::new(),
+            mime_type: None,
+            content_hash: None,
+            mode: None,
+            owner: None,
+            accessed_time: None,
+            symlink_target: None,
+            link_id: None,
         }
     }
 
@@ -66,6 +95,55 @@ impl FileMetadata {
         &self.user_metadata
     }
 
+    /// Get the extended attributes
+    pub fn get_xattrs(&self) -> &ExtendedAttributes {
+        &self.xattrs
+    }
+
+    /// Get the extended attributes for modification
+    pub fn get_mut_xattrs(&mut self) -> &mut ExtendedAttributes {
+        &mut self.xattrs
+    }
+
+    /// Get the MIME type of the content
+    pub fn get_mime_type(&self) -> Option<&String> {
+        self.mime_type.as_ref()
+    }
+
+    /// Get the SHA-256 digest of the content as recorded when it was last written
+    pub fn get_content_hash(&self) -> Option<&Vec<u8>> {
+        self.content_hash.as_ref()
+    }
+
+    /// Get the POSIX permission bits, if recorded
+    pub fn get_mode(&self) -> Option<u32> {
+        self.mode
+    }
+
+    /// Get the POSIX user id of the owner, if recorded
+    pub fn get_owner(&self) -> Option<u32> {
+        self.owner
+    }
+
+    /// Returns true if any of the executable permission bits is set
+    pub fn is_executable(&self) -> bool {
+        self.mode.map_or(false, |mode| mode & EXECUTABLE_MODE_BITS != 0)
+    }
+
+    /// Get time of last access. Defaults to the time of modification if no access was recorded.
+    pub fn get_accessed_time(&self) -> &Tm {
+        self.accessed_time.as_ref().unwrap_or(&self.modified_time)
+    }
+
+    /// Get the target path if this is the metadata of a symbolic link
+    pub fn get_symlink_target(&self) -> Option<&String> {
+        self.symlink_target.as_ref()
+    }
+
+    /// Get the id of the shared file object if this is the metadata of a hard linked file
+    pub fn get_link_id(&self) -> Option<&XorName> {
+        self.link_id.as_ref()
+    }
 
     /// Set name associated with the structure (file or directory)
     pub fn set_name(&mut self, name: String) {
@@ -86,14 +164,65 @@ impl FileMetadata {
     pub fn set_user_metadata(&mut self, user_metadata: Vec<u8>) {
         self.user_metadata = user_metadata;
     }
+
+    /// Set the MIME type of the content
+    pub fn set_mime_type(&mut self, mime_type: Option<String>) {
+        self.mime_type = mime_type;
+    }
+
+    /// Set the SHA-256 digest of the content
+    pub fn set_content_hash(&mut self, content_hash: Option<Vec<u8>>) {
+        self.content_hash = content_hash;
+    }
+
+    /// Set the POSIX permission bits
+    pub fn set_mode(&mut self, mode: Option<u32>) {
+        self.mode = mode;
+    }
+
+    /// Set the POSIX user id of the owner
+    pub fn set_owner(&mut self, owner: Option<u32>) {
+        self.owner = owner;
+    }
+
+    /// Sets or clears all executable permission bits, starting from `DEFAULT_FILE_MODE` if no
+    /// permission bits were recorded
+    pub fn set_executable(&mut self, executable: bool) {
+        let mode = self.mode.unwrap_or(DEFAULT_FILE_MODE);
+        self.mode = Some(if executable {
+            mode | EXECUTABLE_MODE_BITS
+        } else {
+            mode & !EXECUTABLE_MODE_BITS
+        });
+    }
+
+    /// Set time of last access
+    pub fn set_accessed_time(&mut self, accessed_time: Tm) {
+        self.accessed_time = Some(accessed_time);
+    }
+
+    /// Set the target path of a symbolic link. Symbolic links are stored in the DirectoryListing
+    /// as files carrying their target.
+    pub fn set_symlink_target(&mut self, symlink_target: Option<String>) {
+        self.symlink_target = symlink_target;
+    }
+
+    /// Set the id of the shared file object all hard links to the file refer to
+    pub fn set_link_id(&mut self, link_id: Option<XorName>) {
+        self.link_id = link_id;
+    }
 }
 
 impl Encodable for FileMetadata {
     fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
         let created_time = self.created_time.to_timespec();
         let modified_time = self.modified_time.to_timespec();
+        let accessed_time = self.accessed_time.as_ref().map(|accessed_time| {
+            let accessed_time = accessed_time.to_timespec();
+            (accessed_time.sec, accessed_time.nsec)
+        });
 
-        e.emit_struct("FileMetadata", 7, |e| {
+        e.emit_struct("FileMetadata", 16, |e| {
             try!(e.emit_struct_field("name", 0, |e| self.name.encode(e)));
             try!(e.emit_struct_field("size", 1, |e| self.size.encode(e)));
             try!(e.emit_struct_field("created_time_sec", 2, |e| created_time.sec.encode(e)));
@@ -101,6 +230,15 @@ impl Encodable for FileMetadata {
             try!(e.emit_struct_field("modified_time_sec", 4, |e| modified_time.sec.encode(e)));
             try!(e.emit_struct_field("modified_time_nsec", 5, |e| modified_time.nsec.encode(e)));
             try!(e.emit_struct_field("user_metadata", 6, |e| self.user_metadata.encode(e)));
+            try!(e.emit_struct_field("format_version", 7, |e| FORMAT_VERSION.encode(e)));
+            try!(e.emit_struct_field("xattrs", 8, |e| self.xattrs.encode(e)));
+            try!(e.emit_struct_field("mime_type", 9, |e| self.mime_type.encode(e)));
+            try!(e.emit_struct_field("content_hash", 10, |e| self.content_hash.encode(e)));
+            try!(e.emit_struct_field("mode", 11, |e| self.mode.encode(e)));
+            try!(e.emit_struct_field("owner", 12, |e| self.owner.encode(e)));
+            try!(e.emit_struct_field("accessed_time", 13, |e| accessed_time.encode(e)));
+            try!(e.emit_struct_field("symlink_target", 14, |e| self.symlink_target.encode(e)));
+            try!(e.emit_struct_field("link_id", 15, |e| self.link_id.encode(e)));
 
             Ok(())
         })
@@ -109,8 +247,8 @@ impl Encodable for FileMetadata {
 
 impl Decodable for FileMetadata {
     fn decode<D: Decoder>(d: &mut D) -> Result<FileMetadata, D::Error> {
-        d.read_struct("FileMetadata", 7, |d| {
-            Ok(FileMetadata {
+        d.read_struct("FileMetadata", 16, |d| {
+            let mut metadata = FileMetadata {
                 name: try!(d.read_struct_field("name", 0, |d| Decodable::decode(d))),
                 size: try!(d.read_struct_field("size", 1, |d| Decodable::decode(d))),
                 created_time: ::time::at_utc(Timespec {
@@ -130,7 +268,46 @@ impl Decodable for FileMetadata {
                 user_metadata: try!(d.read_struct_field("user_metadata",
                                                         6,
                                                         |d| Decodable::decode(d))),
-            })
+                xattrs: ExtendedAttributes::new(),
+                mime_type: None,
+                content_hash: None,
+                mode: None,
+                owner: None,
+                accessed_time: None,
+                symlink_target: None,
+                link_id: None,
+            };
+            let format_version: u32 = try!(d.read_struct_field("format_version",
+                                                               7,
+                                                               |d| Decodable::decode(d)));
+            if format_version > FORMAT_VERSION {
+                return Err(d.error(&format!("Unsupported FileMetadata format version {}",
+                                            format_version)));
+            }
+            metadata.xattrs = try!(d.read_struct_field("xattrs", 8, |d| Decodable::decode(d)));
+            metadata.mime_type = try!(d.read_struct_field("mime_type",
+                                                          9,
+                                                          |d| Decodable::decode(d)));
+            metadata.content_hash = try!(d.read_struct_field("content_hash",
+                                                             10,
+                                                             |d| Decodable::decode(d)));
+            metadata.mode = try!(d.read_struct_field("mode", 11, |d| Decodable::decode(d)));
+            metadata.owner = try!(d.read_struct_field("owner", 12, |d| Decodable::decode(d)));
+            let accessed_time: Option<(i64, i32)> =
+                try!(d.read_struct_field("accessed_time", 13, |d| Decodable::decode(d)));
+            metadata.accessed_time = accessed_time.map(|(sec, nsec)| {
+                ::time::at_utc(Timespec {
+                    sec: sec,
+                    nsec: nsec,
+                })
+            });
+            metadata.symlink_target = try!(d.read_struct_field("symlink_target",
+                                                               14,
+                                                               |d| Decodable::decode(d)));
+            metadata.link_id = try!(d.read_struct_field("link_id",
+                                                        15,
+                                                        |d| Decodable::decode(d)));
+            Ok(metadata)
         })
     }
 }
@@ -138,6 +315,7 @@ impl Decodable for FileMetadata {
 #[cfg(test)]
 mod test {
     use super::*;
+    use metadata::xattr::XattrValue;
     use maidsafe_utilities::serialisation::{serialise, deserialise};
 
     #[test]
@@ -148,4 +326,59 @@ mod test {
         let obj_after = unwrap_result!(deserialise(&serialised_data));
         assert_eq!(obj_before, obj_after);
     }
+
+    #[test]
+    fn serialise_and_deserialise_xattrs() {
+        let mut obj_before = FileMetadata::new("hello.txt".to_string(),
+                                               "legacy".to_string().into_bytes());
+        let _ = obj_before.get_mut_xattrs()
+                          .set("user.mime_type".to_string(),
+                               XattrValue::Text("application/json".to_string()));
+        let serialised_data = unwrap_result!(serialise(&obj_before));
+        let obj_after: FileMetadata = unwrap_result!(deserialise(&serialised_data));
+        assert_eq!(obj_before, obj_after);
+        assert_eq!(*obj_after.get_user_metadata(), "legacy".to_string().into_bytes());
+        assert_eq!(obj_after.get_xattrs().len(), 1);
+    }
+
+    #[test]
+    fn serialise_and_deserialise_mime_type_and_content_hash() {
+        let mut obj_before = FileMetadata::new("hello.txt".to_string(), Vec::new());
+        obj_before.set_mime_type(Some("text/plain".to_string()));
+        obj_before.set_content_hash(Some(vec![7u8; 32]));
+        let serialised_data = unwrap_result!(serialise(&obj_before));
+        let obj_after: FileMetadata = unwrap_result!(deserialise(&serialised_data));
+        assert_eq!(obj_before, obj_after);
+        assert_eq!(obj_after.get_mime_type(), Some(&"text/plain".to_string()));
+        assert_eq!(obj_after.get_content_hash(), Some(&vec![7u8; 32]));
+        // Neither is mistaken for user metadata or an extended attribute
+        assert!(obj_after.get_user_metadata().is_empty());
+        assert!(obj_after.get_xattrs().is_empty());
+    }
+
+    #[test]
+    fn posix_attributes() {
+        let mut obj_before = FileMetadata::new("run.sh".to_string(), Vec::new());
+        assert_eq!(obj_before.get_mode(), None);
+        assert!(!obj_before.is_executable());
+        assert_eq!(obj_before.get_accessed_time(), obj_before.get_modified_time());
+
+        obj_before.set_executable(true);
+        assert_eq!(obj_before.get_mode(), Some(0o755));
+        assert!(obj_before.is_executable());
+        obj_before.set_mode(Some(0o600));
+        assert!(!obj_before.is_executable());
+        assert_eq!(obj_before.get_owner(), None);
+        obj_before.set_owner(Some(1000));
+        let accessed_time = ::time::at_utc(::time::Timespec::new(1_000_000, 500));
+        obj_before.set_accessed_time(accessed_time.clone());
+
+        let serialised_data = unwrap_result!(serialise(&obj_before));
+        let obj_after: FileMetadata = unwrap_result!(deserialise(&serialised_data));
+        assert_eq!(obj_before, obj_after);
+        assert_eq!(obj_after.get_mode(), Some(0o600));
+        assert_eq!(obj_after.get_owner(), Some(1000));
+        assert_eq!(*obj_after.get_accessed_time(), accessed_time);
+        assert!(obj_after.get_symlink_target().is_none());
+    }
 }
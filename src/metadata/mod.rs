@@ -21,3 +21,9 @@ pub mod file_metadata;
 pub mod directory_key;
 /// DirectoryMetadata
 pub mod directory_metadata;
+/// Extended attributes stored with the user metadata
+pub mod xattr;
+/// Timestamp, signer and message recorded for each version of a directory
+pub mod version_record;
+/// Rules deciding which versions of a versioned directory are kept when it is pruned
+pub mod retention_policy;
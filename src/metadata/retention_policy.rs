@@ -0,0 +1,187 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::collections::{BTreeSet, HashSet};
+
+use time::{Duration, Tm};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+const SECONDS_PER_WEEK: i64 = 7 * SECONDS_PER_DAY;
+// 1970-01-01 was a Thursday. Shifting by three days makes the weeks counted from the epoch begin
+// on Mondays.
+const WEEK_START_OFFSET: i64 = 3 * SECONDS_PER_DAY;
+
+/// Rules deciding which versions of a versioned directory are kept when it is pruned with
+/// `DirectoryHelper::prune_versions`. A version is kept if any of the rules keeps it and the
+/// latest version is always kept. A policy without any rule keeps every version.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, RustcEncodable,
+         RustcDecodable)]
+pub struct RetentionPolicy {
+    keep_last: Option<u64>,
+    keep_newer_than: Option<i64>,
+    keep_daily: Option<u64>,
+    keep_weekly: Option<u64>,
+}
+
+impl RetentionPolicy {
+    /// Create a new instance of RetentionPolicy without any rule
+    pub fn new() -> RetentionPolicy {
+        RetentionPolicy::default()
+    }
+
+    /// Number of most recent versions kept
+    pub fn get_keep_last(&self) -> Option<u64> {
+        self.keep_last
+    }
+
+    /// Age below which versions are kept
+    pub fn get_keep_newer_than(&self) -> Option<Duration> {
+        self.keep_newer_than.map(Duration::seconds)
+    }
+
+    /// Number of most recent days for which the last version of the day is kept
+    pub fn get_keep_daily(&self) -> Option<u64> {
+        self.keep_daily
+    }
+
+    /// Number of most recent weeks for which the last version of the week is kept. Weeks begin
+    /// on Monday, UTC.
+    pub fn get_keep_weekly(&self) -> Option<u64> {
+        self.keep_weekly
+    }
+
+    /// Keep the `count` most recent versions
+    pub fn set_keep_last(&mut self, count: Option<u64>) {
+        self.keep_last = count;
+    }
+
+    /// Keep the versions made less than `age` ago. The age is kept to the second.
+    pub fn set_keep_newer_than(&mut self, age: Option<Duration>) {
+        self.keep_newer_than = age.map(|age| age.num_seconds());
+    }
+
+    /// Keep the last version of each of the `count` most recent days which have versions
+    pub fn set_keep_daily(&mut self, count: Option<u64>) {
+        self.keep_daily = count;
+    }
+
+    /// Keep the last version of each of the `count` most recent weeks which have versions
+    pub fn set_keep_weekly(&mut self, count: Option<u64>) {
+        self.keep_weekly = count;
+    }
+
+    /// Returns true if the policy has no rule, i.e. keeps every version
+    pub fn is_empty(&self) -> bool {
+        self.keep_last.is_none() && self.keep_newer_than.is_none() && self.keep_daily.is_none() &&
+        self.keep_weekly.is_none()
+    }
+
+    /// Returns the positions of the versions kept by the policy. `timestamps` holds the time each
+    /// version was made at, in the order the versions were appended, and `now` is the time the
+    /// policy is applied at.
+    pub fn get_kept_versions(&self, timestamps: &[Tm], now: &Tm) -> BTreeSet<usize> {
+        if self.is_empty() {
+            return (0..timestamps.len()).collect();
+        }
+        let mut kept = BTreeSet::new();
+        if timestamps.is_empty() {
+            return kept;
+        }
+        let _ = kept.insert(timestamps.len() - 1);
+        if let Some(count) = self.keep_last {
+            let first = timestamps.len().saturating_sub(count as usize);
+            kept.extend(first..timestamps.len());
+        }
+        if let Some(age) = self.keep_newer_than {
+            let oldest = now.to_timespec().sec - age;
+            kept.extend((0..timestamps.len())
+                            .filter(|&position| timestamps[position].to_timespec().sec >= oldest));
+        }
+        if let Some(count) = self.keep_daily {
+            kept.extend(RetentionPolicy::get_snapshots(timestamps, SECONDS_PER_DAY, 0, count));
+        }
+        if let Some(count) = self.keep_weekly {
+            kept.extend(RetentionPolicy::get_snapshots(timestamps,
+                                                       SECONDS_PER_WEEK,
+                                                       WEEK_START_OFFSET,
+                                                       count));
+        }
+        kept
+    }
+
+    /// Positions of the last version of each of the `count` most recent periods which have
+    /// versions
+    fn get_snapshots(timestamps: &[Tm], period: i64, offset: i64, count: u64) -> Vec<usize> {
+        let mut periods = HashSet::new();
+        let mut snapshots = Vec::new();
+        for (position, timestamp) in timestamps.iter().enumerate().rev() {
+            if periods.len() as u64 == count {
+                break;
+            }
+            if periods.insert((timestamp.to_timespec().sec + offset) / period) {
+                snapshots.push(position);
+            }
+        }
+        snapshots
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use time::{self, Duration, Timespec, Tm};
+
+    fn kept(policy: &RetentionPolicy, timestamps: &[Tm], now: &Tm) -> Vec<usize> {
+        policy.get_kept_versions(timestamps, now).into_iter().collect()
+    }
+
+    #[test]
+    fn kept_versions() {
+        // Monday 2016-03-07 00:00:00 UTC
+        let monday = time::at_utc(Timespec::new(1457308800, 0));
+        // Two versions on each day of two weeks
+        let timestamps = (0..28)
+                             .map(|half_day| monday + Duration::hours(12 * half_day))
+                             .collect::<Vec<_>>();
+        let now = timestamps[27] + Duration::hours(1);
+
+        let mut policy = RetentionPolicy::new();
+        assert!(policy.is_empty());
+        assert_eq!(kept(&policy, &timestamps, &now).len(), 28);
+        assert!(policy.get_kept_versions(&[], &now).is_empty());
+
+        policy.set_keep_last(Some(3));
+        assert_eq!(kept(&policy, &timestamps, &now), vec![25, 26, 27]);
+        policy.set_keep_last(Some(0));
+        assert_eq!(kept(&policy, &timestamps, &now), vec![27]);
+        policy.set_keep_last(None);
+
+        policy.set_keep_newer_than(Some(Duration::hours(25)));
+        assert_eq!(kept(&policy, &timestamps, &now), vec![25, 26, 27]);
+        policy.set_keep_newer_than(None);
+
+        policy.set_keep_daily(Some(3));
+        assert_eq!(kept(&policy, &timestamps, &now), vec![23, 25, 27]);
+        policy.set_keep_daily(None);
+
+        policy.set_keep_weekly(Some(5));
+        assert_eq!(kept(&policy, &timestamps, &now), vec![13, 27]);
+
+        policy.set_keep_last(Some(2));
+        assert_eq!(kept(&policy, &timestamps, &now), vec![13, 26, 27]);
+    }
+}
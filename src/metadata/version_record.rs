@@ -0,0 +1,121 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+
+use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+use sodiumoxide::crypto::sign;
+use time::{Timespec, Tm};
+use xor_name::XorName;
+
+/// Describes a version appended to a versioned directory: when and by whom it was made, along
+/// with an optional message from the writer
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionRecord {
+    version: XorName,
+    timestamp: Tm,
+    signer: sign::PublicKey,
+    message: Option<String>,
+}
+
+impl VersionRecord {
+    /// Create a new instance of VersionRecord
+    pub fn new(version: XorName,
+               timestamp: Tm,
+               signer: sign::PublicKey,
+               message: Option<String>)
+               -> VersionRecord {
+        VersionRecord {
+            version: version,
+            timestamp: timestamp,
+            signer: signer,
+            message: message,
+        }
+    }
+
+    /// Name of the version, as returned by `DirectoryHelper::get_versions`
+    pub fn get_version(&self) -> &XorName {
+        &self.version
+    }
+
+    /// Time the version was appended at
+    pub fn get_timestamp(&self) -> &Tm {
+        &self.timestamp
+    }
+
+    /// Public signing key of the client which appended the version
+    pub fn get_signer(&self) -> &sign::PublicKey {
+        &self.signer
+    }
+
+    /// Message supplied by the writer of the version
+    pub fn get_message(&self) -> Option<&String> {
+        self.message.as_ref()
+    }
+}
+
+impl Encodable for VersionRecord {
+    fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
+        let timestamp = self.timestamp.to_timespec();
+        e.emit_struct("VersionRecord", 5, |e| {
+            try!(e.emit_struct_field("version", 0, |e| self.version.encode(e)));
+            try!(e.emit_struct_field("timestamp_sec", 1, |e| timestamp.sec.encode(e)));
+            try!(e.emit_struct_field("timestamp_nsec", 2, |e| timestamp.nsec.encode(e)));
+            try!(e.emit_struct_field("signer", 3, |e| self.signer.encode(e)));
+            try!(e.emit_struct_field("message", 4, |e| self.message.encode(e)));
+            Ok(())
+        })
+    }
+}
+
+impl Decodable for VersionRecord {
+    fn decode<D: Decoder>(d: &mut D) -> Result<VersionRecord, D::Error> {
+        d.read_struct("VersionRecord", 5, |d| {
+            Ok(VersionRecord {
+                version: try!(d.read_struct_field("version", 0, |d| Decodable::decode(d))),
+                timestamp: ::time::at_utc(Timespec {
+                    sec: try!(d.read_struct_field("timestamp_sec", 1, |d| Decodable::decode(d))),
+                    nsec: try!(d.read_struct_field("timestamp_nsec", 2, |d| Decodable::decode(d))),
+                }),
+                signer: try!(d.read_struct_field("signer", 3, |d| Decodable::decode(d))),
+                message: try!(d.read_struct_field("message", 4, |d| Decodable::decode(d))),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use maidsafe_utilities::serialisation::{serialise, deserialise};
+    use sodiumoxide::crypto::sign;
+    use xor_name::XorName;
+
+    #[test]
+    fn serialise_and_deserialise_version_record() {
+        let (signer, _) = sign::gen_keypair();
+        let timestamp = ::time::at_utc(::time::Timespec::new(1_000_000, 500));
+        let obj_before = VersionRecord::new(XorName([1u8; 64]),
+                                            timestamp.clone(),
+                                            signer,
+                                            Some("Initial import".to_string()));
+        let serialised_data = unwrap_result!(serialise(&obj_before));
+        let obj_after: VersionRecord = unwrap_result!(deserialise(&serialised_data));
+        assert_eq!(obj_before, obj_after);
+        assert_eq!(*obj_after.get_timestamp(), timestamp);
+        assert_eq!(obj_after.get_message(), Some(&"Initial import".to_string()));
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::collections::BTreeMap;
+
+/// Value of an extended attribute
+#[derive(RustcEncodable, RustcDecodable, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum XattrValue {
+    /// UTF-8 text
+    Text(String),
+    /// Arbitrary bytes
+    Binary(Vec<u8>),
+    /// Signed integer
+    Integer(i64),
+}
+
+impl XattrValue {
+    /// Returns the text if the value is Text
+    pub fn as_text(&self) -> Option<&String> {
+        match *self {
+            XattrValue::Text(ref text) => Some(text),
+            XattrValue::Binary(_) |
+            XattrValue::Integer(_) => None,
+        }
+    }
+
+    /// Returns the value as bytes if the value is Text or Binary. Text is returned UTF-8 encoded.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match *self {
+            XattrValue::Text(ref text) => Some(text.as_bytes()),
+            XattrValue::Binary(ref data) => Some(&data[..]),
+            XattrValue::Integer(_) => None,
+        }
+    }
+
+    /// Returns the integer if the value is Integer
+    pub fn as_integer(&self) -> Option<i64> {
+        match *self {
+            XattrValue::Integer(value) => Some(value),
+            XattrValue::Text(_) |
+            XattrValue::Binary(_) => None,
+        }
+    }
+}
+
+/// Typed key/value extended attributes of a file or directory, kept alongside the opaque user
+/// metadata
+#[derive(RustcEncodable, RustcDecodable, Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExtendedAttributes {
+    attributes: BTreeMap<String, XattrValue>,
+}
+
+impl ExtendedAttributes {
+    /// Create an empty set of ExtendedAttributes
+    pub fn new() -> ExtendedAttributes {
+        ExtendedAttributes { attributes: BTreeMap::new() }
+    }
+
+    /// Returns the value of the attribute
+    pub fn get(&self, key: &str) -> Option<&XattrValue> {
+        self.attributes.get(key)
+    }
+
+    /// Sets the value of the attribute.
+    /// Returns the previous value if the attribute was already set
+    pub fn set(&mut self, key: String, value: XattrValue) -> Option<XattrValue> {
+        self.attributes.insert(key, value)
+    }
+
+    /// Removes the attribute.
+    /// Returns the value if the attribute was set
+    pub fn remove(&mut self, key: &str) -> Option<XattrValue> {
+        self.attributes.remove(key)
+    }
+
+    /// Returns the keys of all attributes in sorted order
+    pub fn list(&self) -> Vec<&String> {
+        self.attributes.keys().collect()
+    }
+
+    /// Returns the number of attributes
+    pub fn len(&self) -> usize {
+        self.attributes.len()
+    }
+
+    /// Returns true if no attribute is set
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use maidsafe_utilities::serialisation::{serialise, deserialise};
+
+    #[test]
+    fn get_set_remove_list() {
+        let mut xattrs = ExtendedAttributes::new();
+        assert!(xattrs.is_empty());
+        assert!(xattrs.set("user.colour".to_string(), XattrValue::Text("red".to_string()))
+                      .is_none());
+        assert!(xattrs.set("user.icon".to_string(), XattrValue::Binary(vec![1, 2, 3]))
+                      .is_none());
+        assert_eq!(xattrs.len(), 2);
+        assert_eq!(xattrs.list(), vec!["user.colour", "user.icon"]);
+        assert_eq!(unwrap_option!(xattrs.get("user.colour"), "Attribute not found").as_text(),
+                   Some(&"red".to_string()));
+        assert_eq!(unwrap_option!(xattrs.get("user.icon"), "Attribute not found").as_bytes(),
+                   Some(&[1u8, 2, 3][..]));
+        assert!(xattrs.set("user.rating".to_string(), XattrValue::Integer(-5)).is_none());
+        assert_eq!(unwrap_option!(xattrs.get("user.rating"), "Attribute not found").as_integer(),
+                   Some(-5));
+        assert!(xattrs.remove("user.rating").is_some());
+
+        let previous = xattrs.set("user.colour".to_string(), XattrValue::Text("blue".to_string()));
+        assert_eq!(previous, Some(XattrValue::Text("red".to_string())));
+        assert!(xattrs.remove("user.icon").is_some());
+        assert!(xattrs.get("user.icon").is_none());
+        assert_eq!(xattrs.len(), 1);
+    }
+
+    #[test]
+    fn serialise_and_deserialise() {
+        let mut xattrs = ExtendedAttributes::new();
+        let _ = xattrs.set("user.tag".to_string(), XattrValue::Text("holiday".to_string()));
+        let _ = xattrs.set("user.icon".to_string(), XattrValue::Binary(vec![1, 2, 3]));
+        let _ = xattrs.set("user.rating".to_string(), XattrValue::Integer(-5));
+        let serialised_data = unwrap_result!(serialise(&xattrs));
+        let deserialised: ExtendedAttributes = unwrap_result!(deserialise(&serialised_data));
+        assert_eq!(deserialised, xattrs);
+    }
+}
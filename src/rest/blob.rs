@@ -16,6 +16,7 @@
 // relating to use of the SAFE Network Software.
 
 use file::File;
+use metadata::xattr::ExtendedAttributes;
 use time::Tm;
 
 #[allow(dead_code)]
@@ -38,6 +39,21 @@ impl Blob {
         }
     }
 
+    /// Get the extended attributes of the Blob
+    pub fn get_xattrs(&self) -> &ExtendedAttributes {
+        self.file.get_xattrs()
+    }
+
+    /// Get the MIME type of the Blob content
+    pub fn get_mime_type(&self) -> Option<&String> {
+        self.file.get_metadata().get_mime_type()
+    }
+
+    /// Get the SHA-256 digest of the Blob content
+    pub fn get_content_hash(&self) -> Option<&Vec<u8>> {
+        self.file.get_metadata().get_content_hash()
+    }
+
     /// Get the creation time for Blob
     pub fn get_created_time(&self) -> &Tm {
         self.file.get_metadata().get_created_time()
@@ -22,10 +22,14 @@ use xor_name::XorName;
 
 use errors::NfsError;
 use directory_listing::DirectoryListing;
-use helper::directory_helper::DirectoryHelper;
+use file::File;
+use helper::directory_helper::{DirectoryHelper, ListingCursor};
 use helper::file_helper::FileHelper;
 use helper::reader::Reader;
 use helper::writer::{Mode, Writer};
+use metadata::retention_policy::RetentionPolicy;
+use metadata::version_record::VersionRecord;
+use metadata::xattr::ExtendedAttributes;
 
 /// Container Repersents a Directory.
 /// Container can have its own metadata, sub-containers and files
@@ -84,7 +88,8 @@ impl Container {
                   metadata: Option<String>)
                   -> Result<(::rest::Container, Option<::rest::Container>), NfsError> {
         if name.is_empty() {
-            return Err(NfsError::ParameterIsNotValid);
+            return Err(NfsError::ParameterIsNotValid("Container name can not be empty"
+                                                         .to_string()));
         }
         let user_metadata = try!(self.validate_metadata(metadata));
         let tag_type = if versioned {
@@ -137,6 +142,11 @@ impl Container {
         }
     }
 
+    /// Returns the extended attributes of the container
+    pub fn get_xattrs(&self) -> &ExtendedAttributes {
+        self.directory_listing.get_xattrs()
+    }
+
     /// Returns the name of the container
     pub fn get_name(&self) -> &String {
         self.directory_listing.get_metadata().get_name()
@@ -147,11 +157,41 @@ impl Container {
         self.directory_listing.get_files().iter().map(|x| ::rest::Blob::from(x.clone())).collect()
     }
 
+    /// Returns up to `limit` Blobs of the container, skipping the first `offset` Blobs
+    pub fn list(&self, offset: usize, limit: usize) -> Vec<::rest::Blob> {
+        self.directory_listing
+            .get_files()
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(|x| ::rest::Blob::from(x.clone()))
+            .collect()
+    }
+
+    /// Fetches up to `limit` Blobs of the container from the network, starting at `cursor` or at
+    /// the first Blob if `None` is passed. Only the shards holding the page are read for
+    /// containers created with `::SHARDED_DIRECTORY_LISTING_TAG`.
+    /// Returns (blobs, Option<cursor of the next page>)
+    pub fn list_from_cursor(&self,
+                            cursor: Option<&ListingCursor>,
+                            limit: usize)
+                            -> Result<(Vec<::rest::Blob>, Option<ListingCursor>), NfsError> {
+        let directory_helper = DirectoryHelper::new(self.client.clone());
+        let (files, next_cursor) = try!(directory_helper.list_files(self.directory_listing
+                                                                        .get_key(),
+                                                                    cursor,
+                                                                    limit));
+        Ok((files.into_iter().map(::rest::Blob::from).collect(), next_cursor))
+    }
+
     /// Returns a Blob from the container
     pub fn get_blob(&self, name: String) -> Result<::rest::blob::Blob, NfsError> {
         match self.directory_listing.find_file(&name) {
-            Some(file) => Ok(::rest::blob::Blob::from(file.clone())),
-            None => Err(NfsError::FileNotFound),
+            Some(file) => {
+                let file_helper = FileHelper::new(self.client.clone());
+                Ok(::rest::blob::Blob::from(try!(file_helper.resolve_link(file))))
+            }
+            None => Err(NfsError::FileNotFound(name.clone())),
         }
     }
 
@@ -164,6 +204,20 @@ impl Container {
             .collect()
     }
 
+    /// Returns the Blobs of the container matching the query, sorted and paged as requested
+    pub fn query_blobs(&self,
+                       query: &::rest::ListingQuery)
+                       -> Result<Vec<::rest::Blob>, NfsError> {
+        query.apply(self.get_blobs())
+    }
+
+    /// Returns the child containers matching the query, sorted and paged as requested
+    pub fn query_containers(&self,
+                            query: &::rest::ListingQuery)
+                            -> Result<Vec<::rest::ContainerInfo>, NfsError> {
+        query.apply(self.get_containers())
+    }
+
     /// Updates the metadata of the container
     pub fn update_metadata(&mut self,
                            metadata: Option<String>)
@@ -180,12 +234,64 @@ impl Container {
         }))
     }
 
+    /// Replaces the extended attributes of the container
+    pub fn update_xattrs(&mut self,
+                         xattrs: ExtendedAttributes)
+                         -> Result<Option<::rest::container::Container>, NfsError> {
+        *self.directory_listing.get_mut_xattrs() = xattrs;
+        let directory_helper = DirectoryHelper::new(self.client.clone());
+        let parent_directory = try!(directory_helper.update(&self.directory_listing));
+        Ok(parent_directory.iter().next().map(|parent_directory| {
+            Container {
+                client: self.client.clone(),
+                directory_listing: parent_directory.clone(),
+            }
+        }))
+    }
+
     /// Retrieves Versions for the container
     pub fn get_versions(&self) -> Result<Vec<[u8; 64]>, NfsError> {
         self.list_container_versions(self.directory_listing.get_key().get_id(),
                                      self.directory_listing.get_key().get_type_tag())
     }
 
+    /// Retrieves the timestamp, signer and message of each version of the container, in the
+    /// order of `get_versions`
+    pub fn get_version_log(&self) -> Result<Vec<VersionRecord>, NfsError> {
+        let directory_helper = DirectoryHelper::new(self.client.clone());
+        directory_helper.get_version_log(self.directory_listing.get_key())
+    }
+
+    /// Returns the retention policy applied when the versions of the container are pruned
+    pub fn get_retention_policy(&self) -> Option<&RetentionPolicy> {
+        self.directory_listing.get_metadata().get_retention_policy()
+    }
+
+    /// Replaces the retention policy of the container
+    pub fn update_retention_policy(&mut self,
+                                   retention_policy: Option<RetentionPolicy>)
+                                   -> Result<Option<::rest::container::Container>, NfsError> {
+        self.directory_listing.get_mut_metadata().set_retention_policy(retention_policy);
+        let directory_helper = DirectoryHelper::new(self.client.clone());
+        let parent_directory = try!(directory_helper.update(&self.directory_listing));
+        Ok(parent_directory.iter().next().map(|parent_directory| {
+            Container {
+                client: self.client.clone(),
+                directory_listing: parent_directory.clone(),
+            }
+        }))
+    }
+
+    /// Drops the versions of the container which are not kept by its retention policy. If
+    /// `dry_run` is true, the versions which would be dropped are only listed.
+    /// Returns the dropped versions
+    pub fn prune_versions(&self, dry_run: bool) -> Result<Vec<[u8; 64]>, NfsError> {
+        let directory_helper = DirectoryHelper::new(self.client.clone());
+        let versions = try!(directory_helper.prune_versions(self.directory_listing.get_key(),
+                                                            dry_run));
+        Ok(versions.get_versions().iter().map(|v| v.0).collect())
+    }
+
     /// Retrieves Versions for the container being referred by the container_id
     pub fn get_container_versions(&self,
                                   container_info: &::rest::container_info::ContainerInfo)
@@ -221,6 +327,29 @@ impl Container {
         })
     }
 
+    /// Fetches the latest version of a nested container by its slash separated path relative to
+    /// this container, e.g. `Pictures/2016`
+    pub fn get_container_by_path(&self, path: &str) -> Result<Container, NfsError> {
+        let directory_helper = DirectoryHelper::new(self.client.clone());
+        let dir_listing = try!(directory_helper.get_by_path(path,
+                                                            Some(self.directory_listing
+                                                                     .get_key())));
+        Ok(Container {
+            client: self.client.clone(),
+            directory_listing: dir_listing,
+        })
+    }
+
+    /// Returns a Blob from a nested container by its slash separated path relative to this
+    /// container, e.g. `Pictures/2016/a.jpg`
+    pub fn get_blob_by_path(&self, path: &str) -> Result<::rest::blob::Blob, NfsError> {
+        let directory_helper = DirectoryHelper::new(self.client.clone());
+        let (file, _) = try!(directory_helper.get_file_by_path(path,
+                                                               Some(self.directory_listing
+                                                                        .get_key())));
+        Ok(::rest::blob::Blob::from(file))
+    }
+
     /// Deletes the child container
     pub fn delete_container(&mut self,
                             name: &String)
@@ -244,7 +373,7 @@ impl Container {
                        metadata: Option<String>)
                        -> Result<Writer, NfsError> {
         if name.is_empty() {
-            return Err(NfsError::ParameterIsNotValid);
+            return Err(NfsError::ParameterIsNotValid("Blob name can not be empty".to_string()));
         }
         let user_metadata = try!(self.validate_metadata(metadata));
         let file_helper = FileHelper::new(self.client.clone());
@@ -271,8 +400,12 @@ impl Container {
 
     /// Return a writter object for the Blob, through which the content of the blob can be updated
     /// This is useful while handling larger files, to enable writting content in parts
-    pub fn get_blob_writer(&mut self, blob: &::rest::Blob) -> Result<Writer, NfsError> {
-        self.get_writer_for_blob(blob, Mode::Modify)
+    /// `Mode::Append` can be used to add content to the end of the blob
+    pub fn get_blob_writer(&mut self,
+                           blob: &::rest::Blob,
+                           mode: Mode)
+                           -> Result<Writer, NfsError> {
+        self.get_writer_for_blob(blob, mode)
     }
 
     /// Reads the content of the blob and returns the complete content
@@ -293,12 +426,40 @@ impl Container {
 
     /// Returns the list of versions_id for the blob
     pub fn get_blob_versions(&self, name: &String) -> Result<Vec<::rest::blob::Blob>, NfsError> {
-        let file = try!(self.directory_listing.find_file(name).ok_or(NfsError::FileNotFound));
+        let file = try!(self.directory_listing
+                            .find_file(name)
+                            .ok_or(NfsError::FileNotFound(name.clone())));
         let file_helper = FileHelper::new(self.client.clone());
         let versions = try!(file_helper.get_versions(&file, &self.directory_listing));
         Ok(versions.iter().map(|file| ::rest::blob::Blob::from(file.clone())).collect())
     }
 
+    /// Makes a version of the blob, as returned by `get_blob_versions`, its latest content and
+    /// metadata again. The version is appended to the history of the blob, which is never
+    /// rewritten.
+    /// Returns Updated parent container, if the parent container exists.
+    pub fn restore_blob_version(&mut self,
+                                name: &String,
+                                version: &::rest::blob::Blob)
+                                -> Result<Option<Container>, NfsError> {
+        let file = try!(self.directory_listing
+                            .find_file(name)
+                            .map(|file| file.clone())
+                            .ok_or(NfsError::FileNotFound(name.clone())));
+        let file_helper = FileHelper::new(self.client.clone());
+        if let Some(parent_directory_listing) =
+               try!(file_helper.restore_version(&file,
+                                                version.into_file(),
+                                                &mut self.directory_listing)) {
+            Ok(Some(Container {
+                client: self.client.clone(),
+                directory_listing: parent_directory_listing,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
     /// Update the metadata of the Blob in the container
     /// Returns Updated parent container, if the parent container exists.
     pub fn update_blob_metadata(&mut self,
@@ -320,6 +481,25 @@ impl Container {
         }
     }
 
+    /// Replaces the extended attributes of the blob
+    pub fn update_blob_xattrs(&mut self,
+                              mut blob: ::rest::blob::Blob,
+                              xattrs: ExtendedAttributes)
+                              -> Result<Option<Container>, NfsError> {
+        let file_helper = FileHelper::new(self.client.clone());
+        let file = blob.into_mut_file();
+        *file.get_mut_xattrs() = xattrs;
+        if let Some(parent_directory_listing) =
+               try!(file_helper.update_metadata(file.clone(), &mut self.directory_listing)) {
+            Ok(Some(Container {
+                client: self.client.clone(),
+                directory_listing: parent_directory_listing,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
     /// Delete blob from the container
     pub fn delete_blob(&mut self, name: String) -> Result<(), NfsError> {
         let file_helper = FileHelper::new(self.client.clone());
@@ -328,6 +508,7 @@ impl Container {
     }
 
     /// Copies the latest blob version from the container to the specified destination container
+    /// The copy shares the content with the blob but is updated independently of it
     pub fn copy_blob(&mut self,
                      blob_name: &String,
                      to_container: &::rest::container_info::ContainerInfo)
@@ -336,18 +517,95 @@ impl Container {
         if self.directory_listing.get_key() == to_dir.get_key() {
             return Err(NfsError::DestinationAndSourceAreSame);
         }
-        let file = try!(self.directory_listing.find_file(blob_name).ok_or(NfsError::FileNotFound));
+        let file = try!(self.directory_listing
+                            .find_file(blob_name)
+                            .ok_or(NfsError::FileNotFound(blob_name.clone())));
         let directory_helper = DirectoryHelper::new(self.client.clone());
         let mut destination = try!(directory_helper.get(to_dir.get_key()));
         if destination.find_file(blob_name).is_some() {
             return Err(NfsError::FileAlreadyExistsWithSameName);
         }
+        let file_helper = FileHelper::new(self.client.clone());
+        let file = try!(file_helper.resolve_link(file));
+        let mut metadata = file.get_metadata().clone();
+        metadata.set_link_id(None);
         debug!("Adding {:?} blob to destination files ...", blob_name);
-        destination.get_mut_files().push(file.clone());
+        destination.get_mut_files().push(try!(File::new(metadata, file.get_datamap().clone())));
         let _ = try!(directory_helper.update(&destination));
         Ok(())
     }
 
+    /// Links the blob into the specified destination container under the same name. Content
+    /// written to the blob through either container is seen through both, and the content is
+    /// only released when the blob is deleted from all containers linking to it.
+    pub fn link_blob(&mut self,
+                     blob_name: &String,
+                     to_container: &::rest::container_info::ContainerInfo)
+                     -> Result<(), NfsError> {
+        let to_dir = to_container.into_directory_metadata();
+        let directory_helper = DirectoryHelper::new(self.client.clone());
+        let mut destination = try!(directory_helper.get(to_dir.get_key()));
+        let file_helper = FileHelper::new(self.client.clone());
+        file_helper.link(blob_name,
+                         blob_name.clone(),
+                         &mut self.directory_listing,
+                         Some(&mut destination))
+    }
+
+    /// Returns the number of container entries linking to the content of the blob
+    pub fn get_blob_link_count(&self, blob: &::rest::Blob) -> Result<u64, NfsError> {
+        let file_helper = FileHelper::new(self.client.clone());
+        file_helper.get_link_count(blob.into_file())
+    }
+
+    /// Moves the blob from the container to the specified destination container
+    pub fn move_blob(&mut self,
+                     blob_name: &String,
+                     to_container: &::rest::container_info::ContainerInfo)
+                     -> Result<(), NfsError> {
+        let to_dir = to_container.into_directory_metadata();
+        let directory_helper = DirectoryHelper::new(self.client.clone());
+        let mut destination = try!(directory_helper.get(to_dir.get_key()));
+        let file_helper = FileHelper::new(self.client.clone());
+        file_helper.move_file(blob_name,
+                              blob_name.clone(),
+                              &mut self.directory_listing,
+                              Some(&mut destination))
+    }
+
+    /// Renames a blob in the container
+    pub fn rename_blob(&mut self, blob_name: &String, new_name: String) -> Result<(), NfsError> {
+        if new_name.is_empty() {
+            return Err(NfsError::ParameterIsNotValid("Blob name can not be empty".to_string()));
+        }
+        let file_helper = FileHelper::new(self.client.clone());
+        file_helper.move_file(blob_name, new_name, &mut self.directory_listing, None)
+    }
+
+    /// Moves the child container to the specified destination container
+    pub fn move_container(&mut self,
+                          name: &String,
+                          to_container: &::rest::container_info::ContainerInfo)
+                          -> Result<(), NfsError> {
+        let to_dir = to_container.into_directory_metadata();
+        let directory_helper = DirectoryHelper::new(self.client.clone());
+        let mut destination = try!(directory_helper.get(to_dir.get_key()));
+        directory_helper.move_directory(name,
+                                        name.clone(),
+                                        &mut self.directory_listing,
+                                        Some(&mut destination))
+    }
+
+    /// Renames the child container
+    pub fn rename_container(&mut self, name: &String, new_name: String) -> Result<(), NfsError> {
+        if new_name.is_empty() {
+            return Err(NfsError::ParameterIsNotValid("Container name can not be empty"
+                                                         .to_string()));
+        }
+        let directory_helper = DirectoryHelper::new(self.client.clone());
+        directory_helper.move_directory(name, new_name, &mut self.directory_listing, None)
+    }
+
     fn get_writer_for_blob(&self,
                            blob: &::rest::blob::Blob,
                            mode: Mode)
@@ -363,7 +621,7 @@ impl Container {
                                -> Result<Reader<'a>, NfsError> {
         match self.directory_listing.find_file(blob.get_name()) {
             Some(_) => Ok(Reader::new(self.client.clone(), blob.into_file())),
-            None => Err(NfsError::FileNotFound),
+            None => Err(NfsError::FileNotFound(blob.get_name().clone())),
         }
     }
 
@@ -380,7 +638,7 @@ impl Container {
         match metadata {
             Some(data) => {
                 if data.len() == 0 {
-                    Err(NfsError::ParameterIsNotValid)
+                    Err(NfsError::ParameterIsNotValid("Metadata can not be empty".to_string()))
                 } else {
                     Ok(data.into_bytes())
                 }
@@ -394,6 +652,10 @@ impl Container {
 mod test {
     use super::*;
     use std::sync::{Arc, Mutex};
+    use errors::NfsError;
+    use helper::writer::Mode;
+    use metadata::xattr::{ExtendedAttributes, XattrValue};
+    use rest::{ListingQuery, SortBy, SortOrder};
     use safe_core::client::Client;
     use safe_core::utility::test_utils;
 
@@ -448,6 +710,165 @@ mod test {
         assert_eq!(container.get_containers().len(), 0);
     }
 
+    #[test]
+    fn get_container_and_blob_by_path() {
+        let client = get_client();
+        let mut container = unwrap_result!(Container::authorise(client.clone(), None));
+        let (mut home_container, _) = unwrap_result!(container.create("Home".to_string(),
+                                                                      true,
+                                                                      ::AccessLevel::Private,
+                                                                      None));
+        let (mut pictures_container, _) =
+            unwrap_result!(home_container.create("Pictures".to_string(),
+                                                 false,
+                                                 ::AccessLevel::Private,
+                                                 None));
+        let mut writer = unwrap_result!(pictures_container.create_blob("a.jpg".to_string(),
+                                                                       None));
+        writer.write(&[1u8; 10], 0);
+        let _ = unwrap_result!(writer.close());
+
+        let resolved = unwrap_result!(container.get_container_by_path("Home/Pictures"));
+        assert_eq!(*resolved.get_name(), "Pictures".to_string());
+        assert_eq!(resolved.get_blobs().len(), 1);
+
+        // Blobs are read through the container holding them
+        let blob = unwrap_result!(container.get_blob_by_path("/Home/Pictures/a.jpg"));
+        assert_eq!(unwrap_result!(resolved.get_blob_content(&blob)), vec![1u8; 10]);
+
+        match container.get_blob_by_path("Home/Pictures/b.jpg") {
+            Err(NfsError::FileNotFound(path)) => assert_eq!(path, "/Home/Pictures/b.jpg"),
+            _ => panic!("Expected FileNotFound"),
+        }
+    }
+
+    #[test]
+    fn move_and_rename_container() {
+        let client = get_client();
+        let mut container = unwrap_result!(Container::authorise(client, None));
+        let (mut home_container, _) = unwrap_result!(container.create("Home".to_string(),
+                                                                      true,
+                                                                      ::AccessLevel::Private,
+                                                                      None));
+        let (docs_container, _) = unwrap_result!(container.create("Docs".to_string(),
+                                                                  true,
+                                                                  ::AccessLevel::Private,
+                                                                  None));
+        let _ = unwrap_result!(home_container.create("Pictures".to_string(),
+                                                     true,
+                                                     ::AccessLevel::Private,
+                                                     None));
+        unwrap_result!(home_container.rename_container(&"Pictures".to_string(),
+                                                       "Photos".to_string()));
+        assert_eq!(home_container.get_containers()[0].get_name(), "Photos");
+
+        unwrap_result!(home_container.move_container(&"Photos".to_string(),
+                                                     &docs_container.get_info()));
+        assert_eq!(home_container.get_containers().len(), 0);
+        assert!(container.get_container_by_path("Docs/Photos").is_ok());
+        assert!(container.get_container_by_path("Home/Photos").is_err());
+    }
+
+    #[test]
+    fn list_blobs_by_page() {
+        let client = get_client();
+        let mut container = unwrap_result!(Container::authorise(client, None));
+        for i in 0..5 {
+            let writer = unwrap_result!(container.create_blob(format!("{}.txt", i), None));
+            let (directory, _) = unwrap_result!(writer.close());
+            container.directory_listing = directory;
+        }
+
+        assert_eq!(container.list(0, 2).len(), 2);
+        assert_eq!(container.list(4, 2).len(), 1);
+        assert!(container.list(5, 2).is_empty());
+
+        let (blobs, cursor) = unwrap_result!(container.list_from_cursor(None, 3));
+        assert_eq!(blobs.len(), 3);
+        let (blobs, cursor) = unwrap_result!(container.list_from_cursor(cursor.as_ref(), 3));
+        assert_eq!(blobs.len(), 2);
+        assert!(cursor.is_none());
+    }
+
+    #[test]
+    fn query_blobs_and_containers() {
+        let client = get_client();
+        let mut container = unwrap_result!(Container::authorise(client, None));
+        for name in vec!["b.txt", "a.jpg", "c.jpg"] {
+            let writer = unwrap_result!(container.create_blob(name.to_string(), None));
+            let (directory, _) = unwrap_result!(writer.close());
+            container.directory_listing = directory;
+        }
+        let _ = unwrap_result!(container.create("Photos".to_string(),
+                                                false,
+                                                ::AccessLevel::Private,
+                                                None));
+
+        let mut query = ListingQuery::new();
+        query.set_name_pattern(Some("*.jpg".to_string()));
+        query.set_sort(SortBy::Name, SortOrder::Descending);
+        let blobs = unwrap_result!(container.query_blobs(&query));
+        assert_eq!(blobs.len(), 2);
+        assert_eq!(*blobs[0].get_name(), "c.jpg".to_string());
+        assert_eq!(*blobs[1].get_name(), "a.jpg".to_string());
+        assert!(unwrap_result!(container.query_containers(&query)).is_empty());
+
+        let mut query = ListingQuery::new();
+        query.set_name_prefix(Some("Pho".to_string()));
+        assert_eq!(unwrap_result!(container.query_containers(&query)).len(), 1);
+    }
+
+    #[test]
+    fn update_xattrs() {
+        let client = get_client();
+        let mut container = unwrap_result!(Container::authorise(client, None));
+        let (mut home_container, _) = unwrap_result!(container.create("Home".to_string(),
+                                                                      false,
+                                                                      ::AccessLevel::Private,
+                                                                      None));
+        let writer = unwrap_result!(home_container.create_blob("a.txt".to_string(),
+                                                               Some("notes".to_string())));
+        let (directory, _) = unwrap_result!(writer.close());
+        home_container.directory_listing = directory;
+
+        let mut xattrs = ExtendedAttributes::new();
+        let _ = xattrs.set("user.author".to_string(), XattrValue::Text("alice".to_string()));
+        let _ = xattrs.set("user.thumbnail".to_string(), XattrValue::Binary(vec![9u8; 16]));
+        let blob = unwrap_result!(home_container.get_blob("a.txt".to_string()));
+        let _ = unwrap_result!(home_container.update_blob_xattrs(blob, xattrs.clone()));
+        let _ = unwrap_result!(home_container.update_xattrs(xattrs.clone()));
+
+        let home_container = unwrap_result!(container.get_container(&home_container.get_info(),
+                                                                    None));
+        assert_eq!(*home_container.get_xattrs(), xattrs);
+        let blob = unwrap_result!(home_container.get_blob("a.txt".to_string()));
+        assert_eq!(*blob.get_xattrs(), xattrs);
+        // The plain user metadata is left untouched
+        assert_eq!(blob.get_metadata(), "notes".to_string());
+    }
+
+    #[test]
+    fn append_to_blob() {
+        let client = get_client();
+        let mut container = unwrap_result!(Container::authorise(client, None));
+        let mut writer = unwrap_result!(container.create_blob("log.txt".to_string(), None));
+        writer.write(&[1u8; 10], 0);
+        let (directory, _) = unwrap_result!(writer.close());
+        container.directory_listing = directory;
+
+        let blob = unwrap_result!(container.get_blob("log.txt".to_string()));
+        let mut writer = unwrap_result!(container.get_blob_writer(&blob, Mode::Append));
+        writer.write(&[2u8; 10], 0);
+        let (directory, _) = unwrap_result!(writer.close());
+        container.directory_listing = directory;
+
+        let blob = unwrap_result!(container.get_blob("log.txt".to_string()));
+        let content = unwrap_result!(container.get_blob_content(&blob));
+        assert_eq!(content.len(), 20);
+        assert_eq!(&content[0..10], &[1u8; 10]);
+        assert_eq!(&content[10..20], &[2u8; 10]);
+    }
+
     #[test]
     fn create_update_delete_blob() {
         let client = get_client();
@@ -506,6 +927,20 @@ mod test {
         docs_container = unwrap_result!(container.get_container(&docs_container.get_info(), None));
         assert_eq!(docs_container.get_blobs().len(), 1);
 
+        unwrap_result!(home_container.rename_blob(&"sample.txt".to_string(),
+                                                  "renamed.txt".to_string()));
+        assert!(home_container.get_blob("sample.txt".to_string()).is_err());
+        assert!(home_container.move_blob(&"renamed.txt".to_string(), &docs_container.get_info())
+                              .is_ok());
+        assert_eq!(home_container.get_blobs().len(), 0);
+        docs_container = unwrap_result!(container.get_container(&docs_container.get_info(), None));
+        assert_eq!(docs_container.get_blobs().len(), 2);
+        unwrap_result!(docs_container.move_blob(&"renamed.txt".to_string(),
+                                                &home_container.get_info()));
+        home_container = unwrap_result!(container.get_container(&home_container.get_info(), None));
+        unwrap_result!(home_container.rename_blob(&"renamed.txt".to_string(),
+                                                  "sample.txt".to_string()));
+
         let _ = home_container.delete_blob("sample.txt".to_string());
         assert_eq!(home_container.get_blobs().len(), 0);
 
@@ -0,0 +1,350 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::cmp::Ordering;
+
+use errors::NfsError;
+use rest::{Blob, ContainerInfo};
+use time::Tm;
+
+/// Field by which the entries of a Container are sorted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    /// Sort by name
+    Name,
+    /// Sort by size. Containers have no size and are sorted by name instead.
+    Size,
+    /// Sort by the time of creation
+    CreatedTime,
+    /// Sort by the time of last modification
+    ModifiedTime,
+}
+
+/// Direction in which the entries of a Container are sorted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Smallest first
+    Ascending,
+    /// Largest first
+    Descending,
+}
+
+/// An entry of a Container which a ListingQuery can be applied to
+pub trait ListingEntry {
+    /// Name of the entry
+    fn get_name(&self) -> &String;
+    /// Size of the entry in bytes, if it has one
+    fn get_size(&self) -> Option<u64>;
+    /// Time of creation of the entry
+    fn get_created_time(&self) -> &Tm;
+    /// Time of last modification of the entry
+    fn get_modified_time(&self) -> &Tm;
+}
+
+impl ListingEntry for Blob {
+    fn get_name(&self) -> &String {
+        Blob::get_name(self)
+    }
+
+    fn get_size(&self) -> Option<u64> {
+        Some(Blob::get_size(self))
+    }
+
+    fn get_created_time(&self) -> &Tm {
+        Blob::get_created_time(self)
+    }
+
+    fn get_modified_time(&self) -> &Tm {
+        Blob::get_modified_time(self)
+    }
+}
+
+impl ListingEntry for ContainerInfo {
+    fn get_name(&self) -> &String {
+        ContainerInfo::get_name(self)
+    }
+
+    fn get_size(&self) -> Option<u64> {
+        None
+    }
+
+    fn get_created_time(&self) -> &Tm {
+        ContainerInfo::get_created_time(self)
+    }
+
+    fn get_modified_time(&self) -> &Tm {
+        ContainerInfo::get_modified_time(self)
+    }
+}
+
+/// Filters, sorting and paging applied to the listing of a Container by `Container::query_blobs`
+/// and `Container::query_containers`.
+/// A new query sorts by name in ascending order and returns every entry.
+#[derive(Debug, Clone)]
+pub struct ListingQuery {
+    sort_by: SortBy,
+    sort_order: SortOrder,
+    name_prefix: Option<String>,
+    name_pattern: Option<String>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    modified_since: Option<Tm>,
+    offset: usize,
+    limit: Option<usize>,
+}
+
+impl ListingQuery {
+    /// Create a new ListingQuery
+    pub fn new() -> ListingQuery {
+        ListingQuery {
+            sort_by: SortBy::Name,
+            sort_order: SortOrder::Ascending,
+            name_prefix: None,
+            name_pattern: None,
+            min_size: None,
+            max_size: None,
+            modified_since: None,
+            offset: 0,
+            limit: None,
+        }
+    }
+
+    /// Set the field and direction of the sort
+    pub fn set_sort(&mut self, sort_by: SortBy, sort_order: SortOrder) {
+        self.sort_by = sort_by;
+        self.sort_order = sort_order;
+    }
+
+    /// Only return entries whose name starts with the prefix
+    pub fn set_name_prefix(&mut self, name_prefix: Option<String>) {
+        self.name_prefix = name_prefix;
+    }
+
+    /// Only return entries whose name matches the glob pattern, where `*` matches any sequence
+    /// of characters and `?` matches a single character
+    pub fn set_name_pattern(&mut self, name_pattern: Option<String>) {
+        self.name_pattern = name_pattern;
+    }
+
+    /// Only return entries whose size in bytes lies within the inclusive range.
+    /// Entries without a size, i.e. Containers, never match a size range.
+    pub fn set_size_range(&mut self, min_size: Option<u64>, max_size: Option<u64>) {
+        self.min_size = min_size;
+        self.max_size = max_size;
+    }
+
+    /// Only return entries modified at or after the time
+    pub fn set_modified_since(&mut self, modified_since: Option<Tm>) {
+        self.modified_since = modified_since;
+    }
+
+    /// Skip the first `offset` entries after filtering and sorting
+    pub fn set_offset(&mut self, offset: usize) {
+        self.offset = offset;
+    }
+
+    /// Return at most `limit` entries
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    /// Filters, sorts and pages the entries
+    pub fn apply<T: ListingEntry>(&self, entries: Vec<T>) -> Result<Vec<T>, NfsError> {
+        if let (Some(min_size), Some(max_size)) = (self.min_size, self.max_size) {
+            if min_size > max_size {
+                return Err(NfsError::ParameterIsNotValid("Minimum size is larger than the \
+                                                          maximum size"
+                                                             .to_string()));
+            }
+        }
+        let name_pattern: Option<Vec<char>> = self.name_pattern
+                                                  .as_ref()
+                                                  .map(|pattern| pattern.chars().collect());
+        let mut matching: Vec<T> = entries.into_iter()
+                                          .filter(|entry| {
+                                              self.matches(entry, name_pattern.as_ref())
+                                          })
+                                          .collect();
+        matching.sort_by(|lhs, rhs| {
+            let ordering = self.compare(lhs, rhs);
+            match self.sort_order {
+                SortOrder::Ascending => ordering,
+                SortOrder::Descending => ordering.reverse(),
+            }
+        });
+        let limit = self.limit.unwrap_or(matching.len());
+        Ok(matching.into_iter().skip(self.offset).take(limit).collect())
+    }
+
+    fn matches<T: ListingEntry>(&self, entry: &T, name_pattern: Option<&Vec<char>>) -> bool {
+        if let Some(ref name_prefix) = self.name_prefix {
+            if !entry.get_name().starts_with(&name_prefix[..]) {
+                return false;
+            }
+        }
+        if let Some(name_pattern) = name_pattern {
+            let name: Vec<char> = entry.get_name().chars().collect();
+            if !matches_pattern(name_pattern, &name) {
+                return false;
+            }
+        }
+        if self.min_size.is_some() || self.max_size.is_some() {
+            match entry.get_size() {
+                Some(size) => {
+                    if self.min_size.map_or(false, |min_size| size < min_size) ||
+                       self.max_size.map_or(false, |max_size| size > max_size) {
+                        return false;
+                    }
+                }
+                None => return false,
+            }
+        }
+        if let Some(ref modified_since) = self.modified_since {
+            if entry.get_modified_time() < modified_since {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Compares by the sort field, falling back to the name for ties
+    fn compare<T: ListingEntry>(&self, lhs: &T, rhs: &T) -> Ordering {
+        let ordering = match self.sort_by {
+            SortBy::Name => Ordering::Equal,
+            SortBy::Size => lhs.get_size().cmp(&rhs.get_size()),
+            SortBy::CreatedTime => lhs.get_created_time().cmp(rhs.get_created_time()),
+            SortBy::ModifiedTime => lhs.get_modified_time().cmp(rhs.get_modified_time()),
+        };
+        if ordering == Ordering::Equal {
+            lhs.get_name().cmp(rhs.get_name())
+        } else {
+            ordering
+        }
+    }
+}
+
+impl Default for ListingQuery {
+    fn default() -> ListingQuery {
+        ListingQuery::new()
+    }
+}
+
+/// Glob matching supporting `*` and `?`, backtracking to the last `*` on a mismatch
+fn matches_pattern(pattern: &[char], name: &[char]) -> bool {
+    let mut pattern_index = 0;
+    let mut name_index = 0;
+    let mut last_star: Option<(usize, usize)> = None;
+    while name_index < name.len() {
+        if pattern_index < pattern.len() &&
+           (pattern[pattern_index] == '?' || pattern[pattern_index] == name[name_index]) {
+            pattern_index += 1;
+            name_index += 1;
+        } else if pattern_index < pattern.len() && pattern[pattern_index] == '*' {
+            last_star = Some((pattern_index, name_index));
+            pattern_index += 1;
+        } else if let Some((star_index, star_name_index)) = last_star {
+            pattern_index = star_index + 1;
+            name_index = star_name_index + 1;
+            last_star = Some((star_index, name_index));
+        } else {
+            return false;
+        }
+    }
+    pattern[pattern_index..].iter().all(|character| *character == '*')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::matches_pattern;
+    use file::File;
+    use metadata::file_metadata::FileMetadata;
+    use rest::Blob;
+    use self_encryption::DataMap;
+
+    fn new_blob(name: &str, size: u64, modified_time: ::time::Tm) -> Blob {
+        let mut metadata = FileMetadata::new(name.to_string(), Vec::new());
+        metadata.set_size(size);
+        metadata.set_modified_time(modified_time);
+        Blob::from(unwrap_result!(File::new(metadata, DataMap::None)))
+    }
+
+    fn names(blobs: &[Blob]) -> Vec<String> {
+        blobs.iter().map(|blob| blob.get_name().clone()).collect()
+    }
+
+    #[test]
+    fn glob_patterns() {
+        let matches = |pattern: &str, name: &str| {
+            matches_pattern(&pattern.chars().collect::<Vec<_>>(),
+                            &name.chars().collect::<Vec<_>>())
+        };
+        assert!(matches("*.jpg", "a.jpg"));
+        assert!(matches("*.jpg", ".jpg"));
+        assert!(!matches("*.jpg", "a.jpeg"));
+        assert!(matches("img_??.*", "img_01.png"));
+        assert!(!matches("img_??.*", "img_1.png"));
+        assert!(matches("*a*b*", "xxaxxbxx"));
+        assert!(!matches("*a*b", "xxaxxbxx"));
+        assert!(matches("*", ""));
+        assert!(!matches("?", ""));
+    }
+
+    #[test]
+    fn filter_sort_and_page() {
+        let now = ::time::now_utc();
+        let earlier = now - ::time::Duration::days(1);
+        let blobs = || {
+            vec![new_blob("c.txt", 30, now),
+                 new_blob("a.txt", 10, earlier),
+                 new_blob("b.jpg", 20, now),
+                 new_blob("d.jpg", 40, earlier)]
+        };
+
+        let mut query = ListingQuery::new();
+        assert_eq!(names(&unwrap_result!(query.apply(blobs()))),
+                   vec!["a.txt", "b.jpg", "c.txt", "d.jpg"]);
+
+        query.set_sort(SortBy::Size, SortOrder::Descending);
+        assert_eq!(names(&unwrap_result!(query.apply(blobs()))),
+                   vec!["d.jpg", "c.txt", "b.jpg", "a.txt"]);
+
+        query.set_offset(1);
+        query.set_limit(Some(2));
+        assert_eq!(names(&unwrap_result!(query.apply(blobs()))),
+                   vec!["c.txt", "b.jpg"]);
+
+        let mut query = ListingQuery::new();
+        query.set_name_pattern(Some("*.jpg".to_string()));
+        assert_eq!(names(&unwrap_result!(query.apply(blobs()))),
+                   vec!["b.jpg", "d.jpg"]);
+        query.set_modified_since(Some(now));
+        assert_eq!(names(&unwrap_result!(query.apply(blobs()))), vec!["b.jpg"]);
+
+        let mut query = ListingQuery::new();
+        query.set_name_prefix(Some("c".to_string()));
+        assert_eq!(names(&unwrap_result!(query.apply(blobs()))), vec!["c.txt"]);
+
+        let mut query = ListingQuery::new();
+        query.set_size_range(Some(15), Some(30));
+        query.set_sort(SortBy::ModifiedTime, SortOrder::Ascending);
+        assert_eq!(names(&unwrap_result!(query.apply(blobs()))),
+                   vec!["b.jpg", "c.txt"]);
+        query.set_size_range(Some(30), Some(15));
+        assert!(query.apply(blobs()).is_err());
+    }
+}
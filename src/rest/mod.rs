@@ -18,7 +18,9 @@
 mod container;
 mod blob;
 mod container_info;
+mod listing_query;
 
 pub use self::container::*;
 pub use self::blob::*;
 pub use self::container_info::*;
+pub use self::listing_query::*;
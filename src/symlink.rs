@@ -0,0 +1,121 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use errors::NfsError;
+use file::File;
+use metadata::file_metadata::FileMetadata;
+use self_encryption::DataMap;
+use xor_name::XorName;
+
+/// Symbolic link held in a DirectoryListing alongside its files and sub directories.
+/// The target is a slash separated path. It is resolved relative to the directory holding the
+/// link or, if it starts with `/`, relative to the directory path resolution started from.
+/// On the network a Symlink is stored as an empty File whose metadata carries the target, so
+/// older clients see it as an empty file.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Symlink {
+    file: File,
+}
+
+impl Symlink {
+    /// Create a new instance of Symlink
+    pub fn new(name: String, target: String) -> Result<Symlink, NfsError> {
+        if target.is_empty() {
+            return Err(NfsError::ParameterIsNotValid("Symlink target can not be empty"
+                                                         .to_string()));
+        }
+        let mut metadata = FileMetadata::new(name, Vec::new());
+        metadata.set_symlink_target(Some(target));
+        Ok(Symlink { file: try!(File::new(metadata, DataMap::None)) })
+    }
+
+    /// Returns the Symlink stored as `file`, or `None` if `file` is a regular File
+    pub fn from_file(file: File) -> Option<Symlink> {
+        if file.get_metadata().get_symlink_target().is_some() {
+            Some(Symlink { file: file })
+        } else {
+            None
+        }
+    }
+
+    /// Returns unique id
+    pub fn get_id(&self) -> &XorName {
+        self.file.get_id()
+    }
+
+    /// Get the name of the Symlink
+    pub fn get_name(&self) -> &String {
+        self.file.get_name()
+    }
+
+    /// Get the target path of the Symlink
+    pub fn get_target(&self) -> &str {
+        self.file.get_metadata().get_symlink_target().map_or("", |target| &target[..])
+    }
+
+    /// Get metadata associated with the Symlink
+    pub fn get_metadata(&self) -> &FileMetadata {
+        self.file.get_metadata()
+    }
+
+    /// Returns the File the Symlink is stored as
+    pub fn as_file(&self) -> &File {
+        &self.file
+    }
+
+    /// Set the name of the Symlink
+    pub fn set_name(&mut self, name: String) {
+        self.file.get_mut_metadata().set_name(name);
+    }
+
+    /// Set the target path of the Symlink
+    pub fn set_target(&mut self, target: String) -> Result<(), NfsError> {
+        if target.is_empty() {
+            return Err(NfsError::ParameterIsNotValid("Symlink target can not be empty"
+                                                         .to_string()));
+        }
+        let metadata = self.file.get_mut_metadata();
+        metadata.set_symlink_target(Some(target));
+        metadata.set_modified_time(::time::now_utc());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use file::File;
+    use maidsafe_utilities::serialisation::{serialise, deserialise};
+    use metadata::file_metadata::FileMetadata;
+    use self_encryption::DataMap;
+
+    #[test]
+    fn stored_as_file() {
+        let symlink = unwrap_result!(Symlink::new("latest".to_string(), "2016/a.jpg".to_string()));
+        assert_eq!(symlink.get_target(), "2016/a.jpg");
+        assert!(Symlink::new("latest".to_string(), "".to_string()).is_err());
+
+        let serialised_data = unwrap_result!(serialise(symlink.as_file()));
+        let file: File = unwrap_result!(deserialise(&serialised_data));
+        let symlink_after = unwrap_option!(Symlink::from_file(file), "Expected a Symlink");
+        assert_eq!(symlink_after, symlink);
+
+        let file = unwrap_result!(File::new(FileMetadata::new("a.jpg".to_string(), Vec::new()),
+                                            DataMap::None));
+        assert!(Symlink::from_file(file).is_none());
+    }
+}